@@ -1,12 +1,9 @@
 
-DROP TABLE IF EXISTS market_stats;
-DROP TABLE IF EXISTS funding_rates;
-DROP TABLE IF EXISTS markets;
-DROP TABLE IF EXISTS tokens;
-DROP TABLE IF EXISTS exchanges;
+-- Baseline schema. Forward-only: never DROP a data table here or in later
+-- migrations; every statement must be safe on a database that already has data.
 
 -- ---------- Tables ----------
-CREATE TABLE exchanges (
+CREATE TABLE IF NOT EXISTS exchanges (
     id SERIAL PRIMARY KEY,
     name TEXT NOT NULL UNIQUE,                    -- will be normalized to Capitalized via trigger
     funding_interval_minutes INTEGER,             -- optional; fill later from sync/config
@@ -15,13 +12,13 @@ CREATE TABLE exchanges (
     updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
 );
 
-CREATE TABLE tokens (
+CREATE TABLE IF NOT EXISTS tokens (
     id SERIAL PRIMARY KEY,
     symbol TEXT NOT NULL UNIQUE,
     created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
 );
 
-CREATE TABLE markets (
+CREATE TABLE IF NOT EXISTS markets (
     id SERIAL PRIMARY KEY,
     exchange_id INTEGER NOT NULL REFERENCES exchanges(id) ON DELETE CASCADE,
     token_id INTEGER NOT NULL REFERENCES tokens(id) ON DELETE CASCADE,
@@ -32,7 +29,7 @@ CREATE TABLE markets (
     UNIQUE(exchange_id, market_symbol)
 );
 
-CREATE TABLE funding_rates (
+CREATE TABLE IF NOT EXISTS funding_rates (
     id BIGSERIAL PRIMARY KEY,
     exchange_id INTEGER NOT NULL REFERENCES exchanges(id) ON DELETE CASCADE,
     market_id INTEGER NOT NULL REFERENCES markets(id) ON DELETE CASCADE,
@@ -41,7 +38,7 @@ CREATE TABLE funding_rates (
     UNIQUE(market_id, timestamp)
 );
 
-CREATE TABLE market_stats (
+CREATE TABLE IF NOT EXISTS market_stats (
     id BIGSERIAL PRIMARY KEY,
     market_id INTEGER NOT NULL REFERENCES markets(id) ON DELETE CASCADE,
     open_interest NUMERIC(30,10),
@@ -51,11 +48,11 @@ CREATE TABLE market_stats (
 );
 
 -- ---------- Indexes ----------
-CREATE INDEX idx_markets_on_exchange_id ON markets(exchange_id);
-CREATE INDEX idx_markets_on_token_id ON markets(token_id);
-CREATE INDEX idx_funding_rates_on_market_id_timestamp_desc ON funding_rates(market_id, timestamp DESC);
-CREATE INDEX idx_funding_rates_on_exchange_id_timestamp_desc ON funding_rates(exchange_id, timestamp DESC);
-CREATE INDEX idx_market_stats_market_timestamp ON market_stats(market_id, timestamp DESC);
+CREATE INDEX IF NOT EXISTS idx_markets_on_exchange_id ON markets(exchange_id);
+CREATE INDEX IF NOT EXISTS idx_markets_on_token_id ON markets(token_id);
+CREATE INDEX IF NOT EXISTS idx_funding_rates_on_market_id_timestamp_desc ON funding_rates(market_id, timestamp DESC);
+CREATE INDEX IF NOT EXISTS idx_funding_rates_on_exchange_id_timestamp_desc ON funding_rates(exchange_id, timestamp DESC);
+CREATE INDEX IF NOT EXISTS idx_market_stats_market_timestamp ON market_stats(market_id, timestamp DESC);
 
 
 CREATE OR REPLACE FUNCTION normalize_exchange_name()
@@ -73,12 +70,6 @@ FOR EACH ROW
 EXECUTE FUNCTION normalize_exchange_name();
 
 
--- ---------- Clean drop (views only) ----------
-DROP VIEW IF EXISTS funding_matrix_view;
-DROP VIEW IF EXISTS latest_funding_8h_view;
-DROP VIEW IF EXISTS funding_8h_view;
-DROP VIEW IF EXISTS latest_market_stats_view;
-
 -- ---------- Latest market stats per market (carry exchange_id) ----------
 CREATE OR REPLACE VIEW latest_market_stats_view AS
 WITH ranked AS (
@@ -0,0 +1,46 @@
+-- Native funding interval per market (e.g. Paradex 8h, Extended 1h).
+-- exchanges.funding_interval_minutes stays the collection cadence / fallback.
+ALTER TABLE markets ADD COLUMN IF NOT EXISTS funding_interval_minutes INTEGER;
+
+-- Existing markets get their venue's interval now rather than at the next discovery.
+UPDATE markets m
+SET funding_interval_minutes = CASE lower(e.name) WHEN 'paradex' THEN 480 WHEN 'extended' THEN 60 END
+FROM exchanges e
+WHERE e.id = m.exchange_id
+  AND m.funding_interval_minutes IS NULL
+  AND lower(e.name) IN ('paradex', 'extended');
+
+-- ---------- Frontend-ready matrix (per token, per exchange) ----------
+-- funding_rate_8h is the 8h-bucket average of the *native* rate; the API
+-- converts it using funding_interval_minutes.
+CREATE OR REPLACE VIEW funding_matrix_view AS
+SELECT
+  t.symbol,
+  jsonb_object_agg(
+    e.name,
+    jsonb_build_object(
+      'market_symbol',            m.market_symbol,
+      'funding_rate_8h',          (lfr.rate_8h)::float8,
+      'funding_bucket',           lfr.timestamp,
+      'funding_interval_minutes', COALESCE(m.funding_interval_minutes, e.funding_interval_minutes),
+      'open_interest',            (lms.open_interest)::float8,
+      'volume_24h',               (lms.volume_24h)::float8,
+      'stats_ts',                 lms.timestamp
+    )
+    ORDER BY e.name
+  ) AS per_exchange,
+  MAX(
+    GREATEST(
+      COALESCE(lfr.timestamp, 'epoch'::timestamptz),
+      COALESCE(lms.timestamp, 'epoch'::timestamptz)
+    )
+  ) AS last_update
+FROM markets m
+JOIN tokens    t ON t.id = m.token_id
+JOIN exchanges e ON e.id = m.exchange_id
+LEFT JOIN latest_funding_8h_view lfr
+  ON lfr.exchange_id = e.id AND lfr.market_id = m.id
+LEFT JOIN latest_market_stats_view  lms
+  ON lms.exchange_id = e.id AND lms.market_id = m.id
+WHERE m.is_active = true
+GROUP BY t.symbol;
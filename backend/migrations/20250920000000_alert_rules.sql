@@ -0,0 +1,17 @@
+-- Alert rules evaluated after each ingestion run.
+-- `condition` is the tagged JSON form of alerts::rules::AlertCondition.
+CREATE TABLE IF NOT EXISTS alert_rules (
+  id                  SERIAL PRIMARY KEY,
+  name                TEXT NOT NULL,
+  condition           JSONB NOT NULL,
+  webhook_url         TEXT NOT NULL,
+  webhook_format      TEXT NOT NULL DEFAULT 'generic'
+                      CHECK (webhook_format IN ('generic', 'slack', 'discord', 'telegram')),
+  telegram_chat_id    TEXT,
+  cooldown_minutes    INTEGER NOT NULL DEFAULT 60 CHECK (cooldown_minutes >= 0),
+  is_active           BOOLEAN NOT NULL DEFAULT true,
+  last_evaluated_at   TIMESTAMPTZ,
+  last_fired_at       TIMESTAMPTZ,
+  created_at          TIMESTAMPTZ NOT NULL DEFAULT now(),
+  updated_at          TIMESTAMPTZ NOT NULL DEFAULT now()
+);
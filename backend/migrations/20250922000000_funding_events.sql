@@ -0,0 +1,18 @@
+-- Sign flips, z-score outliers and regime changes detected on funding_rates.
+-- One row per (market, kind, sample) so re-running detection is idempotent.
+CREATE TABLE IF NOT EXISTS funding_events (
+  id           BIGSERIAL PRIMARY KEY,
+  exchange_id  INTEGER NOT NULL REFERENCES exchanges(id) ON DELETE CASCADE,
+  market_id    INTEGER NOT NULL REFERENCES markets(id) ON DELETE CASCADE,
+  kind         TEXT NOT NULL CHECK (kind IN ('sign_flip', 'zscore', 'regime_change')),
+  direction    TEXT NOT NULL CHECK (direction IN ('positive', 'negative')),
+  timestamp    TIMESTAMPTZ NOT NULL,            -- funding sample that triggered the event
+  rate         NUMERIC(18,10) NOT NULL,         -- native rate of that sample
+  prev_rate    NUMERIC(18,10),
+  zscore       DOUBLE PRECISION,
+  detail       TEXT NOT NULL,
+  created_at   TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  UNIQUE (market_id, kind, timestamp)
+);
+
+CREATE INDEX IF NOT EXISTS idx_funding_events_timestamp_desc ON funding_events(timestamp DESC);
@@ -0,0 +1,63 @@
+-- Mark / index / last prices captured alongside market_stats.
+CREATE TABLE IF NOT EXISTS price_snapshots (
+    id BIGSERIAL PRIMARY KEY,
+    market_id INTEGER NOT NULL REFERENCES markets(id) ON DELETE CASCADE,
+    mark_price NUMERIC(30,10),
+    index_price NUMERIC(30,10),
+    last_price NUMERIC(30,10),
+    timestamp TIMESTAMPTZ NOT NULL,
+    UNIQUE(market_id, timestamp)
+);
+
+CREATE INDEX IF NOT EXISTS idx_price_snapshots_market_timestamp_desc
+    ON price_snapshots(market_id, timestamp DESC);
+
+CREATE OR REPLACE VIEW latest_price_snapshots_view AS
+SELECT DISTINCT ON (market_id)
+  market_id, mark_price, index_price, last_price, timestamp
+FROM price_snapshots
+ORDER BY market_id, timestamp DESC;
+
+-- ---------- Frontend-ready matrix (per token, per exchange) ----------
+CREATE OR REPLACE VIEW funding_matrix_view AS
+SELECT
+  t.symbol,
+  jsonb_object_agg(
+    e.name,
+    jsonb_build_object(
+      'market_symbol',            m.market_symbol,
+      'funding_rate_8h',          (lfr.rate_8h)::float8,
+      'funding_bucket',           lfr.timestamp,
+      'funding_interval_minutes', COALESCE(m.funding_interval_minutes, e.funding_interval_minutes),
+      'open_interest',            (lms.open_interest)::float8,
+      'volume_24h',               (lms.volume_24h)::float8,
+      'stats_ts',                 lms.timestamp,
+      'predicted_rate',           (lpf.rate)::float8,
+      'next_funding_time',        lpf.next_funding_time,
+      'predicted_ts',             lpf.timestamp,
+      'mark_price',               (lps.mark_price)::float8,
+      'index_price',              (lps.index_price)::float8,
+      'last_price',               (lps.last_price)::float8,
+      'price_ts',                 lps.timestamp
+    )
+    ORDER BY e.name
+  ) AS per_exchange,
+  MAX(
+    GREATEST(
+      COALESCE(lfr.timestamp, 'epoch'::timestamptz),
+      COALESCE(lms.timestamp, 'epoch'::timestamptz)
+    )
+  ) AS last_update
+FROM markets m
+JOIN tokens    t ON t.id = m.token_id
+JOIN exchanges e ON e.id = m.exchange_id
+LEFT JOIN latest_funding_8h_view lfr
+  ON lfr.exchange_id = e.id AND lfr.market_id = m.id
+LEFT JOIN latest_market_stats_view  lms
+  ON lms.exchange_id = e.id AND lms.market_id = m.id
+LEFT JOIN latest_predicted_funding_view lpf
+  ON lpf.market_id = m.id
+LEFT JOIN latest_price_snapshots_view lps
+  ON lps.market_id = m.id
+WHERE m.is_active = true
+GROUP BY t.symbol;
@@ -0,0 +1,157 @@
+-- Monthly range partitioning of funding_rates and market_stats on `timestamp`.
+--
+-- Non-destructive: the existing heap table is renamed to <table>_legacy and
+-- attached as the partition for everything before the first monthly partition
+-- (MINVALUE .. start of the month after its newest row). Later months get
+-- their own partitions, created ahead of time by ensure_monthly_partitions()
+-- (called here and by the backend scheduler).
+--
+-- The (market_id, timestamp) unique key stays the row identity; `id` keeps its
+-- sequence but is no longer a primary key (a partitioned PK would have to
+-- include `timestamp`).
+
+-- ---------- Partition helper ----------
+-- Create monthly partitions of `parent` from the month containing `from_ts`
+-- through `months_ahead` months after now. Months already covered (e.g. by the
+-- legacy partition) are skipped.
+CREATE OR REPLACE FUNCTION ensure_monthly_partitions(parent text, from_ts timestamptz, months_ahead int)
+RETURNS int LANGUAGE plpgsql AS $$
+DECLARE
+  m       timestamptz := date_trunc('month', from_ts AT TIME ZONE 'UTC') AT TIME ZONE 'UTC';
+  last_m  timestamptz := date_trunc('month', (now() AT TIME ZONE 'UTC')) AT TIME ZONE 'UTC'
+                         + make_interval(months => months_ahead);
+  part    text;
+  created int := 0;
+BEGIN
+  WHILE m <= last_m LOOP
+    part := format('%s_y%sm%s', parent,
+                   to_char(m AT TIME ZONE 'UTC', 'YYYY'), to_char(m AT TIME ZONE 'UTC', 'MM'));
+    IF to_regclass(part) IS NULL THEN
+      BEGIN
+        EXECUTE format('CREATE TABLE %I PARTITION OF %I FOR VALUES FROM (%L) TO (%L)',
+                       part, parent, m, m + interval '1 month');
+        created := created + 1;
+      EXCEPTION WHEN invalid_object_definition THEN
+        -- overlaps an existing partition (the legacy one); nothing to do
+        NULL;
+      END;
+    END IF;
+    m := m + interval '1 month';
+  END LOOP;
+  RETURN created;
+END;
+$$;
+
+-- ---------- Convert one table ----------
+CREATE OR REPLACE FUNCTION partition_legacy_table(parent text, columns_ddl text, unique_cols text)
+RETURNS void LANGUAGE plpgsql AS $$
+DECLARE
+  legacy  text := parent || '_legacy';
+  upper_b timestamptz;
+  idx     record;
+BEGIN
+  IF EXISTS (SELECT 1 FROM pg_partitioned_table pt JOIN pg_class c ON c.oid = pt.partrelid
+             WHERE c.relname = parent) THEN
+    RETURN; -- already partitioned
+  END IF;
+
+  EXECUTE format('ALTER TABLE %I RENAME TO %I', parent, legacy);
+  -- free the index / constraint names for the new parent
+  FOR idx IN
+    SELECT i.relname FROM pg_index x
+    JOIN pg_class i ON i.oid = x.indexrelid
+    JOIN pg_class t ON t.oid = x.indrelid
+    WHERE t.relname = legacy
+  LOOP
+    EXECUTE format('ALTER INDEX %I RENAME TO %I', idx.relname, idx.relname || '_legacy');
+  END LOOP;
+
+  EXECUTE format('CREATE TABLE %I (%s, UNIQUE (%s)) PARTITION BY RANGE (timestamp)',
+                 parent, columns_ddl, unique_cols);
+
+  EXECUTE format('SELECT date_trunc(''month'', GREATEST(MAX(timestamp), now()) AT TIME ZONE ''UTC'')
+                         AT TIME ZONE ''UTC'' + interval ''1 month'' FROM %I', legacy)
+    INTO upper_b;
+  EXECUTE format('ALTER TABLE %I ATTACH PARTITION %I FOR VALUES FROM (MINVALUE) TO (%L)',
+                 parent, legacy, upper_b);
+END;
+$$;
+
+SELECT partition_legacy_table(
+  'funding_rates',
+  $cols$
+    id BIGINT NOT NULL DEFAULT nextval('funding_rates_id_seq'),
+    exchange_id INTEGER NOT NULL REFERENCES exchanges(id) ON DELETE CASCADE,
+    market_id INTEGER NOT NULL REFERENCES markets(id) ON DELETE CASCADE,
+    rate NUMERIC(18,10) NOT NULL,
+    timestamp TIMESTAMPTZ NOT NULL
+  $cols$,
+  'market_id, timestamp'
+);
+
+SELECT partition_legacy_table(
+  'market_stats',
+  $cols$
+    id BIGINT NOT NULL DEFAULT nextval('market_stats_id_seq'),
+    market_id INTEGER NOT NULL REFERENCES markets(id) ON DELETE CASCADE,
+    open_interest NUMERIC(30,10),
+    volume_24h NUMERIC(30,10),
+    timestamp TIMESTAMPTZ NOT NULL
+  $cols$,
+  'market_id, timestamp'
+);
+
+DROP FUNCTION partition_legacy_table(text, text, text);
+
+CREATE INDEX IF NOT EXISTS idx_funding_rates_on_market_id_timestamp_desc ON funding_rates(market_id, timestamp DESC);
+CREATE INDEX IF NOT EXISTS idx_funding_rates_on_exchange_id_timestamp_desc ON funding_rates(exchange_id, timestamp DESC);
+CREATE INDEX IF NOT EXISTS idx_market_stats_market_timestamp ON market_stats(market_id, timestamp DESC);
+
+SELECT ensure_monthly_partitions('funding_rates', now(), 3);
+SELECT ensure_monthly_partitions('market_stats', now(), 3);
+
+-- ---------- Views ----------
+-- Views are bound to the renamed legacy tables; re-create them on the new
+-- parents. The latest-row views now use per-market index lookups instead of
+-- ranking every row.
+CREATE OR REPLACE VIEW latest_market_stats_view AS
+SELECT m.exchange_id, ms.market_id, ms.open_interest, ms.volume_24h, ms.timestamp
+FROM markets m
+CROSS JOIN LATERAL (
+  SELECT s.market_id, s.open_interest, s.volume_24h, s.timestamp
+  FROM market_stats s
+  WHERE s.market_id = m.id
+  ORDER BY s.timestamp DESC
+  LIMIT 1
+) ms;
+
+CREATE OR REPLACE VIEW funding_8h_view AS
+SELECT
+  fr.exchange_id,
+  fr.market_id,
+  (date_trunc('hour', fr.timestamp)
+   - ( (EXTRACT(HOUR FROM fr.timestamp)::int % 8) * interval '1 hour')
+  ) AS bucket_start,
+  AVG(fr.rate) AS rate_8h,
+  COUNT(*)     AS samples
+FROM funding_rates fr
+GROUP BY fr.exchange_id, fr.market_id, bucket_start;
+
+CREATE OR REPLACE VIEW latest_funding_8h_view AS
+SELECT m.exchange_id, m.id AS market_id, b.rate_8h, lb.bucket_start AS timestamp
+FROM markets m
+CROSS JOIN LATERAL (
+  SELECT (date_trunc('hour', fr.timestamp)
+          - ((EXTRACT(HOUR FROM fr.timestamp)::int % 8) * interval '1 hour')) AS bucket_start
+  FROM funding_rates fr
+  WHERE fr.market_id = m.id
+  ORDER BY fr.timestamp DESC
+  LIMIT 1
+) lb
+CROSS JOIN LATERAL (
+  SELECT AVG(fr.rate) AS rate_8h
+  FROM funding_rates fr
+  WHERE fr.market_id = m.id
+    AND fr.timestamp >= lb.bucket_start
+    AND fr.timestamp <  lb.bucket_start + interval '8 hours'
+) b;
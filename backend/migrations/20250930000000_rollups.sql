@@ -0,0 +1,120 @@
+-- Incrementally maintained funding / stats rollups.
+--
+-- The insert layer (db::insert) adds every newly inserted funding row to the
+-- hourly, 8h and daily aggregates and advances latest_funding /
+-- latest_market_stats, so the latest-row views below are key lookups instead
+-- of scans over the full history. Buckets are UTC (date_bin from the epoch).
+
+CREATE TABLE IF NOT EXISTS funding_rollup_1h (
+    market_id INTEGER NOT NULL REFERENCES markets(id) ON DELETE CASCADE,
+    exchange_id INTEGER NOT NULL REFERENCES exchanges(id) ON DELETE CASCADE,
+    bucket_start TIMESTAMPTZ NOT NULL,
+    rate_sum NUMERIC NOT NULL,
+    rate_min NUMERIC(18,10) NOT NULL,
+    rate_max NUMERIC(18,10) NOT NULL,
+    samples INTEGER NOT NULL,
+    PRIMARY KEY (market_id, bucket_start)
+);
+
+CREATE TABLE IF NOT EXISTS funding_rollup_8h (LIKE funding_rollup_1h INCLUDING ALL);
+CREATE TABLE IF NOT EXISTS funding_rollup_1d (LIKE funding_rollup_1h INCLUDING ALL);
+-- LIKE doesn't copy foreign keys
+ALTER TABLE funding_rollup_8h
+    ADD FOREIGN KEY (market_id) REFERENCES markets(id) ON DELETE CASCADE,
+    ADD FOREIGN KEY (exchange_id) REFERENCES exchanges(id) ON DELETE CASCADE;
+ALTER TABLE funding_rollup_1d
+    ADD FOREIGN KEY (market_id) REFERENCES markets(id) ON DELETE CASCADE,
+    ADD FOREIGN KEY (exchange_id) REFERENCES exchanges(id) ON DELETE CASCADE;
+
+CREATE TABLE IF NOT EXISTS latest_funding (
+    market_id INTEGER PRIMARY KEY REFERENCES markets(id) ON DELETE CASCADE,
+    exchange_id INTEGER NOT NULL REFERENCES exchanges(id) ON DELETE CASCADE,
+    rate NUMERIC(18,10) NOT NULL,
+    timestamp TIMESTAMPTZ NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS latest_market_stats (
+    market_id INTEGER PRIMARY KEY REFERENCES markets(id) ON DELETE CASCADE,
+    open_interest NUMERIC(30,10),
+    volume_24h NUMERIC(30,10),
+    timestamp TIMESTAMPTZ NOT NULL
+);
+
+-- Recompute rollups from the raw tables for buckets from `since` (aligned down
+-- to a UTC day) onwards; NULL means from the oldest raw row still stored, so
+-- aggregates of already-pruned history are kept. Latest tables are refreshed
+-- from the newest raw row per market.
+CREATE OR REPLACE FUNCTION refresh_rollups(since timestamptz DEFAULT NULL)
+RETURNS void LANGUAGE plpgsql AS $$
+DECLARE
+  epoch CONSTANT timestamptz := '1970-01-01 00:00:00+00';
+  b     record;
+BEGIN
+  IF since IS NULL THEN
+    SELECT MIN(timestamp) INTO since FROM funding_rates;
+  END IF;
+
+  IF since IS NOT NULL THEN
+    since := date_bin('1 day', since, epoch);
+    FOR b IN SELECT * FROM (VALUES ('funding_rollup_1h', interval '1 hour'),
+                                   ('funding_rollup_8h', interval '8 hours'),
+                                   ('funding_rollup_1d', interval '1 day')) v(tbl, width)
+    LOOP
+      EXECUTE format('DELETE FROM %I WHERE bucket_start >= $1', b.tbl) USING since;
+      EXECUTE format(
+        'INSERT INTO %I (market_id, exchange_id, bucket_start, rate_sum, rate_min, rate_max, samples)
+         SELECT market_id, MIN(exchange_id), date_bin($1, timestamp, $2) AS bucket,
+                SUM(rate), MIN(rate), MAX(rate), COUNT(*)
+         FROM funding_rates
+         WHERE timestamp >= $3
+         GROUP BY market_id, bucket', b.tbl)
+      USING b.width, epoch, since;
+    END LOOP;
+  END IF;
+
+  INSERT INTO latest_funding (market_id, exchange_id, rate, timestamp)
+  SELECT m.id, f.exchange_id, f.rate, f.timestamp
+  FROM markets m
+  CROSS JOIN LATERAL (
+    SELECT fr.exchange_id, fr.rate, fr.timestamp FROM funding_rates fr
+    WHERE fr.market_id = m.id ORDER BY fr.timestamp DESC LIMIT 1
+  ) f
+  ON CONFLICT (market_id) DO UPDATE
+    SET exchange_id = EXCLUDED.exchange_id, rate = EXCLUDED.rate, timestamp = EXCLUDED.timestamp;
+
+  INSERT INTO latest_market_stats (market_id, open_interest, volume_24h, timestamp)
+  SELECT m.id, s.open_interest, s.volume_24h, s.timestamp
+  FROM markets m
+  CROSS JOIN LATERAL (
+    SELECT ms.open_interest, ms.volume_24h, ms.timestamp FROM market_stats ms
+    WHERE ms.market_id = m.id ORDER BY ms.timestamp DESC LIMIT 1
+  ) s
+  ON CONFLICT (market_id) DO UPDATE
+    SET open_interest = EXCLUDED.open_interest, volume_24h = EXCLUDED.volume_24h,
+        timestamp = EXCLUDED.timestamp;
+END;
+$$;
+
+SELECT refresh_rollups();
+
+-- ---------- Views on top of the rollups ----------
+CREATE OR REPLACE VIEW latest_market_stats_view AS
+SELECT m.exchange_id, l.market_id, l.open_interest, l.volume_24h, l.timestamp
+FROM latest_market_stats l
+JOIN markets m ON m.id = l.market_id;
+
+CREATE OR REPLACE VIEW funding_8h_view AS
+SELECT
+  r.exchange_id,
+  r.market_id,
+  r.bucket_start,
+  r.rate_sum / r.samples AS rate_8h,
+  r.samples::bigint      AS samples
+FROM funding_rollup_8h r;
+
+CREATE OR REPLACE VIEW latest_funding_8h_view AS
+SELECT l.exchange_id, l.market_id, r.rate_sum / r.samples AS rate_8h, r.bucket_start AS timestamp
+FROM latest_funding l
+JOIN funding_rollup_8h r
+  ON r.market_id = l.market_id
+ AND r.bucket_start = date_bin('8 hours', l.timestamp, TIMESTAMPTZ '1970-01-01 00:00:00+00');
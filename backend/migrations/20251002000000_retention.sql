@@ -0,0 +1,5 @@
+-- The id sequences still belong to the *_legacy partitions (the original
+-- tables), so dropping an expired legacy partition would take the sequence the
+-- parent's `id` default uses with it. Hand them to the partitioned parents.
+ALTER SEQUENCE IF EXISTS funding_rates_id_seq OWNED BY funding_rates.id;
+ALTER SEQUENCE IF EXISTS market_stats_id_seq OWNED BY market_stats.id;
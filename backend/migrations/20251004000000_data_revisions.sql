@@ -0,0 +1,16 @@
+-- Audit trail of stored values that a later sync corrected (conflict policy
+-- `overwrite` / `overwrite-if-different`). One row per changed key.
+CREATE TABLE IF NOT EXISTS data_revisions (
+    id BIGSERIAL PRIMARY KEY,
+    table_name TEXT NOT NULL,                 -- funding_rates | market_stats
+    market_id INTEGER NOT NULL REFERENCES markets(id) ON DELETE CASCADE,
+    timestamp TIMESTAMPTZ NOT NULL,           -- key of the revised row
+    old_values JSONB NOT NULL,
+    new_values JSONB NOT NULL,
+    revised_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_data_revisions_key
+    ON data_revisions(table_name, market_id, timestamp);
+CREATE INDEX IF NOT EXISTS idx_data_revisions_revised_at
+    ON data_revisions(revised_at DESC);
@@ -0,0 +1,32 @@
+-- Resumable `sync backfill` runs. A job freezes each market's window when it
+-- starts; checkpoints record how far each market has been written, so
+-- `sync backfill --resume <id>` continues instead of starting over.
+CREATE TABLE IF NOT EXISTS backfill_jobs (
+    id BIGSERIAL PRIMARY KEY,
+    exchange_id INTEGER NOT NULL REFERENCES exchanges(id) ON DELETE CASCADE,
+    spec JSONB NOT NULL,                      -- the requested TimeSpec
+    status TEXT NOT NULL DEFAULT 'running'
+        CHECK (status IN ('running', 'done', 'failed')),
+    error TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    finished_at TIMESTAMPTZ
+);
+
+CREATE TABLE IF NOT EXISTS backfill_checkpoints (
+    job_id BIGINT NOT NULL REFERENCES backfill_jobs(id) ON DELETE CASCADE,
+    market_id INTEGER NOT NULL REFERENCES markets(id) ON DELETE CASCADE,
+    start_ms BIGINT NOT NULL,                 -- resolved window, fixed for the job
+    end_ms BIGINT NOT NULL,
+    cursor_ms BIGINT NOT NULL,                -- window cursor: [start_ms, cursor_ms) is stored
+    page_cursor TEXT,                         -- venue page cursor within the chunk at cursor_ms
+    rows BIGINT NOT NULL DEFAULT 0,
+    requests INTEGER NOT NULL DEFAULT 0,
+    done BOOLEAN NOT NULL DEFAULT false,
+    error TEXT,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (job_id, market_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_backfill_jobs_created_at
+    ON backfill_jobs(created_at DESC);
@@ -0,0 +1,9 @@
+-- Newest funding period each sign_flip rule has compared, per market. Keyed on
+-- the period's own start rather than the rule's last evaluation, so rows that
+-- land late are still checked once.
+CREATE TABLE IF NOT EXISTS alert_rule_periods (
+  rule_id     INTEGER NOT NULL REFERENCES alert_rules(id) ON DELETE CASCADE,
+  market_id   INTEGER NOT NULL REFERENCES markets(id) ON DELETE CASCADE,
+  period_start TIMESTAMPTZ NOT NULL,
+  PRIMARY KEY (rule_id, market_id)
+);
@@ -0,0 +1,7 @@
+-- Catch-all partitions for funding_rates and market_stats. Retention drops
+-- expired monthly partitions, including the MINVALUE-bounded *_legacy one, so
+-- rows older than the oldest remaining month (deep imports, backfills of old
+-- windows) need somewhere to land. Retention deletes expired rows from here
+-- row by row.
+CREATE TABLE IF NOT EXISTS funding_rates_default PARTITION OF funding_rates DEFAULT;
+CREATE TABLE IF NOT EXISTS market_stats_default PARTITION OF market_stats DEFAULT;
@@ -0,0 +1,47 @@
+-- ensure_monthly_partitions() again, now that the DEFAULT partitions exist.
+-- Once retention has dropped a month, rows written for it (imports,
+-- backfills) land in the DEFAULT partition, and creating that month's
+-- partition later would fail its check of the DEFAULT partition. Those rows
+-- are moved into the new partition in the same transaction instead.
+CREATE OR REPLACE FUNCTION ensure_monthly_partitions(parent text, from_ts timestamptz, months_ahead int)
+RETURNS int LANGUAGE plpgsql AS $$
+DECLARE
+  m       timestamptz := date_trunc('month', from_ts AT TIME ZONE 'UTC') AT TIME ZONE 'UTC';
+  last_m  timestamptz := date_trunc('month', (now() AT TIME ZONE 'UTC')) AT TIME ZONE 'UTC'
+                         + make_interval(months => months_ahead);
+  def     regclass;
+  part    text;
+  created int := 0;
+BEGIN
+  SELECT NULLIF(pt.partdefid, 0)::regclass INTO def
+  FROM pg_partitioned_table pt
+  WHERE pt.partrelid = parent::regclass;
+
+  WHILE m <= last_m LOOP
+    part := format('%s_y%sm%s', parent,
+                   to_char(m AT TIME ZONE 'UTC', 'YYYY'), to_char(m AT TIME ZONE 'UTC', 'MM'));
+    IF to_regclass(part) IS NULL THEN
+      BEGIN
+        IF def IS NOT NULL THEN
+          EXECUTE format('CREATE TEMP TABLE partition_rows (LIKE %I) ON COMMIT DROP', parent);
+          EXECUTE format('WITH d AS (DELETE FROM %s WHERE timestamp >= %L AND timestamp < %L RETURNING *)
+                          INSERT INTO partition_rows SELECT * FROM d',
+                         def, m, m + interval '1 month');
+        END IF;
+        EXECUTE format('CREATE TABLE %I PARTITION OF %I FOR VALUES FROM (%L) TO (%L)',
+                       part, parent, m, m + interval '1 month');
+        IF def IS NOT NULL THEN
+          EXECUTE format('INSERT INTO %I SELECT * FROM partition_rows', part);
+          DROP TABLE partition_rows;
+        END IF;
+        created := created + 1;
+      EXCEPTION WHEN invalid_object_definition THEN
+        -- overlaps an existing partition (the legacy one); nothing to do
+        NULL;
+      END;
+    END IF;
+    m := m + interval '1 month';
+  END LOOP;
+  RETURN created;
+END;
+$$;
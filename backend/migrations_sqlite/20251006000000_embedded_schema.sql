@@ -0,0 +1,52 @@
+-- Schema for the embedded SQLite store (src/store/sqlite.rs). Same tables as
+-- the Postgres core without rollups or partitions: rates and amounts are
+-- decimal strings (exact, like NUMERIC), timestamps are unix seconds.
+
+CREATE TABLE IF NOT EXISTS exchanges (
+    id INTEGER PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE,
+    funding_interval_minutes INTEGER,
+    is_active INTEGER NOT NULL DEFAULT 1
+);
+
+CREATE TABLE IF NOT EXISTS tokens (
+    id INTEGER PRIMARY KEY,
+    symbol TEXT NOT NULL UNIQUE
+);
+
+CREATE TABLE IF NOT EXISTS markets (
+    id INTEGER PRIMARY KEY,
+    exchange_id INTEGER NOT NULL REFERENCES exchanges(id) ON DELETE CASCADE,
+    token_id INTEGER NOT NULL REFERENCES tokens(id) ON DELETE CASCADE,
+    market_symbol TEXT NOT NULL,
+    is_active INTEGER NOT NULL DEFAULT 1,
+    funding_interval_minutes INTEGER,
+    UNIQUE(exchange_id, market_symbol)
+);
+
+CREATE TABLE IF NOT EXISTS funding_rates (
+    market_id INTEGER NOT NULL REFERENCES markets(id) ON DELETE CASCADE,
+    exchange_id INTEGER NOT NULL,
+    rate TEXT NOT NULL,
+    timestamp INTEGER NOT NULL,
+    PRIMARY KEY (market_id, timestamp)
+) WITHOUT ROWID;
+
+CREATE TABLE IF NOT EXISTS market_stats (
+    market_id INTEGER NOT NULL REFERENCES markets(id) ON DELETE CASCADE,
+    open_interest TEXT,
+    volume_24h TEXT,
+    timestamp INTEGER NOT NULL,
+    PRIMARY KEY (market_id, timestamp)
+) WITHOUT ROWID;
+
+CREATE TABLE IF NOT EXISTS price_snapshots (
+    market_id INTEGER NOT NULL REFERENCES markets(id) ON DELETE CASCADE,
+    mark_price TEXT,
+    index_price TEXT,
+    last_price TEXT,
+    timestamp INTEGER NOT NULL,
+    PRIMARY KEY (market_id, timestamp)
+) WITHOUT ROWID;
+
+CREATE INDEX IF NOT EXISTS idx_markets_on_token_id ON markets(token_id);
@@ -0,0 +1,280 @@
+// src/alerts/evaluate.rs
+use anyhow::Result;
+use serde::Serialize;
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::time::Duration as StdDuration;
+use time::{Duration, OffsetDateTime};
+use tracing::{info, warn};
+
+use super::rules::{list_rules, mark_evaluated, mark_periods_seen, seen_periods, AlertCondition, AlertRule, StaleData};
+use super::webhook::deliver;
+use crate::analytics::rates::{self, DEFAULT_INTERVAL_MINUTES};
+
+/// Latest state of one active market, shared by every rule in a pass.
+#[derive(Debug, Clone)]
+pub struct MarketSnapshot {
+    pub market_id: i32,
+    pub exchange: String,
+    pub token: String,
+    /// Start of the latest closed funding period (native interval).
+    pub period_start: Option<OffsetDateTime>,
+    pub period_rate: Option<f64>,      // average rate over that period
+    pub prev_period_rate: Option<f64>, // and over the period before it
+    pub rate_8h: Option<f64>,
+    pub apr: Option<f64>,
+    pub funding_ts: Option<OffsetDateTime>,
+    pub open_interest: Option<f64>,
+    pub stats_ts: Option<OffsetDateTime>,
+}
+
+/// One reason a rule fired.
+#[derive(Serialize, Debug, Clone)]
+pub struct AlertMatch {
+    pub exchange: Option<String>,
+    pub token: Option<String>,
+    pub value: f64,
+    pub message: String,
+}
+
+pub async fn load_snapshot(pool: &PgPool) -> Result<Vec<MarketSnapshot>> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT
+          m.id AS "market_id!",
+          e.name AS "exchange!",
+          t.symbol AS "token!",
+          COALESCE(m.funding_interval_minutes, e.funding_interval_minutes) AS interval_minutes,
+          lf.rate::float8 AS rate,
+          lf.timestamp AS "funding_ts?",
+          fp.period_start AS "period_start?",
+          fp.period_rate AS "period_rate?",
+          fp.prev_period_rate AS "prev_period_rate?",
+          lms.open_interest::float8 AS open_interest,
+          lms.timestamp AS "stats_ts?"
+        FROM markets m
+        JOIN tokens    t ON t.id = m.token_id
+        JOIN exchanges e ON e.id = m.exchange_id
+        LEFT JOIN latest_funding lf ON lf.market_id = m.id
+        CROSS JOIN LATERAL (
+          SELECT make_interval(mins => COALESCE(m.funding_interval_minutes, e.funding_interval_minutes, $1)) AS iv
+        ) i
+        -- the two newest closed native periods with data, from the hourly rollups
+        LEFT JOIN LATERAL (
+          SELECT MAX(p.period) AS period_start,
+                 (array_agg(p.rate ORDER BY p.period DESC))[1] AS period_rate,
+                 (array_agg(p.rate ORDER BY p.period DESC))[2] AS prev_period_rate
+          FROM (
+            SELECT date_bin(i.iv, r.bucket_start, TIMESTAMPTZ 'epoch') AS period,
+                   (SUM(r.rate_sum) / SUM(r.samples))::float8 AS rate
+            FROM funding_rollup_1h r
+            WHERE r.market_id = m.id
+              AND r.bucket_start >= date_bin(i.iv, now(), TIMESTAMPTZ 'epoch') - i.iv * 4
+            GROUP BY 1
+            HAVING date_bin(i.iv, r.bucket_start, TIMESTAMPTZ 'epoch') + i.iv <= now()
+            ORDER BY 1 DESC
+            LIMIT 2
+          ) p
+        ) fp ON true
+        LEFT JOIN latest_market_stats_view lms ON lms.market_id = m.id
+        WHERE m.is_active = true AND e.is_active = true
+        "#,
+        DEFAULT_INTERVAL_MINUTES
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|r| {
+            let bases = r
+                .rate
+                .map(|x| rates::all_bases(x, r.interval_minutes.unwrap_or(DEFAULT_INTERVAL_MINUTES)));
+            MarketSnapshot {
+                market_id: r.market_id,
+                exchange: r.exchange,
+                token: r.token,
+                period_start: r.period_start,
+                period_rate: r.period_rate,
+                prev_period_rate: r.prev_period_rate,
+                rate_8h: bases.map(|b| b.rate_8h),
+                apr: bases.map(|b| b.apr),
+                funding_ts: r.funding_ts,
+                open_interest: r.open_interest,
+                stats_ts: r.stats_ts,
+            }
+        })
+        .collect())
+}
+
+fn matches_opt(filter: &Option<String>, value: &str) -> bool {
+    filter.as_deref().is_none_or(|f| f.eq_ignore_ascii_case(value))
+}
+
+/// Markets a sign-flip rule compares, with the period it compares at. Nothing
+/// for other conditions.
+pub fn watched_periods(cond: &AlertCondition, snap: &[MarketSnapshot]) -> Vec<(i32, OffsetDateTime)> {
+    match cond {
+        AlertCondition::SignFlip { exchange, token } => snap
+            .iter()
+            .filter(|m| matches_opt(exchange, &m.exchange) && matches_opt(token, &m.token))
+            .filter_map(|m| Some((m.market_id, m.period_start?)))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Evaluate one condition against the snapshot. `seen` maps market id to the
+/// newest period the rule already compared there, so each sign flip is reported
+/// once, however late its rows arrive.
+pub fn evaluate_condition(
+    cond: &AlertCondition,
+    snap: &[MarketSnapshot],
+    seen: &HashMap<i32, OffsetDateTime>,
+    now: OffsetDateTime,
+) -> Vec<AlertMatch> {
+    match cond {
+        AlertCondition::Spread { token, exchange_a, exchange_b, min_bps } => {
+            let rate_on = |ex: &str| {
+                snap.iter()
+                    .find(|m| m.token.eq_ignore_ascii_case(token) && m.exchange.eq_ignore_ascii_case(ex))
+                    .and_then(|m| m.rate_8h)
+            };
+            match (rate_on(exchange_a), rate_on(exchange_b)) {
+                (Some(a), Some(b)) if ((a - b) * 10_000.0).abs() > *min_bps => {
+                    let bps = (a - b) * 10_000.0;
+                    vec![AlertMatch {
+                        exchange: None,
+                        token: Some(token.to_ascii_uppercase()),
+                        value: bps,
+                        message: format!(
+                            "{} spread {} - {} = {:.2} bps/8h (threshold {:.2})",
+                            token.to_ascii_uppercase(), exchange_a, exchange_b, bps, min_bps
+                        ),
+                    }]
+                }
+                _ => Vec::new(),
+            }
+        }
+
+        AlertCondition::SignFlip { exchange, token } => snap
+            .iter()
+            .filter(|m| matches_opt(exchange, &m.exchange) && matches_opt(token, &m.token))
+            .filter(|m| match (seen.get(&m.market_id), m.period_start) {
+                (Some(seen), Some(start)) => start > *seen,
+                _ => true,
+            })
+            .filter_map(|m| {
+                let (cur, prev) = (m.period_rate?, m.prev_period_rate?);
+                (cur != 0.0 && prev != 0.0 && cur.signum() != prev.signum()).then(|| AlertMatch {
+                    exchange: Some(m.exchange.clone()),
+                    token: Some(m.token.clone()),
+                    value: cur,
+                    message: format!(
+                        "{} on {} flipped {} over the last funding period ({:.6} -> {:.6})",
+                        m.token,
+                        m.exchange,
+                        if cur > 0.0 { "positive" } else { "negative" },
+                        prev,
+                        cur
+                    ),
+                })
+            })
+            .collect(),
+
+        AlertCondition::AprOi { exchange, token, min_apr, min_oi } => snap
+            .iter()
+            .filter(|m| matches_opt(exchange, &m.exchange) && matches_opt(token, &m.token))
+            .filter_map(|m| {
+                let (apr, oi) = (m.apr?, m.open_interest?);
+                (apr >= *min_apr && oi >= *min_oi).then(|| AlertMatch {
+                    exchange: Some(m.exchange.clone()),
+                    token: Some(m.token.clone()),
+                    value: apr,
+                    message: format!(
+                        "{} on {} APR {:.2}% with OI {:.0}",
+                        m.token, m.exchange, apr * 100.0, oi
+                    ),
+                })
+            })
+            .collect(),
+
+        AlertCondition::Stale { exchange, minutes, data } => {
+            let newest = snap
+                .iter()
+                .filter(|m| m.exchange.eq_ignore_ascii_case(exchange))
+                .filter_map(|m| match data {
+                    StaleData::Funding => m.funding_ts,
+                    StaleData::Stats => m.stats_ts,
+                })
+                .max();
+            let age_min = newest.map(|ts| (now - ts).whole_minutes());
+            if age_min.is_none_or(|a| a >= *minutes) {
+                let kind = match data {
+                    StaleData::Funding => "funding",
+                    StaleData::Stats => "stats",
+                };
+                vec![AlertMatch {
+                    exchange: Some(exchange.clone()),
+                    token: None,
+                    value: age_min.unwrap_or(-1) as f64,
+                    message: match age_min {
+                        Some(a) => format!("{exchange} {kind} data is {a} min old (limit {minutes})"),
+                        None => format!("{exchange} has no {kind} data"),
+                    },
+                }]
+            } else {
+                Vec::new()
+            }
+        }
+    }
+}
+
+fn cooling_down(rule: &AlertRule, now: OffsetDateTime) -> bool {
+    rule.last_fired_at
+        .is_some_and(|t| now - t < Duration::minutes(rule.cooldown_minutes as i64))
+}
+
+/// Run every active rule once and deliver the ones that fired. Returns the
+/// number of notifications sent. Delivery failures leave the cooldown untouched
+/// so the alert is retried on the next pass.
+pub async fn evaluate_alerts(pool: &PgPool) -> Result<usize> {
+    let rules = list_rules(pool, true).await?;
+    if rules.is_empty() {
+        return Ok(0);
+    }
+
+    let snap = load_snapshot(pool).await?;
+    let now = OffsetDateTime::now_utc();
+    let client = reqwest::Client::builder()
+        .timeout(StdDuration::from_secs(10))
+        .build()?;
+
+    let mut sent = 0;
+    for rule in &rules {
+        let watched = watched_periods(&rule.condition, &snap);
+        let seen = if watched.is_empty() { HashMap::new() } else { seen_periods(pool, rule.id).await? };
+        let matches = evaluate_condition(&rule.condition, &snap, &seen, now);
+        let mut fired = false;
+        let mut failed = false;
+        if !matches.is_empty() && !cooling_down(rule, now) {
+            match deliver(&client, rule, &matches, now).await {
+                Ok(()) => {
+                    info!("alert '{}' (id={}) fired: {} match(es)", rule.name, rule.id, matches.len());
+                    fired = true;
+                    sent += 1;
+                }
+                Err(e) => {
+                    warn!("alert '{}' (id={}) delivery failed: {e:?}", rule.name, rule.id);
+                    failed = true;
+                }
+            }
+        }
+        // a failed delivery keeps the periods unseen so the flip is retried
+        if !failed {
+            mark_periods_seen(pool, rule.id, &watched).await?;
+        }
+        mark_evaluated(pool, rule.id, now, fired).await?;
+    }
+    Ok(sent)
+}
@@ -0,0 +1,6 @@
+// src/alerts/mod.rs
+pub mod rules;
+pub mod evaluate;
+pub mod webhook;
+
+pub use evaluate::evaluate_alerts;
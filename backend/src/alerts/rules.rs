@@ -0,0 +1,345 @@
+// src/alerts/rules.rs
+use std::collections::HashMap;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use sqlx::{types::Json, PgPool};
+use time::OffsetDateTime;
+
+/// What a rule watches. Stored as tagged JSON in `alert_rules.condition`.
+/// Rates are compared on an 8h basis; APR is simple (non-compounded) as in the API.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum AlertCondition {
+    /// |8h rate on A - 8h rate on B| for `token` exceeds `min_bps`.
+    Spread {
+        token: String,
+        exchange_a: String,
+        exchange_b: String,
+        min_bps: f64,
+    },
+    /// The latest closed funding period (native interval) has the opposite sign
+    /// of the one before it.
+    SignFlip {
+        exchange: Option<String>,
+        token: Option<String>,
+    },
+    /// APR at or above `min_apr` (0.25 = 25%) on a market with OI at or above `min_oi` (USD).
+    AprOi {
+        exchange: Option<String>,
+        token: Option<String>,
+        min_apr: f64,
+        min_oi: f64,
+    },
+    /// Newest funding (or stats) row for `exchange` is older than `minutes`.
+    Stale {
+        exchange: String,
+        minutes: i64,
+        #[serde(default)]
+        data: StaleData,
+    },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum StaleData {
+    #[default]
+    Funding,
+    Stats,
+}
+
+impl AlertCondition {
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            AlertCondition::Spread { exchange_a, exchange_b, min_bps, .. } => {
+                if exchange_a.eq_ignore_ascii_case(exchange_b) {
+                    return Err("exchange_a and exchange_b must differ".into());
+                }
+                if !(min_bps.is_finite() && *min_bps >= 0.0) {
+                    return Err("min_bps must be non-negative".into());
+                }
+            }
+            AlertCondition::AprOi { min_apr, min_oi, .. } => {
+                if !(min_apr.is_finite() && min_oi.is_finite() && *min_oi >= 0.0) {
+                    return Err("min_apr must be finite and min_oi non-negative".into());
+                }
+            }
+            AlertCondition::Stale { minutes, .. } => {
+                if *minutes <= 0 {
+                    return Err("minutes must be positive".into());
+                }
+            }
+            AlertCondition::SignFlip { .. } => {}
+        }
+        Ok(())
+    }
+}
+
+/// Payload shape sent to `webhook_url`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookFormat {
+    #[default]
+    Generic,  // {"rule", "text", "matches", ...}
+    Slack,    // {"text"}
+    Discord,  // {"content"}
+    Telegram, // {"chat_id", "text"} to a bot sendMessage URL
+}
+
+impl WebhookFormat {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            WebhookFormat::Generic => "generic",
+            WebhookFormat::Slack => "slack",
+            WebhookFormat::Discord => "discord",
+            WebhookFormat::Telegram => "telegram",
+        }
+    }
+
+    fn from_db(s: &str) -> Self {
+        match s {
+            "slack" => WebhookFormat::Slack,
+            "discord" => WebhookFormat::Discord,
+            "telegram" => WebhookFormat::Telegram,
+            _ => WebhookFormat::Generic,
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct AlertRule {
+    pub id: i32,
+    pub name: String,
+    pub condition: AlertCondition,
+    pub webhook_url: String,
+    pub webhook_format: WebhookFormat,
+    pub telegram_chat_id: Option<String>,
+    pub cooldown_minutes: i32,
+    pub is_active: bool,
+    #[serde(with = "time::serde::rfc3339::option")]
+    pub last_evaluated_at: Option<OffsetDateTime>,
+    #[serde(with = "time::serde::rfc3339::option")]
+    pub last_fired_at: Option<OffsetDateTime>,
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: OffsetDateTime,
+    #[serde(with = "time::serde::rfc3339")]
+    pub updated_at: OffsetDateTime,
+}
+
+/// Body of create / replace requests.
+#[derive(Deserialize, Debug, Clone)]
+pub struct AlertRuleInput {
+    pub name: String,
+    pub condition: AlertCondition,
+    pub webhook_url: String,
+    #[serde(default)]
+    pub webhook_format: WebhookFormat,
+    pub telegram_chat_id: Option<String>,
+    #[serde(default = "default_cooldown")]
+    pub cooldown_minutes: i32,
+    #[serde(default = "default_active")]
+    pub is_active: bool,
+}
+
+fn default_cooldown() -> i32 {
+    60
+}
+
+fn default_active() -> bool {
+    true
+}
+
+impl AlertRuleInput {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("name is required".into());
+        }
+        if !(self.webhook_url.starts_with("https://") || self.webhook_url.starts_with("http://")) {
+            return Err("webhook_url must be an http(s) URL".into());
+        }
+        if self.webhook_format == WebhookFormat::Telegram && self.telegram_chat_id.is_none() {
+            return Err("telegram webhooks require telegram_chat_id".into());
+        }
+        if self.cooldown_minutes < 0 {
+            return Err("cooldown_minutes must be non-negative".into());
+        }
+        self.condition.validate()
+    }
+}
+
+/* ---------------- DB ---------------- */
+
+struct RuleRow {
+    id: i32,
+    name: String,
+    condition: Json<AlertCondition>,
+    webhook_url: String,
+    webhook_format: String,
+    telegram_chat_id: Option<String>,
+    cooldown_minutes: i32,
+    is_active: bool,
+    last_evaluated_at: Option<OffsetDateTime>,
+    last_fired_at: Option<OffsetDateTime>,
+    created_at: OffsetDateTime,
+    updated_at: OffsetDateTime,
+}
+
+impl From<RuleRow> for AlertRule {
+    fn from(r: RuleRow) -> Self {
+        AlertRule {
+            id: r.id,
+            name: r.name,
+            condition: r.condition.0,
+            webhook_url: r.webhook_url,
+            webhook_format: WebhookFormat::from_db(&r.webhook_format),
+            telegram_chat_id: r.telegram_chat_id,
+            cooldown_minutes: r.cooldown_minutes,
+            is_active: r.is_active,
+            last_evaluated_at: r.last_evaluated_at,
+            last_fired_at: r.last_fired_at,
+            created_at: r.created_at,
+            updated_at: r.updated_at,
+        }
+    }
+}
+
+pub async fn list_rules(pool: &PgPool, active_only: bool) -> Result<Vec<AlertRule>> {
+    let rows = sqlx::query_as!(
+        RuleRow,
+        r#"
+        SELECT id, name, condition AS "condition: Json<AlertCondition>", webhook_url, webhook_format,
+               telegram_chat_id, cooldown_minutes, is_active, last_evaluated_at, last_fired_at,
+               created_at, updated_at
+        FROM alert_rules
+        WHERE NOT $1 OR is_active
+        ORDER BY id
+        "#,
+        active_only
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(rows.into_iter().map(AlertRule::from).collect())
+}
+
+pub async fn get_rule(pool: &PgPool, id: i32) -> Result<Option<AlertRule>> {
+    let row = sqlx::query_as!(
+        RuleRow,
+        r#"
+        SELECT id, name, condition AS "condition: Json<AlertCondition>", webhook_url, webhook_format,
+               telegram_chat_id, cooldown_minutes, is_active, last_evaluated_at, last_fired_at,
+               created_at, updated_at
+        FROM alert_rules
+        WHERE id = $1
+        "#,
+        id
+    )
+    .fetch_optional(pool)
+    .await?;
+    Ok(row.map(AlertRule::from))
+}
+
+pub async fn create_rule(pool: &PgPool, input: &AlertRuleInput) -> Result<AlertRule> {
+    let r = sqlx::query_as!(
+        RuleRow,
+        r#"
+        INSERT INTO alert_rules (name, condition, webhook_url, webhook_format, telegram_chat_id,
+                                 cooldown_minutes, is_active)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        RETURNING id, name, condition AS "condition: Json<AlertCondition>", webhook_url, webhook_format,
+                  telegram_chat_id, cooldown_minutes, is_active, last_evaluated_at, last_fired_at,
+                  created_at, updated_at
+        "#,
+        input.name.trim(),
+        Json(&input.condition) as _,
+        input.webhook_url,
+        input.webhook_format.as_str(),
+        input.telegram_chat_id,
+        input.cooldown_minutes,
+        input.is_active
+    )
+    .fetch_one(pool)
+    .await?;
+    Ok(r.into())
+}
+
+pub async fn update_rule(pool: &PgPool, id: i32, input: &AlertRuleInput) -> Result<Option<AlertRule>> {
+    let row = sqlx::query_as!(
+        RuleRow,
+        r#"
+        UPDATE alert_rules
+        SET name = $2, condition = $3, webhook_url = $4, webhook_format = $5,
+            telegram_chat_id = $6, cooldown_minutes = $7, is_active = $8, updated_at = now()
+        WHERE id = $1
+        RETURNING id, name, condition AS "condition: Json<AlertCondition>", webhook_url, webhook_format,
+                  telegram_chat_id, cooldown_minutes, is_active, last_evaluated_at, last_fired_at,
+                  created_at, updated_at
+        "#,
+        id,
+        input.name.trim(),
+        Json(&input.condition) as _,
+        input.webhook_url,
+        input.webhook_format.as_str(),
+        input.telegram_chat_id,
+        input.cooldown_minutes,
+        input.is_active
+    )
+    .fetch_optional(pool)
+    .await?;
+    Ok(row.map(AlertRule::from))
+}
+
+pub async fn delete_rule(pool: &PgPool, id: i32) -> Result<bool> {
+    let res = sqlx::query!("DELETE FROM alert_rules WHERE id = $1", id)
+        .execute(pool)
+        .await?;
+    Ok(res.rows_affected() > 0)
+}
+
+/// Record an evaluation pass; `fired` also moves the cooldown window.
+pub async fn mark_evaluated(pool: &PgPool, id: i32, at: OffsetDateTime, fired: bool) -> Result<()> {
+    sqlx::query!(
+        r#"
+        UPDATE alert_rules
+        SET last_evaluated_at = $2,
+            last_fired_at = CASE WHEN $3 THEN $2 ELSE last_fired_at END
+        WHERE id = $1
+        "#,
+        id,
+        at,
+        fired
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Newest funding period start each market was last compared at for `rule_id`.
+pub async fn seen_periods(pool: &PgPool, rule_id: i32) -> Result<HashMap<i32, OffsetDateTime>> {
+    let rows = sqlx::query!(
+        "SELECT market_id, period_start FROM alert_rule_periods WHERE rule_id = $1",
+        rule_id
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(rows.into_iter().map(|r| (r.market_id, r.period_start)).collect())
+}
+
+pub async fn mark_periods_seen(pool: &PgPool, rule_id: i32, periods: &[(i32, OffsetDateTime)]) -> Result<()> {
+    if periods.is_empty() {
+        return Ok(());
+    }
+    sqlx::query!(
+        r#"
+        INSERT INTO alert_rule_periods (rule_id, market_id, period_start)
+        SELECT $1, m, p FROM UNNEST($2::int4[], $3::timestamptz[]) AS x(m, p)
+        ON CONFLICT (rule_id, market_id) DO UPDATE
+          SET period_start = GREATEST(alert_rule_periods.period_start, EXCLUDED.period_start)
+        "#,
+        rule_id,
+        &periods.iter().map(|p| p.0).collect::<Vec<_>>(),
+        &periods.iter().map(|p| p.1).collect::<Vec<_>>()
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
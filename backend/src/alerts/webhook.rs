@@ -0,0 +1,67 @@
+// src/alerts/webhook.rs
+use anyhow::{bail, Result};
+use reqwest::header::CONTENT_TYPE;
+use serde_json::{json, Value};
+use time::OffsetDateTime;
+
+use super::evaluate::AlertMatch;
+use super::rules::{AlertRule, WebhookFormat};
+
+const DISCORD_MAX_CHARS: usize = 2000;
+const TELEGRAM_MAX_CHARS: usize = 4096;
+
+fn text(rule: &AlertRule, matches: &[AlertMatch]) -> String {
+    let mut out = format!("[funding alert] {}", rule.name);
+    for m in matches {
+        out.push_str("\n- ");
+        out.push_str(&m.message);
+    }
+    out
+}
+
+fn truncate(mut s: String, max: usize) -> String {
+    if s.chars().count() > max {
+        s = s.chars().take(max.saturating_sub(1)).collect();
+        s.push('…');
+    }
+    s
+}
+
+pub fn payload(rule: &AlertRule, matches: &[AlertMatch], at: OffsetDateTime) -> Value {
+    let text = text(rule, matches);
+    match rule.webhook_format {
+        WebhookFormat::Generic => json!({
+            "rule_id": rule.id,
+            "rule": rule.name,
+            "condition": rule.condition,
+            "fired_at": at.unix_timestamp(),
+            "text": text,
+            "matches": matches,
+        }),
+        WebhookFormat::Slack => json!({ "text": text }),
+        WebhookFormat::Discord => json!({ "content": truncate(text, DISCORD_MAX_CHARS) }),
+        WebhookFormat::Telegram => json!({
+            "chat_id": rule.telegram_chat_id,
+            "text": truncate(text, TELEGRAM_MAX_CHARS),
+        }),
+    }
+}
+
+pub async fn deliver(
+    client: &reqwest::Client,
+    rule: &AlertRule,
+    matches: &[AlertMatch],
+    at: OffsetDateTime,
+) -> Result<()> {
+    let body = serde_json::to_vec(&payload(rule, matches, at))?;
+    let resp = client
+        .post(&rule.webhook_url)
+        .header(CONTENT_TYPE, "application/json")
+        .body(body)
+        .send()
+        .await?;
+    if !resp.status().is_success() {
+        bail!("webhook returned {}", resp.status());
+    }
+    Ok(())
+}
@@ -0,0 +1,461 @@
+// src/analytics/backtest.rs
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::collections::{BTreeMap, HashMap};
+use time::{Duration, OffsetDateTime};
+
+use crate::analytics::rates::{self, RateBasis, DEFAULT_INTERVAL_MINUTES};
+
+/* ---------------- Config ---------------- */
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+pub struct FeeSchedule {
+    pub maker_bps: f64,
+    pub taker_bps: f64,
+}
+
+impl Default for FeeSchedule {
+    fn default() -> Self {
+        Self { maker_bps: 2.0, taker_bps: 5.0 }
+    }
+}
+
+/// Spread strategy: short the highest-funding venue, long the lowest, per token.
+/// Spreads are compared on an 8h basis, whatever each venue's native interval.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct BacktestConfig {
+    #[serde(with = "time::serde::rfc3339::option")]
+    pub from: Option<OffsetDateTime>, // default: 30 days before `to`
+    #[serde(with = "time::serde::rfc3339::option")]
+    pub to: Option<OffsetDateTime>, // default: now
+    pub tokens: Option<Vec<String>>,
+    pub exchanges: Option<Vec<String>>,
+    pub notional: f64,         // per leg, quote currency
+    pub entry_spread_bps: f64, // open when short - long >= this (8h basis)
+    pub exit_spread_bps: f64,  // close when short - long < this (8h basis)
+    pub fees: HashMap<String, FeeSchedule>, // by exchange name, case-insensitive
+    pub default_fees: FeeSchedule,
+    pub use_maker: bool,
+    /// Slippage per leg per fill, in bps per 1% of the market's liquidity taken,
+    /// where liquidity = min(open interest, 24h volume) at the time of the fill.
+    pub impact_bps_per_pct: f64,
+    pub max_slippage_bps: f64, // cap, also used when liquidity is unknown
+    pub min_hold_periods: u32,
+    pub max_hold_periods: Option<u32>,
+    pub max_concurrent: usize,
+}
+
+impl Default for BacktestConfig {
+    fn default() -> Self {
+        Self {
+            from: None,
+            to: None,
+            tokens: None,
+            exchanges: None,
+            notional: 10_000.0,
+            entry_spread_bps: 5.0,
+            exit_spread_bps: 1.0,
+            fees: HashMap::new(),
+            default_fees: FeeSchedule::default(),
+            use_maker: false,
+            impact_bps_per_pct: 1.0,
+            max_slippage_bps: 50.0,
+            min_hold_periods: 3,
+            max_hold_periods: None,
+            max_concurrent: 5,
+        }
+    }
+}
+
+impl BacktestConfig {
+    /// Resolved `[from, to)` window.
+    pub fn window(&self) -> (OffsetDateTime, OffsetDateTime) {
+        let to = self.to.unwrap_or_else(OffsetDateTime::now_utc);
+        let from = self.from.unwrap_or(to - Duration::days(30));
+        (from, to)
+    }
+
+    /// Reject configs that would make the simulation meaningless.
+    pub fn validate(&self) -> Result<(), String> {
+        let (from, to) = self.window();
+        if from >= to {
+            return Err("from must be before to".into());
+        }
+        if !(self.notional.is_finite() && self.notional > 0.0) {
+            return Err("notional must be positive".into());
+        }
+        if self.exit_spread_bps > self.entry_spread_bps {
+            return Err("exit_spread_bps must not exceed entry_spread_bps".into());
+        }
+        if self.max_concurrent == 0 {
+            return Err("max_concurrent must be at least 1".into());
+        }
+        if self.max_hold_periods.is_some_and(|m| m < self.min_hold_periods.max(1)) {
+            return Err("max_hold_periods must be >= min_hold_periods".into());
+        }
+        if self.impact_bps_per_pct < 0.0 || self.max_slippage_bps < 0.0 {
+            return Err("slippage parameters must be non-negative".into());
+        }
+        Ok(())
+    }
+
+    fn fee_bps(&self, exchange: &str) -> f64 {
+        let f = self
+            .fees
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(exchange))
+            .map(|(_, v)| *v)
+            .unwrap_or(self.default_fees);
+        if self.use_maker { f.maker_bps } else { f.taker_bps }
+    }
+
+    fn slippage_bps(&self, liquidity: Option<f64>) -> f64 {
+        match liquidity {
+            Some(l) if l > 0.0 => {
+                (self.impact_bps_per_pct * self.notional / l * 100.0).min(self.max_slippage_bps)
+            }
+            _ => self.max_slippage_bps,
+        }
+    }
+}
+
+/* ---------------- Data ---------------- */
+
+#[derive(Debug, Clone)]
+struct Quote {
+    exchange: String,
+    market_id: i32,
+    rate_8h: f64,
+}
+
+/// Funding per 8h bucket and stats history, as loaded from the DB.
+#[derive(Debug, Default)]
+pub struct BacktestData {
+    buckets: BTreeMap<OffsetDateTime, HashMap<String, Vec<Quote>>>,
+    /// market_id -> (ts, liquidity) ascending
+    liquidity: HashMap<i32, Vec<(OffsetDateTime, f64)>>,
+}
+
+impl BacktestData {
+    fn liquidity_at(&self, market_id: i32, ts: OffsetDateTime) -> Option<f64> {
+        let hist = self.liquidity.get(&market_id)?;
+        let idx = hist.partition_point(|(t, _)| *t <= ts);
+        idx.checked_sub(1).map(|i| hist[i].1)
+    }
+}
+
+pub async fn load_backtest_data(pool: &PgPool, cfg: &BacktestConfig) -> Result<BacktestData> {
+    let (from, to) = cfg.window();
+    let tokens: Option<Vec<String>> = cfg
+        .tokens
+        .as_ref()
+        .map(|v| v.iter().map(|t| t.trim().to_ascii_uppercase()).collect());
+    let exchanges: Option<Vec<String>> = cfg
+        .exchanges
+        .as_ref()
+        .map(|v| v.iter().map(|e| e.trim().to_ascii_lowercase()).collect());
+
+    let rows = sqlx::query!(
+        r#"
+        SELECT
+          date_bin(INTERVAL '8 hours', fr.timestamp, TIMESTAMPTZ 'epoch') AS "bucket!",
+          fr.market_id,
+          t.symbol  AS token,
+          e.name    AS exchange,
+          COALESCE(m.funding_interval_minutes, e.funding_interval_minutes) AS interval_minutes,
+          AVG(fr.rate)::float8 AS "rate!"
+        FROM funding_rates fr
+        JOIN markets   m ON m.id = fr.market_id
+        JOIN tokens    t ON t.id = m.token_id
+        JOIN exchanges e ON e.id = m.exchange_id
+        WHERE fr.timestamp >= $1 AND fr.timestamp < $2
+          AND ($3::text[] IS NULL OR t.symbol = ANY($3))
+          AND ($4::text[] IS NULL OR lower(e.name) = ANY($4))
+        GROUP BY 1, fr.market_id, t.symbol, e.name, interval_minutes
+        "#,
+        from,
+        to,
+        tokens.as_deref(),
+        exchanges.as_deref()
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut data = BacktestData::default();
+    let mut market_ids: Vec<i32> = Vec::new();
+    for r in rows {
+        let interval = r.interval_minutes.unwrap_or(DEFAULT_INTERVAL_MINUTES);
+        market_ids.push(r.market_id);
+        data.buckets
+            .entry(r.bucket)
+            .or_default()
+            .entry(r.token)
+            .or_default()
+            .push(Quote {
+                exchange: r.exchange,
+                market_id: r.market_id,
+                rate_8h: rates::convert(r.rate, interval, RateBasis::EightHour),
+            });
+    }
+    market_ids.sort_unstable();
+    market_ids.dedup();
+
+    // stats from a week before the window so the first fills have a liquidity estimate
+    let stats = sqlx::query!(
+        r#"
+        SELECT market_id, timestamp,
+               LEAST(open_interest, volume_24h)::float8 AS liquidity
+        FROM market_stats
+        WHERE market_id = ANY($1) AND timestamp >= $2 AND timestamp < $3
+        ORDER BY market_id, timestamp
+        "#,
+        &market_ids,
+        from - Duration::days(7),
+        to
+    )
+    .fetch_all(pool)
+    .await?;
+
+    for s in stats {
+        if let Some(l) = s.liquidity {
+            data.liquidity.entry(s.market_id).or_default().push((s.timestamp, l));
+        }
+    }
+
+    Ok(data)
+}
+
+/* ---------------- Simulation ---------------- */
+
+#[derive(Serialize, Debug, Clone)]
+pub struct Trade {
+    pub token: String,
+    pub long_exchange: String,
+    pub short_exchange: String,
+    #[serde(with = "time::serde::rfc3339")]
+    pub entry_ts: OffsetDateTime,
+    #[serde(with = "time::serde::rfc3339")]
+    pub exit_ts: OffsetDateTime,
+    pub periods: u32,
+    pub entry_spread_bps: f64,
+    pub exit_spread_bps: Option<f64>,
+    pub funding_pnl: f64,
+    pub fees: f64,
+    pub slippage: f64,
+    pub net_pnl: f64,
+    pub exit_reason: String,
+}
+
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct BacktestSummary {
+    pub trades: usize,
+    pub wins: usize,
+    pub win_rate: f64,
+    pub funding_pnl: f64,
+    pub fees: f64,
+    pub slippage: f64,
+    pub net_pnl: f64,
+    pub return_on_capital: f64, // net / (2 legs * notional * max_concurrent)
+    pub avg_hold_periods: f64,
+    pub max_drawdown: f64,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct BacktestReport {
+    #[serde(with = "time::serde::rfc3339")]
+    pub from: OffsetDateTime,
+    #[serde(with = "time::serde::rfc3339")]
+    pub to: OffsetDateTime,
+    pub config: BacktestConfig,
+    pub summary: BacktestSummary,
+    pub trades: Vec<Trade>,
+}
+
+#[derive(Debug)]
+struct Position {
+    token: String,
+    long: Quote,
+    short: Quote,
+    entry_ts: OffsetDateTime,
+    entry_spread_bps: f64,
+    last_spread_bps: Option<f64>,
+    periods: u32,
+    funding_pnl: f64,
+    fees: f64,
+    slippage: f64,
+}
+
+/// One fill on both legs: (fees, slippage) in quote currency.
+fn fill_costs(cfg: &BacktestConfig, data: &BacktestData, long: &Quote, short: &Quote, ts: OffsetDateTime) -> (f64, f64) {
+    let fees = cfg.notional * (cfg.fee_bps(&long.exchange) + cfg.fee_bps(&short.exchange)) / 10_000.0;
+    let slip = cfg.notional
+        * (cfg.slippage_bps(data.liquidity_at(long.market_id, ts))
+            + cfg.slippage_bps(data.liquidity_at(short.market_id, ts)))
+        / 10_000.0;
+    (fees, slip)
+}
+
+fn close(cfg: &BacktestConfig, data: &BacktestData, p: Position, ts: OffsetDateTime, reason: &str) -> Trade {
+    let (fees, slip) = fill_costs(cfg, data, &p.long, &p.short, ts);
+    let fees = p.fees + fees;
+    let slippage = p.slippage + slip;
+    Trade {
+        token: p.token,
+        long_exchange: p.long.exchange,
+        short_exchange: p.short.exchange,
+        entry_ts: p.entry_ts,
+        exit_ts: ts,
+        periods: p.periods,
+        entry_spread_bps: p.entry_spread_bps,
+        exit_spread_bps: p.last_spread_bps,
+        funding_pnl: p.funding_pnl,
+        fees,
+        slippage,
+        net_pnl: p.funding_pnl - fees - slippage,
+        exit_reason: reason.to_string(),
+    }
+}
+
+/// Widest (long = lowest rate, short = highest rate) pair for a token in one bucket.
+fn best_pair(quotes: &[Quote]) -> Option<(&Quote, &Quote)> {
+    let long = quotes.iter().min_by(|a, b| a.rate_8h.total_cmp(&b.rate_8h))?;
+    let short = quotes.iter().max_by(|a, b| a.rate_8h.total_cmp(&b.rate_8h))?;
+    (long.market_id != short.market_id).then_some((long, short))
+}
+
+/// Walk the 8h buckets in order: accrue funding on open positions, apply exit
+/// rules, then open the widest new spreads while capacity remains.
+pub fn run_backtest(data: &BacktestData, cfg: &BacktestConfig) -> BacktestReport {
+    let (from, to) = cfg.window();
+    let mut open: Vec<Position> = Vec::new();
+    let mut trades: Vec<Trade> = Vec::new();
+
+    let mut equity = 0.0_f64;
+    let mut peak = 0.0_f64;
+    let mut max_drawdown = 0.0_f64;
+
+    let last_bucket = data.buckets.keys().next_back().copied();
+
+    for (ts, by_token) in &data.buckets {
+        let ts = *ts;
+
+        // 1) funding for this bucket on positions opened earlier
+        for p in open.iter_mut() {
+            let find = |q: &Quote| {
+                by_token
+                    .get(&p.token)
+                    .and_then(|qs| qs.iter().find(|x| x.market_id == q.market_id))
+                    .map(|x| x.rate_8h)
+            };
+            if let (Some(l), Some(s)) = (find(&p.long), find(&p.short)) {
+                let pnl = cfg.notional * (s - l);
+                p.funding_pnl += pnl;
+                p.periods += 1;
+                p.last_spread_bps = Some((s - l) * 10_000.0);
+                equity += pnl;
+            }
+        }
+
+        // 2) exits
+        let mut still_open = Vec::with_capacity(open.len());
+        for p in open.drain(..) {
+            let held = p.periods >= cfg.min_hold_periods;
+            let reason = if cfg.max_hold_periods.is_some_and(|m| p.periods >= m) {
+                Some("max_hold")
+            } else if held && p.last_spread_bps.is_some_and(|s| s < cfg.exit_spread_bps) {
+                Some("spread_below_exit")
+            } else {
+                None
+            };
+            match reason {
+                Some(r) => {
+                    let entry_costs = p.fees + p.slippage;
+                    let t = close(cfg, data, p, ts, r);
+                    equity -= t.fees + t.slippage - entry_costs;
+                    trades.push(t);
+                }
+                None => still_open.push(p),
+            }
+        }
+        open = still_open;
+
+        // 3) entries, widest spread first (none on the last bucket: nothing left to accrue)
+        if Some(ts) == last_bucket {
+            peak = peak.max(equity);
+            max_drawdown = max_drawdown.max(peak - equity);
+            continue;
+        }
+        let mut candidates: Vec<(&String, &Quote, &Quote, f64)> = by_token
+            .iter()
+            .filter(|(token, _)| !open.iter().any(|p| &p.token == *token))
+            .filter_map(|(token, quotes)| {
+                let (l, s) = best_pair(quotes)?;
+                let spread_bps = (s.rate_8h - l.rate_8h) * 10_000.0;
+                (spread_bps >= cfg.entry_spread_bps).then_some((token, l, s, spread_bps))
+            })
+            .collect();
+        candidates.sort_by(|a, b| b.3.total_cmp(&a.3));
+
+        for (token, l, s, spread_bps) in candidates {
+            if open.len() >= cfg.max_concurrent {
+                break;
+            }
+            let (fees, slippage) = fill_costs(cfg, data, l, s, ts);
+            equity -= fees + slippage;
+            open.push(Position {
+                token: token.clone(),
+                long: l.clone(),
+                short: s.clone(),
+                entry_ts: ts,
+                entry_spread_bps: spread_bps,
+                last_spread_bps: Some(spread_bps),
+                periods: 0,
+                funding_pnl: 0.0,
+                fees,
+                slippage,
+            });
+        }
+
+        peak = peak.max(equity);
+        max_drawdown = max_drawdown.max(peak - equity);
+    }
+
+    // whatever is still open is closed on the last bucket, exit costs included
+    if let Some(last) = last_bucket {
+        for p in open.drain(..) {
+            let entry_costs = p.fees + p.slippage;
+            let t = close(cfg, data, p, last, "end_of_data");
+            equity -= t.fees + t.slippage - entry_costs;
+            trades.push(t);
+        }
+        peak = peak.max(equity);
+        max_drawdown = max_drawdown.max(peak - equity);
+    }
+
+    let mut summary = BacktestSummary {
+        trades: trades.len(),
+        wins: trades.iter().filter(|t| t.net_pnl > 0.0).count(),
+        funding_pnl: trades.iter().map(|t| t.funding_pnl).sum(),
+        fees: trades.iter().map(|t| t.fees).sum(),
+        slippage: trades.iter().map(|t| t.slippage).sum(),
+        net_pnl: trades.iter().map(|t| t.net_pnl).sum(),
+        max_drawdown,
+        ..Default::default()
+    };
+    if !trades.is_empty() {
+        summary.win_rate = summary.wins as f64 / trades.len() as f64;
+        summary.avg_hold_periods =
+            trades.iter().map(|t| t.periods as f64).sum::<f64>() / trades.len() as f64;
+    }
+    let capital = 2.0 * cfg.notional * cfg.max_concurrent.max(1) as f64;
+    summary.return_on_capital = summary.net_pnl / capital;
+
+    BacktestReport { from, to, config: cfg.clone(), summary, trades }
+}
+
+pub async fn backtest(pool: &PgPool, cfg: &BacktestConfig) -> Result<BacktestReport> {
+    let data = load_backtest_data(pool, cfg).await?;
+    Ok(run_backtest(&data, cfg))
+}
@@ -0,0 +1,168 @@
+// src/analytics/carry.rs
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use time::OffsetDateTime;
+
+use crate::analytics::rates::DEFAULT_INTERVAL_MINUTES;
+use crate::store::FundingStore;
+
+/// One market's funding, resolved to its native payment interval.
+#[derive(Debug, Clone)]
+pub struct FundingSeries {
+    pub exchange: String,
+    pub market_symbol: String,
+    pub funding_interval_minutes: i32,
+    /// (payment bucket start, average native rate in that bucket)
+    pub points: Vec<(OffsetDateTime, f64)>,
+}
+
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct LegSummary {
+    pub exchange: String,
+    pub market_symbol: String,
+    pub funding_interval_minutes: i32,
+    pub payments: usize,
+    pub received: f64, // sum of positive payments, quote currency
+    pub paid: f64,     // sum of negative payments, as a positive number
+    pub net: f64,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct CarryPoint {
+    #[serde(with = "time::serde::rfc3339")]
+    pub ts: OffsetDateTime,
+    pub long_rate: Option<f64>,
+    pub short_rate: Option<f64>,
+    pub long_pnl: f64,
+    pub short_pnl: f64,
+    pub net_pnl: f64,
+    pub cumulative: f64,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct CarryReport {
+    pub token: String,
+    pub notional: f64,
+    #[serde(with = "time::serde::rfc3339")]
+    pub from: OffsetDateTime,
+    #[serde(with = "time::serde::rfc3339")]
+    pub to: OffsetDateTime,
+    pub long: LegSummary,
+    pub short: LegSummary,
+    pub net_carry: f64,
+    pub net_carry_bps: f64, // net_carry / notional, in bps
+    pub apr: f64,           // net carry annualised over the requested range
+    pub points: Vec<CarryPoint>,
+}
+
+/// Stored funding for `token` on `exchange`, averaged into buckets of the market's
+/// native interval over `[from, to)`. A bucket still open at `to` is left out.
+pub async fn load_funding_series(
+    store: &dyn FundingStore,
+    token: &str,
+    exchange: &str,
+    from: OffsetDateTime,
+    to: OffsetDateTime,
+) -> Result<Option<FundingSeries>> {
+    let Some(market) = store.find_market(token, exchange).await? else {
+        return Ok(None);
+    };
+
+    let interval = market.funding_interval_minutes.unwrap_or(DEFAULT_INTERVAL_MINUTES);
+    let points = store.funding_history(market.id, interval, from, to).await?;
+
+    Ok(Some(FundingSeries {
+        exchange: market.exchange,
+        market_symbol: market.market_symbol,
+        funding_interval_minutes: interval,
+        points,
+    }))
+}
+
+/// Replay both legs: the long pays `rate * notional` per payment, the short receives it.
+pub fn replay_carry(long: &FundingSeries, short: &FundingSeries, notional: f64) -> (LegSummary, LegSummary, Vec<CarryPoint>) {
+    let mut events: BTreeMap<OffsetDateTime, (Option<f64>, Option<f64>)> = BTreeMap::new();
+    for (ts, r) in &long.points {
+        events.entry(*ts).or_default().0 = Some(*r);
+    }
+    for (ts, r) in &short.points {
+        events.entry(*ts).or_default().1 = Some(*r);
+    }
+
+    let mut long_sum = leg_summary(long);
+    let mut short_sum = leg_summary(short);
+    let mut cumulative = 0.0;
+    let mut points = Vec::with_capacity(events.len());
+
+    for (ts, (long_rate, short_rate)) in events {
+        let long_pnl = long_rate.map_or(0.0, |r| -r * notional);
+        let short_pnl = short_rate.map_or(0.0, |r| r * notional);
+        if long_rate.is_some() {
+            long_sum.add(long_pnl);
+        }
+        if short_rate.is_some() {
+            short_sum.add(short_pnl);
+        }
+        cumulative += long_pnl + short_pnl;
+        points.push(CarryPoint {
+            ts,
+            long_rate,
+            short_rate,
+            long_pnl,
+            short_pnl,
+            net_pnl: long_pnl + short_pnl,
+            cumulative,
+        });
+    }
+
+    (long_sum, short_sum, points)
+}
+
+fn leg_summary(s: &FundingSeries) -> LegSummary {
+    LegSummary {
+        exchange: s.exchange.clone(),
+        market_symbol: s.market_symbol.clone(),
+        funding_interval_minutes: s.funding_interval_minutes,
+        ..Default::default()
+    }
+}
+
+impl LegSummary {
+    fn add(&mut self, pnl: f64) {
+        self.payments += 1;
+        if pnl >= 0.0 {
+            self.received += pnl;
+        } else {
+            self.paid -= pnl;
+        }
+        self.net += pnl;
+    }
+}
+
+/// Historical carry of long `long` / short `short` over `[from, to)`.
+pub fn build_carry_report(
+    token: &str,
+    long: &FundingSeries,
+    short: &FundingSeries,
+    notional: f64,
+    from: OffsetDateTime,
+    to: OffsetDateTime,
+) -> CarryReport {
+    let (long_sum, short_sum, points) = replay_carry(long, short, notional);
+    let net_carry = long_sum.net + short_sum.net;
+    let years = (to - from).as_seconds_f64() / (365.0 * 86_400.0);
+
+    CarryReport {
+        token: token.to_ascii_uppercase(),
+        notional,
+        from,
+        to,
+        long: long_sum,
+        short: short_sum,
+        net_carry,
+        net_carry_bps: net_carry / notional * 10_000.0,
+        apr: if years > 0.0 { net_carry / notional / years } else { 0.0 },
+        points,
+    }
+}
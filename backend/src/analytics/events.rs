@@ -0,0 +1,241 @@
+// src/analytics/events.rs
+use anyhow::Result;
+use sqlx::PgPool;
+use time::{Duration, OffsetDateTime};
+
+use crate::analytics::rates::DEFAULT_INTERVAL_MINUTES;
+
+/// Detection thresholds, counted in the market's native funding intervals
+/// (8h on Paradex, 1h on Extended). Overridable with FUNDING_EVENT_* env vars.
+#[derive(Debug, Clone)]
+pub struct EventConfig {
+    pub zscore_threshold: f64,      // |z| at or above this is an outlier
+    pub history_periods: usize,     // rolling history the z-score is taken against
+    pub min_history_periods: usize, // skip z-scores on thin history
+    pub regime_periods: usize,      // N same-sign periods followed by N of the other sign
+}
+
+impl Default for EventConfig {
+    fn default() -> Self {
+        Self {
+            zscore_threshold: 2.5,
+            history_periods: 21, // a week of 8h payments
+            min_history_periods: 9,
+            regime_periods: 3,
+        }
+    }
+}
+
+impl EventConfig {
+    pub fn from_env() -> Self {
+        fn var<T: std::str::FromStr>(name: &str, default: T) -> T {
+            std::env::var(name).ok().and_then(|v| v.parse().ok()).unwrap_or(default)
+        }
+        let d = Self::default();
+        Self {
+            zscore_threshold: var("FUNDING_EVENT_ZSCORE", d.zscore_threshold),
+            history_periods: var("FUNDING_EVENT_HISTORY_PERIODS", d.history_periods).max(1),
+            min_history_periods: var("FUNDING_EVENT_MIN_PERIODS", d.min_history_periods),
+            regime_periods: var("FUNDING_EVENT_REGIME_PERIODS", d.regime_periods).max(1),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FundingEvent {
+    pub exchange_id: i32,
+    pub market_id: i32,
+    pub kind: &'static str, // sign_flip | zscore | regime_change
+    pub direction: &'static str,
+    pub timestamp: OffsetDateTime,
+    pub rate: f64,
+    pub prev_rate: Option<f64>,
+    pub zscore: Option<f64>,
+    pub detail: String,
+}
+
+fn direction(x: f64) -> &'static str {
+    if x >= 0.0 { "positive" } else { "negative" }
+}
+
+/// Events for one market's funding periods (ascending, one average rate per
+/// native interval). Only periods starting at or after `scan_from` can trigger;
+/// earlier ones serve as history.
+fn detect_market(
+    exchange_id: i32,
+    market_id: i32,
+    periods: &[(OffsetDateTime, f64)],
+    scan_from: OffsetDateTime,
+    cfg: &EventConfig,
+) -> Vec<FundingEvent> {
+    let mut out = Vec::new();
+    let n = cfg.regime_periods;
+
+    // running sums over the previous `history_periods` periods
+    let (mut lo, mut sum, mut sumsq) = (0usize, 0.0_f64, 0.0_f64);
+
+    for (i, &(ts, rate)) in periods.iter().enumerate() {
+        while i - lo > cfg.history_periods {
+            sum -= periods[lo].1;
+            sumsq -= periods[lo].1 * periods[lo].1;
+            lo += 1;
+        }
+        let count = i - lo;
+        let event = |kind, dir, prev_rate, zscore, detail| FundingEvent {
+            exchange_id,
+            market_id,
+            kind,
+            direction: dir,
+            timestamp: ts,
+            rate,
+            prev_rate,
+            zscore,
+            detail,
+        };
+
+        if ts >= scan_from {
+            // sign flip vs previous period
+            if let Some(&(_, prev)) = i.checked_sub(1).and_then(|j| periods.get(j))
+                && rate != 0.0
+                && prev != 0.0
+                && rate.signum() != prev.signum()
+            {
+                out.push(event(
+                    "sign_flip",
+                    direction(rate),
+                    Some(prev),
+                    None,
+                    format!("funding turned {} ({prev:.8} -> {rate:.8})", direction(rate)),
+                ));
+            }
+
+            // z-score vs rolling history
+            if count >= cfg.min_history_periods.max(2) {
+                let mean = sum / count as f64;
+                let var = (sumsq / count as f64 - mean * mean).max(0.0);
+                let sd = var.sqrt();
+                if sd > 1e-12 {
+                    let z = (rate - mean) / sd;
+                    if z.abs() >= cfg.zscore_threshold {
+                        out.push(event(
+                            "zscore",
+                            direction(z),
+                            None,
+                            Some(z),
+                            format!(
+                                "rate {rate:.8} is {z:.2} sd from its mean {mean:.8} over {count} periods"
+                            ),
+                        ));
+                    }
+                }
+            }
+
+            // regime change: n periods of one sign, then n of the other
+            if i + 1 >= 2 * n {
+                let recent = &periods[i + 1 - n..=i];
+                let before = &periods[i + 1 - 2 * n..i + 1 - n];
+                let all = |w: &[(OffsetDateTime, f64)], pos: bool| {
+                    w.iter().all(|&(_, r)| if pos { r > 0.0 } else { r < 0.0 })
+                };
+                let pos_now = rate > 0.0;
+                if all(recent, pos_now) && all(before, !pos_now) {
+                    let prev_mean = before.iter().map(|s| s.1).sum::<f64>() / n as f64;
+                    out.push(event(
+                        "regime_change",
+                        direction(rate),
+                        Some(prev_mean),
+                        None,
+                        format!(
+                            "{n} consecutive {} periods after {n} {}",
+                            direction(rate),
+                            direction(-rate)
+                        ),
+                    ));
+                }
+            }
+        }
+
+        sum += rate;
+        sumsq += rate * rate;
+    }
+    out
+}
+
+/// Scan the funding periods of the last `scan_hours` of every active market and
+/// store new events. Periods are averaged from `funding_rollup_1h` into each
+/// market's native interval; only closed periods count. Safe to re-run over
+/// overlapping windows. Returns rows inserted.
+pub async fn detect_funding_events(pool: &PgPool, cfg: &EventConfig, scan_hours: i64) -> Result<u64> {
+    let now = OffsetDateTime::now_utc();
+    let scan_from = now - Duration::hours(scan_hours);
+
+    let rows = sqlx::query!(
+        r#"
+        WITH mk AS (
+          SELECT m.id, m.exchange_id,
+                 make_interval(mins => COALESCE(m.funding_interval_minutes, e.funding_interval_minutes, $3)) AS iv
+          FROM markets m
+          JOIN exchanges e ON e.id = m.exchange_id
+          WHERE m.is_active = true
+        )
+        SELECT mk.exchange_id, r.market_id,
+               date_bin(mk.iv, r.bucket_start, TIMESTAMPTZ 'epoch') AS "timestamp!",
+               (SUM(r.rate_sum) / SUM(r.samples))::float8 AS "rate!"
+        FROM funding_rollup_1h r
+        JOIN mk ON mk.id = r.market_id
+        WHERE r.bucket_start >= date_bin(mk.iv, $1, TIMESTAMPTZ 'epoch') - mk.iv * $2
+        GROUP BY mk.exchange_id, r.market_id, mk.iv, 3
+        HAVING date_bin(mk.iv, r.bucket_start, TIMESTAMPTZ 'epoch') + mk.iv <= $4
+        ORDER BY r.market_id, 3
+        "#,
+        scan_from,
+        cfg.history_periods as f64,
+        DEFAULT_INTERVAL_MINUTES,
+        now
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut events: Vec<FundingEvent> = Vec::new();
+    let mut start = 0;
+    while start < rows.len() {
+        let market_id = rows[start].market_id;
+        let end = start + rows[start..].iter().take_while(|r| r.market_id == market_id).count();
+        let periods: Vec<(OffsetDateTime, f64)> =
+            rows[start..end].iter().map(|r| (r.timestamp, r.rate)).collect();
+        events.extend(detect_market(rows[start].exchange_id, market_id, &periods, scan_from, cfg));
+        start = end;
+    }
+
+    if events.is_empty() {
+        return Ok(0);
+    }
+
+    let mut inserted = 0;
+    for chunk in events.chunks(5_000) {
+        let res = sqlx::query!(
+            r#"
+            INSERT INTO funding_events
+              (exchange_id, market_id, kind, direction, timestamp, rate, prev_rate, zscore, detail)
+            SELECT e, m, k, d, ts, r::numeric, p::numeric, z, dt
+            FROM UNNEST($1::int4[], $2::int4[], $3::text[], $4::text[], $5::timestamptz[],
+                        $6::float8[], $7::float8[], $8::float8[], $9::text[])
+                 AS x(e, m, k, d, ts, r, p, z, dt)
+            ON CONFLICT (market_id, kind, timestamp) DO NOTHING
+            "#,
+            &chunk.iter().map(|e| e.exchange_id).collect::<Vec<_>>(),
+            &chunk.iter().map(|e| e.market_id).collect::<Vec<_>>(),
+            &chunk.iter().map(|e| e.kind.to_string()).collect::<Vec<_>>(),
+            &chunk.iter().map(|e| e.direction.to_string()).collect::<Vec<_>>(),
+            &chunk.iter().map(|e| e.timestamp).collect::<Vec<_>>(),
+            &chunk.iter().map(|e| e.rate).collect::<Vec<_>>(),
+            &chunk.iter().map(|e| e.prev_rate).collect::<Vec<_>>() as &[Option<f64>],
+            &chunk.iter().map(|e| e.zscore).collect::<Vec<_>>() as &[Option<f64>],
+            &chunk.iter().map(|e| e.detail.clone()).collect::<Vec<_>>()
+        )
+        .execute(pool)
+        .await?;
+        inserted += res.rows_affected();
+    }
+    Ok(inserted)
+}
@@ -0,0 +1,6 @@
+pub mod rates;
+pub mod rolling;
+pub mod carry;
+pub mod backtest;
+pub mod events;
+pub mod prices;
\ No newline at end of file
@@ -0,0 +1,22 @@
+// src/analytics/prices.rs
+
+/// Premium of mark over index in bps: (mark - index) / index. Positive means
+/// the perp trades rich, which is what positive funding pays down.
+pub fn premium_bps(mark: Option<f64>, index: Option<f64>) -> Option<f64> {
+    match (mark, index) {
+        (Some(m), Some(i)) if i > 0.0 => Some((m - i) / i * 10_000.0),
+        _ => None,
+    }
+}
+
+/// Widest cross-venue gap between prices of the same token in bps of the lowest:
+/// (max - min) / min. Needs at least two venues with a price.
+pub fn divergence_bps(prices: impl IntoIterator<Item = f64>) -> Option<f64> {
+    let (n, lo, hi) = prices
+        .into_iter()
+        .filter(|p| p.is_finite() && *p > 0.0)
+        .fold((0usize, f64::INFINITY, f64::NEG_INFINITY), |(n, lo, hi), p| {
+            (n + 1, lo.min(p), hi.max(p))
+        });
+    (n >= 2).then(|| (hi - lo) / lo * 10_000.0)
+}
@@ -0,0 +1,63 @@
+// src/analytics/rates.rs
+use serde::{Deserialize, Serialize};
+
+/// Native interval assumed when neither the market nor the exchange carries one.
+pub const DEFAULT_INTERVAL_MINUTES: i32 = 480;
+
+const HOURS_PER_DAY: f64 = 24.0;
+const DAYS_PER_YEAR: f64 = 365.0;
+
+/// Time basis a funding rate is expressed in.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RateBasis {
+    #[serde(rename = "hourly")]
+    Hourly,
+    #[default]
+    #[serde(rename = "8h")]
+    EightHour,
+    #[serde(rename = "daily")]
+    Daily,
+    #[serde(rename = "apr")]
+    Apr,
+}
+
+impl RateBasis {
+    pub fn hours(self) -> f64 {
+        match self {
+            RateBasis::Hourly => 1.0,
+            RateBasis::EightHour => 8.0,
+            RateBasis::Daily => HOURS_PER_DAY,
+            RateBasis::Apr => HOURS_PER_DAY * DAYS_PER_YEAR,
+        }
+    }
+}
+
+/// A native per-interval rate restated on every basis we expose.
+/// APR is simple (non-compounded): hourly * 24 * 365.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct RateBases {
+    pub hourly: f64,
+    pub rate_8h: f64,
+    pub daily: f64,
+    pub apr: f64,
+}
+
+/// Rate per hour for a rate paid every `interval_minutes`.
+pub fn hourly_rate(rate: f64, interval_minutes: i32) -> f64 {
+    let minutes = if interval_minutes > 0 { interval_minutes } else { DEFAULT_INTERVAL_MINUTES };
+    rate * 60.0 / minutes as f64
+}
+
+pub fn convert(rate: f64, interval_minutes: i32, basis: RateBasis) -> f64 {
+    hourly_rate(rate, interval_minutes) * basis.hours()
+}
+
+pub fn all_bases(rate: f64, interval_minutes: i32) -> RateBases {
+    let hourly = hourly_rate(rate, interval_minutes);
+    RateBases {
+        hourly,
+        rate_8h: hourly * RateBasis::EightHour.hours(),
+        daily: hourly * RateBasis::Daily.hours(),
+        apr: hourly * RateBasis::Apr.hours(),
+    }
+}
@@ -0,0 +1,123 @@
+// src/analytics/rolling.rs
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+
+use crate::analytics::rates::{self, RateBasis, DEFAULT_INTERVAL_MINUTES};
+
+/// (label, hours) of every rolling window we report, shortest first.
+pub const WINDOWS: [(&str, i32); 4] = [("24h", 24), ("3d", 72), ("7d", 168), ("30d", 720)];
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RollingWindow {
+    pub window: String,
+    pub hours: i32,
+    pub mean: f64,
+    pub median: f64,
+    pub stdev: Option<f64>, // None with a single hour
+    pub min: f64,
+    pub max: f64,
+    pub samples: i64,
+}
+
+impl RollingWindow {
+    /// Restate every rate statistic by a positive factor (basis conversion).
+    fn scaled(mut self, factor: f64) -> Self {
+        self.mean *= factor;
+        self.median *= factor;
+        self.stdev = self.stdev.map(|s| s * factor);
+        self.min *= factor;
+        self.max *= factor;
+        self
+    }
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct MarketRolling {
+    pub market_id: i32,
+    pub exchange: String,
+    pub market_symbol: String,
+    pub token: String,
+    pub funding_interval_minutes: i32,
+    pub windows: Vec<RollingWindow>,
+}
+
+/// Rolling funding statistics per active market over every window in `WINDOWS`,
+/// converted to `basis`. `tokens` limits it to those tokens (case-insensitive).
+///
+/// Read from `funding_rollup_1h`, so no raw ticks are scanned: mean, min, max
+/// and sample count are exact over the hourly buckets starting in the window;
+/// median and stdev are taken over the hourly averages.
+pub async fn rolling_stats(
+    pool: &PgPool,
+    tokens: Option<&[String]>,
+    basis: RateBasis,
+) -> Result<Vec<MarketRolling>> {
+    let labels: Vec<String> = WINDOWS.iter().map(|(l, _)| l.to_string()).collect();
+    let hours: Vec<i32> = WINDOWS.iter().map(|(_, h)| *h).collect();
+
+    let rows = sqlx::query!(
+        r#"
+        SELECT
+          m.id                 AS "market_id!",
+          e.name               AS "exchange!",
+          m.market_symbol      AS "market_symbol!",
+          t.symbol             AS "token!",
+          COALESCE(m.funding_interval_minutes, e.funding_interval_minutes) AS interval_minutes,
+          w.label              AS "window!",
+          w.hours              AS "hours!",
+          (SUM(r.rate_sum) / SUM(r.samples))::float8 AS "mean!",
+          (percentile_cont(0.5) WITHIN GROUP (ORDER BY r.rate_sum / r.samples))::float8 AS "median!",
+          stddev_samp(r.rate_sum / r.samples)::float8 AS stdev,
+          MIN(r.rate_min)::float8 AS "min!",
+          MAX(r.rate_max)::float8 AS "max!",
+          SUM(r.samples)::int8 AS "samples!"
+        FROM markets m
+        JOIN tokens    t ON t.id = m.token_id
+        JOIN exchanges e ON e.id = m.exchange_id
+        CROSS JOIN UNNEST($1::text[], $2::int4[]) AS w(label, hours)
+        JOIN funding_rollup_1h r
+          ON r.market_id = m.id
+         AND r.bucket_start >= NOW() - make_interval(hours => w.hours)
+        WHERE m.is_active = true
+          AND ($3::text[] IS NULL OR t.symbol = ANY(SELECT upper(x) FROM UNNEST($3::text[]) x))
+        GROUP BY m.id, e.name, m.market_symbol, t.symbol, interval_minutes, w.label, w.hours
+        ORDER BY e.name, m.market_symbol, w.hours
+        "#,
+        &labels,
+        &hours,
+        tokens
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut out: Vec<MarketRolling> = Vec::new();
+    for r in rows {
+        let interval = r.interval_minutes.unwrap_or(DEFAULT_INTERVAL_MINUTES);
+        let window = RollingWindow {
+            window: r.window,
+            hours: r.hours,
+            mean: r.mean,
+            median: r.median,
+            stdev: r.stdev,
+            min: r.min,
+            max: r.max,
+            samples: r.samples,
+        }
+        .scaled(rates::convert(1.0, interval, basis));
+
+        match out.last_mut() {
+            Some(last) if last.market_id == r.market_id => last.windows.push(window),
+            _ => out.push(MarketRolling {
+                market_id: r.market_id,
+                exchange: r.exchange,
+                market_symbol: r.market_symbol,
+                token: r.token,
+                funding_interval_minutes: interval,
+                windows: vec![window],
+            }),
+        }
+    }
+
+    Ok(out)
+}
@@ -0,0 +1,58 @@
+// src/api/alerts.rs
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json,
+};
+use sqlx::PgPool;
+
+use super::ApiError;
+use crate::alerts::rules::{self, AlertRule, AlertRuleInput};
+
+fn not_found(id: i32) -> ApiError {
+    ApiError::not_found(format!("alert rule {id} not found"))
+}
+
+/// `GET /api/alerts/rules`
+pub async fn list_rules(State(pool): State<PgPool>) -> Result<Json<Vec<AlertRule>>, ApiError> {
+    Ok(Json(rules::list_rules(&pool, false).await?))
+}
+
+/// `POST /api/alerts/rules`
+pub async fn create_rule(
+    State(pool): State<PgPool>,
+    Json(input): Json<AlertRuleInput>,
+) -> Result<(StatusCode, Json<AlertRule>), ApiError> {
+    input.validate().map_err(ApiError::bad_request)?;
+    Ok((StatusCode::CREATED, Json(rules::create_rule(&pool, &input).await?)))
+}
+
+/// `GET /api/alerts/rules/{id}`
+pub async fn get_rule(
+    State(pool): State<PgPool>,
+    Path(id): Path<i32>,
+) -> Result<Json<AlertRule>, ApiError> {
+    rules::get_rule(&pool, id).await?.map(Json).ok_or_else(|| not_found(id))
+}
+
+/// `PUT /api/alerts/rules/{id}` — full replace; evaluation state is kept.
+pub async fn update_rule(
+    State(pool): State<PgPool>,
+    Path(id): Path<i32>,
+    Json(input): Json<AlertRuleInput>,
+) -> Result<Json<AlertRule>, ApiError> {
+    input.validate().map_err(ApiError::bad_request)?;
+    rules::update_rule(&pool, id, &input).await?.map(Json).ok_or_else(|| not_found(id))
+}
+
+/// `DELETE /api/alerts/rules/{id}`
+pub async fn delete_rule(
+    State(pool): State<PgPool>,
+    Path(id): Path<i32>,
+) -> Result<StatusCode, ApiError> {
+    if rules::delete_rule(&pool, id).await? {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(not_found(id))
+    }
+}
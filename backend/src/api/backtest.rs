@@ -0,0 +1,16 @@
+// src/api/backtest.rs
+use axum::{extract::State, response::Json};
+use sqlx::PgPool;
+
+use super::ApiError;
+use crate::analytics::backtest::{backtest, BacktestConfig, BacktestReport};
+
+/// `POST /api/backtest` — run the spread strategy over stored history.
+/// Body is a `BacktestConfig`; omitted fields take their defaults.
+pub async fn post_backtest(
+    State(pool): State<PgPool>,
+    Json(cfg): Json<BacktestConfig>,
+) -> Result<Json<BacktestReport>, ApiError> {
+    cfg.validate().map_err(ApiError::bad_request)?;
+    Ok(Json(backtest(&pool, &cfg).await?))
+}
@@ -0,0 +1,58 @@
+// src/api/carry.rs
+use axum::{
+    extract::{Query, State},
+    response::Json,
+};
+use serde::Deserialize;
+use time::{Duration, OffsetDateTime};
+
+use super::{parse_ts, ApiError, AppState};
+use crate::analytics::carry::{build_carry_report, load_funding_series, CarryReport};
+
+const DEFAULT_LOOKBACK_DAYS: i64 = 7;
+
+#[derive(Deserialize, Debug)]
+pub struct CarryQuery {
+    token: String,
+    long: String,  // exchange holding the long leg
+    short: String, // exchange holding the short leg
+    notional: f64, // per leg, quote currency
+    from: Option<String>, // unix ms or RFC 3339; default 7 days before `to`
+    to: Option<String>,   // default now
+}
+
+/// `GET /api/carry` — replay stored funding for a long/short pair and return the
+/// cumulative funding per leg, net carry and the per-interval PnL series.
+pub async fn get_carry(
+    State(state): State<AppState>,
+    Query(q): Query<CarryQuery>,
+) -> Result<Json<CarryReport>, ApiError> {
+    if q.long.eq_ignore_ascii_case(&q.short) {
+        return Err(ApiError::bad_request("long and short must be different exchanges"));
+    }
+    if !(q.notional.is_finite() && q.notional > 0.0) {
+        return Err(ApiError::bad_request("notional must be positive"));
+    }
+
+    let to = match q.to.as_deref() {
+        Some(s) => parse_ts("to", s)?,
+        None => OffsetDateTime::now_utc(),
+    };
+    let from = match q.from.as_deref() {
+        Some(s) => parse_ts("from", s)?,
+        None => to - Duration::days(DEFAULT_LOOKBACK_DAYS),
+    };
+    if from >= to {
+        return Err(ApiError::bad_request("from must be before to"));
+    }
+
+    let token = q.token.trim().to_ascii_uppercase();
+    let long = load_funding_series(&*state.store, &token, &q.long, from, to)
+        .await?
+        .ok_or_else(|| ApiError::not_found(format!("no {} market on {}", token, q.long)))?;
+    let short = load_funding_series(&*state.store, &token, &q.short, from, to)
+        .await?
+        .ok_or_else(|| ApiError::not_found(format!("no {} market on {}", token, q.short)))?;
+
+    Ok(Json(build_carry_report(&token, &long, &short, q.notional, from, to)))
+}
@@ -0,0 +1,82 @@
+// src/api/events.rs
+use axum::{
+    extract::{Query, State},
+    response::Json,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use time::OffsetDateTime;
+
+use super::{parse_ts, ApiError};
+
+const DEFAULT_LIMIT: i64 = 100;
+const MAX_LIMIT: i64 = 1000;
+
+#[derive(Deserialize, Debug)]
+pub struct EventsQuery {
+    exchange: Option<String>,
+    token: Option<String>,
+    kind: Option<String>, // sign_flip | zscore | regime_change
+    since: Option<String>, // unix ms or RFC 3339
+    limit: Option<i64>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct EventRow {
+    id: i64,
+    exchange: String,
+    token: String,
+    market_symbol: String,
+    kind: String,
+    direction: String,
+    #[serde(with = "time::serde::rfc3339")]
+    timestamp: OffsetDateTime,
+    rate: f64,
+    prev_rate: Option<f64>,
+    zscore: Option<f64>,
+    detail: String,
+}
+
+/// `GET /api/events` — newest funding events first.
+pub async fn get_events(
+    State(pool): State<PgPool>,
+    Query(q): Query<EventsQuery>,
+) -> Result<Json<Vec<EventRow>>, ApiError> {
+    if let Some(k) = q.kind.as_deref()
+        && !matches!(k, "sign_flip" | "zscore" | "regime_change")
+    {
+        return Err(ApiError::bad_request(format!(
+            "invalid kind '{k}': expected sign_flip, zscore or regime_change"
+        )));
+    }
+    let since = q.since.as_deref().map(|s| parse_ts("since", s)).transpose()?;
+    let limit = q.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+
+    let rows = sqlx::query_as!(
+        EventRow,
+        r#"
+        SELECT fe.id, e.name AS exchange, t.symbol AS token, m.market_symbol,
+               fe.kind, fe.direction, fe.timestamp,
+               fe.rate::float8 AS "rate!", fe.prev_rate::float8 AS prev_rate, fe.zscore, fe.detail
+        FROM funding_events fe
+        JOIN markets   m ON m.id = fe.market_id
+        JOIN tokens    t ON t.id = m.token_id
+        JOIN exchanges e ON e.id = fe.exchange_id
+        WHERE ($1::text IS NULL OR lower(e.name) = lower($1))
+          AND ($2::text IS NULL OR t.symbol = upper($2))
+          AND ($3::text IS NULL OR fe.kind = $3)
+          AND ($4::timestamptz IS NULL OR fe.timestamp >= $4)
+        ORDER BY fe.timestamp DESC, fe.id DESC
+        LIMIT $5
+        "#,
+        q.exchange.as_deref().map(str::trim),
+        q.token.as_deref().map(str::trim),
+        q.kind,
+        since,
+        limit
+    )
+    .fetch_all(&pool)
+    .await?;
+
+    Ok(Json(rows))
+}
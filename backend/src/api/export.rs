@@ -0,0 +1,90 @@
+// src/api/export.rs
+use axum::{
+    extract::{Query, State},
+    http::header,
+    response::{IntoResponse, Response},
+};
+use serde::Deserialize;
+use sqlx::PgPool;
+use time::{Duration, OffsetDateTime};
+
+use super::{parse_ts, ApiError};
+use crate::db::export::{self, CsvSink, Dataset, ExportFilter, ExportFormat, ParquetSink};
+
+const DEFAULT_DAYS: i64 = 7;
+/// Larger exports belong in `sync export`, which streams to disk.
+const MAX_ROWS: i64 = 1_000_000;
+
+#[derive(Deserialize, Debug)]
+pub struct ExportQuery {
+    dataset: Option<String>, // funding (default) | funding_8h | stats
+    format: Option<String>,  // csv (default) | parquet
+    token: Option<String>,
+    exchange: Option<String>,
+    from: Option<String>, // unix ms or RFC 3339; default `to` - 7 days
+    to: Option<String>,   // default now
+}
+
+/// `GET /api/export` — one dataset as a CSV or single Parquet file download.
+pub async fn get_export(
+    State(pool): State<PgPool>,
+    Query(q): Query<ExportQuery>,
+) -> Result<Response, ApiError> {
+    let bad = |e: anyhow::Error| ApiError::bad_request(e.to_string());
+    let dataset = match q.dataset.as_deref() {
+        Some(d) => d.parse::<Dataset>().map_err(bad)?,
+        None => Dataset::Funding,
+    };
+    let format = match q.format.as_deref() {
+        Some(f) => f.parse::<ExportFormat>().map_err(bad)?,
+        None => ExportFormat::Csv,
+    };
+    let to = q.to.as_deref().map(|s| parse_ts("to", s)).transpose()?.unwrap_or_else(OffsetDateTime::now_utc);
+    let from = q.from.as_deref().map(|s| parse_ts("from", s)).transpose()?.unwrap_or(to - Duration::days(DEFAULT_DAYS));
+    if from >= to {
+        return Err(ApiError::bad_request("from must be before to"));
+    }
+
+    let filter = ExportFilter {
+        token: q.token,
+        exchange: q.exchange,
+        from: Some(from),
+        to: Some(to),
+        limit: Some(MAX_ROWS + 1),
+    };
+    // Refuse before building anything in memory
+    if export::count(&pool, dataset, &filter).await? > MAX_ROWS {
+        return Err(too_many_rows());
+    }
+    let columns = dataset.columns();
+    let (body, rows, content_type, ext) = match format {
+        ExportFormat::Csv => {
+            let mut sink = CsvSink::new(Vec::new(), columns)?;
+            let rows = export::export(&pool, dataset, &filter, &mut sink).await?;
+            (sink.finish()?, rows, "text/csv; charset=utf-8", "csv")
+        }
+        ExportFormat::Parquet => {
+            let mut sink = ParquetSink::new(Vec::new(), columns)?;
+            let rows = export::export(&pool, dataset, &filter, &mut sink).await?;
+            (sink.finish()?, rows, "application/vnd.apache.parquet", "parquet")
+        }
+    };
+    // Rows may have landed since the count
+    if rows > MAX_ROWS as u64 {
+        return Err(too_many_rows());
+    }
+
+    let filename = format!("{}_{}_{}.{ext}", dataset.name(), from.date(), to.date());
+    Ok((
+        [
+            (header::CONTENT_TYPE, content_type.to_string()),
+            (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{filename}\"")),
+        ],
+        body,
+    )
+        .into_response())
+}
+
+fn too_many_rows() -> ApiError {
+    ApiError::bad_request(format!("more than {MAX_ROWS} rows; narrow from/to/token/exchange or use `sync export`"))
+}
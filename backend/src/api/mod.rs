@@ -0,0 +1,74 @@
+// src/api/mod.rs
+pub mod tokens;
+pub mod carry;
+pub mod backtest;
+pub mod alerts;
+pub mod events;
+pub mod export;
+
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Json, Response},
+};
+use serde_json::json;
+use sqlx::PgPool;
+use std::sync::Arc;
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+use tracing::error;
+
+use crate::store::FundingStore;
+
+/// State of the routes every storage backend can serve. `pool` is only set on
+/// Postgres; the routes registered with a bare `PgPool` need it outright.
+#[derive(Clone)]
+pub struct AppState {
+    pub store: Arc<dyn FundingStore>,
+    pub pool: Option<PgPool>,
+}
+
+pub fn fmt_ts(ts: OffsetDateTime) -> String {
+    ts.format(&Rfc3339).unwrap_or_else(|_| ts.to_string())
+}
+
+/// Query-string timestamp: unix milliseconds (like the sync CLI) or RFC 3339.
+pub fn parse_ts(name: &str, s: &str) -> Result<OffsetDateTime, ApiError> {
+    let s = s.trim();
+    let parsed = match s.parse::<i64>() {
+        Ok(ms) => OffsetDateTime::from_unix_timestamp_nanos(ms as i128 * 1_000_000).ok(),
+        Err(_) => OffsetDateTime::parse(s, &Rfc3339).ok(),
+    };
+    parsed.ok_or_else(|| ApiError::bad_request(format!("invalid {name} '{s}': expected unix ms or RFC 3339")))
+}
+
+/// Error body for JSON endpoints: `{"error": "..."}` with the given status.
+#[derive(Debug)]
+pub struct ApiError(pub StatusCode, pub String);
+
+impl ApiError {
+    pub fn not_found(msg: impl Into<String>) -> Self {
+        Self(StatusCode::NOT_FOUND, msg.into())
+    }
+
+    pub fn bad_request(msg: impl Into<String>) -> Self {
+        Self(StatusCode::BAD_REQUEST, msg.into())
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.0, Json(json!({ "error": self.1 }))).into_response()
+    }
+}
+
+impl From<anyhow::Error> for ApiError {
+    fn from(e: anyhow::Error) -> Self {
+        error!("api error: {e:?}");
+        Self(StatusCode::INTERNAL_SERVER_ERROR, "internal error".to_string())
+    }
+}
+
+impl From<sqlx::Error> for ApiError {
+    fn from(e: sqlx::Error) -> Self {
+        anyhow::Error::from(e).into()
+    }
+}
@@ -0,0 +1,121 @@
+// src/api/tokens.rs
+use axum::{
+    extract::{Path, Query, State},
+    response::Json,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::collections::BTreeMap;
+
+use super::{fmt_ts, ApiError};
+use crate::analytics::prices;
+use crate::analytics::rates::{self, RateBasis, DEFAULT_INTERVAL_MINUTES};
+use crate::analytics::rolling::{rolling_stats, RollingWindow};
+
+#[derive(Deserialize, Debug, Default)]
+pub struct TokenQuery {
+    basis: Option<RateBasis>, // hourly | 8h | daily | apr (default 8h)
+}
+
+#[derive(Serialize, Debug)]
+pub struct TokenExchange {
+    market_symbol: String,
+    funding_interval_minutes: i32,
+    funding_rate: Option<f64>, // latest 8h bucket, in `basis`
+    funding_ts: Option<String>,
+    open_interest: Option<f64>,
+    volume_24h: Option<f64>,
+    stats_ts: Option<String>,
+    mark_price: Option<f64>,
+    index_price: Option<f64>,
+    last_price: Option<f64>,
+    premium_bps: Option<f64>, // (mark - index) / index
+    price_ts: Option<String>,
+    rolling: Vec<RollingWindow>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct TokenDetail {
+    token: String,
+    basis: RateBasis,
+    price_divergence_bps: Option<f64>, // widest mark-price gap across venues
+    exchanges: BTreeMap<String, TokenExchange>,
+}
+
+/// `GET /api/tokens/{symbol}` — latest values plus rolling funding statistics per venue.
+pub async fn get_token_detail(
+    State(pool): State<PgPool>,
+    Path(symbol): Path<String>,
+    Query(q): Query<TokenQuery>,
+) -> Result<Json<TokenDetail>, ApiError> {
+    let basis = q.basis.unwrap_or_default();
+    let token = symbol.trim().to_ascii_uppercase();
+
+    let markets = sqlx::query!(
+        r#"
+        SELECT
+          e.name          AS "exchange!",
+          m.market_symbol AS "market_symbol!",
+          COALESCE(m.funding_interval_minutes, e.funding_interval_minutes) AS interval_minutes,
+          lfr.rate_8h::float8 AS funding_rate,
+          lfr.timestamp   AS "funding_ts?",
+          lms.open_interest::float8 AS open_interest,
+          lms.volume_24h::float8    AS volume_24h,
+          lms.timestamp   AS "stats_ts?",
+          lps.mark_price::float8  AS mark_price,
+          lps.index_price::float8 AS index_price,
+          lps.last_price::float8  AS last_price,
+          lps.timestamp   AS "price_ts?"
+        FROM markets m
+        JOIN tokens    t ON t.id = m.token_id
+        JOIN exchanges e ON e.id = m.exchange_id
+        LEFT JOIN latest_funding_8h_view   lfr ON lfr.market_id = m.id
+        LEFT JOIN latest_market_stats_view lms ON lms.market_id = m.id
+        LEFT JOIN latest_price_snapshots_view lps ON lps.market_id = m.id
+        WHERE m.is_active = true AND t.symbol = $1
+        "#,
+        token
+    )
+    .fetch_all(&pool)
+    .await?;
+
+    if markets.is_empty() {
+        return Err(ApiError::not_found(format!("unknown token '{token}'")));
+    }
+
+    let mut rolling: BTreeMap<(String, String), Vec<RollingWindow>> = rolling_stats(&pool, Some(std::slice::from_ref(&token)), basis)
+        .await?
+        .into_iter()
+        .map(|m| ((m.exchange, m.market_symbol), m.windows))
+        .collect();
+
+    let price_divergence_bps = prices::divergence_bps(markets.iter().filter_map(|m| m.mark_price));
+
+    let exchanges = markets
+        .into_iter()
+        .map(|m| {
+            let interval = m.interval_minutes.unwrap_or(DEFAULT_INTERVAL_MINUTES);
+            let windows = rolling
+                .remove(&(m.exchange.clone(), m.market_symbol.clone()))
+                .unwrap_or_default();
+            let entry = TokenExchange {
+                market_symbol: m.market_symbol,
+                funding_interval_minutes: interval,
+                funding_rate: m.funding_rate.map(|r| rates::convert(r, interval, basis)),
+                funding_ts: m.funding_ts.map(fmt_ts),
+                open_interest: m.open_interest,
+                volume_24h: m.volume_24h,
+                stats_ts: m.stats_ts.map(fmt_ts),
+                mark_price: m.mark_price,
+                index_price: m.index_price,
+                last_price: m.last_price,
+                premium_bps: prices::premium_bps(m.mark_price, m.index_price),
+                price_ts: m.price_ts.map(fmt_ts),
+                rolling: windows,
+            };
+            (m.exchange, entry)
+        })
+        .collect();
+
+    Ok(Json(TokenDetail { token, basis, price_divergence_bps, exchanges }))
+}
@@ -0,0 +1,414 @@
+// backend/src/backfill.rs
+// Resumable funding backfills for `sync backfill`. A job resolves every
+// market's window once (the same plan `--dry-run` prints) and stores it in
+// `backfill_checkpoints`; markets are then fetched chunk by chunk and page by
+// page, and each page is written in the same transaction that advances its
+// checkpoint. A job runs in one process at a time; a killed or failed job
+// continues from its checkpoints with `sync backfill --resume <id>`.
+// Only the sync CLI uses this.
+use anyhow::{anyhow, bail, Context, Result};
+use futures::{stream, StreamExt};
+use serde::Serialize;
+use sqlx::PgPool;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+use time::OffsetDateTime;
+use tracing::{info, warn};
+
+use crate::data::filter::MarketFilter;
+use crate::data::funding::{
+    fetch_funding_page, funding_chunks, funding_source, plan_funding_for_exchange, TimeSpec, MAX_PAGES_PER_WINDOW,
+};
+use crate::db::insert;
+use crate::store::PgStore;
+
+/// Minimum time between progress lines.
+const PROGRESS_EVERY: Duration = Duration::from_secs(10);
+
+/// First key of the advisory lock `run_job` holds on a job (the second is the job id).
+const JOB_LOCK_CLASS: i32 = 0x6266_6a62;
+
+/// One job, with its checkpoints rolled up.
+#[derive(Debug, Clone, Serialize)]
+pub struct JobSummary {
+    pub id: i64,
+    pub exchange: String,
+    pub status: String,
+    pub error: Option<String>,
+    pub markets: i64,
+    pub markets_done: i64,
+    pub rows: i64,
+    pub requests: i64,
+    /// Share of the total window already written, 0..=1.
+    pub progress: f64,
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: OffsetDateTime,
+    #[serde(with = "time::serde::rfc3339")]
+    pub updated_at: OffsetDateTime,
+}
+
+struct Checkpoint {
+    market_id: i32,
+    market_symbol: String,
+    end_ms: u64,
+    cursor_ms: u64,
+    page_cursor: Option<String>,
+}
+
+/// Shared counters for the progress / ETA line.
+struct Progress {
+    job_id: i64,
+    markets: usize,
+    total_ms: u64,
+    /// Window already written when this run started (resumed jobs).
+    done_at_start: u64,
+    done_ms: AtomicU64,
+    markets_done: AtomicUsize,
+    rows: AtomicU64,
+    requests: AtomicU64,
+    started: Instant,
+    last_logged_ms: AtomicU64,
+}
+
+impl Progress {
+    fn line(&self) -> String {
+        let done = self.done_ms.load(Ordering::Relaxed);
+        let pct = if self.total_ms == 0 { 100.0 } else { done as f64 * 100.0 / self.total_ms as f64 };
+        let gained = done.saturating_sub(self.done_at_start);
+        let eta = if done >= self.total_ms {
+            "0s".to_string()
+        } else if gained == 0 {
+            "unknown".to_string()
+        } else {
+            let secs = self.started.elapsed().as_secs_f64() * (self.total_ms - done) as f64 / gained as f64;
+            fmt_secs(secs as u64)
+        };
+        format!(
+            "backfill #{}: {:.1}% ({}/{} markets, {} rows, {} requests), elapsed {}, ETA {}",
+            self.job_id,
+            pct,
+            self.markets_done.load(Ordering::Relaxed),
+            self.markets,
+            self.rows.load(Ordering::Relaxed),
+            self.requests.load(Ordering::Relaxed),
+            fmt_secs(self.started.elapsed().as_secs()),
+            eta
+        )
+    }
+
+    /// Log at most every PROGRESS_EVERY.
+    fn tick(&self) {
+        let now_ms = self.started.elapsed().as_millis() as u64;
+        let last = self.last_logged_ms.load(Ordering::Relaxed);
+        if now_ms.saturating_sub(last) >= PROGRESS_EVERY.as_millis() as u64
+            && self
+                .last_logged_ms
+                .compare_exchange(last, now_ms, Ordering::Relaxed, Ordering::Relaxed)
+                .is_ok()
+        {
+            info!("{}", self.line());
+        }
+    }
+}
+
+fn fmt_secs(secs: u64) -> String {
+    match secs {
+        s if s >= 3600 => format!("{}h{:02}m", s / 3600, s % 3600 / 60),
+        s if s >= 60 => format!("{}m{:02}s", s / 60, s % 60),
+        s => format!("{s}s"),
+    }
+}
+
+/// Plan `spec` for the exchange's active markets and store it as a new job.
+pub async fn create_job(pool: &PgPool, exchange_id: i32, exchange_name: &str, spec: &TimeSpec) -> Result<i64> {
+    let plan = plan_funding_for_exchange(&PgStore::new(pool.clone()), exchange_id, exchange_name, spec, &MarketFilter::default())
+        .await?;
+
+    let mut market_ids = Vec::new();
+    let mut starts = Vec::new();
+    let mut ends = Vec::new();
+    for m in &plan.markets {
+        match &m.skip {
+            Some(reason) => warn!("backfill: skip {} {}: {}", exchange_name, m.market_symbol, reason),
+            None => {
+                market_ids.push(m.market_id);
+                starts.push(m.start_ms as i64);
+                ends.push(m.end_ms as i64);
+            }
+        }
+    }
+
+    let mut tx = pool.begin().await?;
+    let job_id = sqlx::query_scalar!(
+        "INSERT INTO backfill_jobs (exchange_id, spec) VALUES ($1, $2) RETURNING id",
+        exchange_id,
+        serde_json::to_value(spec)?
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+    sqlx::query!(
+        r#"
+        INSERT INTO backfill_checkpoints (job_id, market_id, start_ms, end_ms, cursor_ms)
+        SELECT $1, m, s, e, s
+        FROM UNNEST($2::int[], $3::bigint[], $4::bigint[]) AS t(m, s, e)
+        "#,
+        job_id,
+        &market_ids,
+        &starts,
+        &ends
+    )
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    info!("backfill #{}: {} market(s) of {} planned", job_id, market_ids.len(), exchange_name);
+    Ok(job_id)
+}
+
+/// Fetch everything the job's checkpoints have not reached yet. Markets run
+/// concurrently (SYNC_CONC_MARKETS); one failing market does not stop the
+/// others, it leaves the job `failed` and resumable.
+pub async fn run_job(pool: &PgPool, job_id: i64) -> Result<JobSummary> {
+    let job = sqlx::query!(
+        r#"
+        SELECT j.exchange_id, e.name AS exchange, j.status
+        FROM backfill_jobs j
+        JOIN exchanges e ON e.id = j.exchange_id
+        WHERE j.id = $1
+        "#,
+        job_id
+    )
+    .fetch_optional(pool)
+    .await?
+    .ok_or_else(|| anyhow!("backfill job {} not found", job_id))?;
+
+    // Session-level lock on a dedicated connection: it is released when the
+    // connection closes, however this run ends
+    let mut lock = pool.acquire().await?.detach();
+    let locked: bool = sqlx::query_scalar("SELECT pg_try_advisory_lock($1, $2)")
+        .bind(JOB_LOCK_CLASS)
+        .bind(i32::try_from(job_id)?)
+        .fetch_one(&mut lock)
+        .await?;
+    if !locked {
+        bail!("backfill job {} is already running in another process", job_id);
+    }
+
+    if job.status == "done" {
+        info!("backfill #{}: already done", job_id);
+        return job_summary(pool, job_id).await;
+    }
+    let source = funding_source(&job.exchange)
+        .ok_or_else(|| anyhow!("unsupported exchange '{}'", job.exchange))?;
+    let chunk_ms = source.chunk_ms();
+
+    sqlx::query!(
+        "UPDATE backfill_jobs SET status = 'running', error = NULL, updated_at = NOW() WHERE id = $1",
+        job_id
+    )
+    .execute(pool)
+    .await?;
+
+    let rows = sqlx::query!(
+        r#"
+        SELECT c.market_id, m.market_symbol, c.start_ms, c.end_ms, c.cursor_ms, c.page_cursor, c.done
+        FROM backfill_checkpoints c
+        JOIN markets m ON m.id = c.market_id
+        WHERE c.job_id = $1
+        ORDER BY m.market_symbol
+        "#,
+        job_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let progress = Progress {
+        job_id,
+        markets: rows.len(),
+        total_ms: rows.iter().map(|r| (r.end_ms - r.start_ms + 1) as u64).sum(),
+        done_at_start: rows.iter().map(|r| (r.cursor_ms - r.start_ms) as u64).sum(),
+        done_ms: AtomicU64::new(0),
+        markets_done: AtomicUsize::new(rows.iter().filter(|r| r.done).count()),
+        rows: AtomicU64::new(0),
+        requests: AtomicU64::new(0),
+        started: Instant::now(),
+        last_logged_ms: AtomicU64::new(0),
+    };
+    progress.done_ms.store(progress.done_at_start, Ordering::Relaxed);
+    info!("{}", progress.line());
+
+    let pending: Vec<Checkpoint> = rows
+        .into_iter()
+        .filter(|r| !r.done)
+        .map(|r| Checkpoint {
+            market_id: r.market_id,
+            market_symbol: r.market_symbol,
+            end_ms: r.end_ms as u64,
+            cursor_ms: r.cursor_ms as u64,
+            page_cursor: r.page_cursor,
+        })
+        .collect();
+
+    let conc: usize = std::env::var("SYNC_CONC_MARKETS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(16);
+    let exchange = job.exchange.as_str();
+    let failures: Vec<String> = stream::iter(pending)
+        .map(|cp| {
+            let progress = &progress;
+            async move {
+                let symbol = cp.market_symbol.clone();
+                let market_id = cp.market_id;
+                match run_market(pool, job_id, job.exchange_id, exchange, cp, chunk_ms, progress).await {
+                    Ok(()) => None,
+                    Err(e) => {
+                        warn!("backfill #{}: {} {} failed: {:#}", job_id, exchange, symbol, e);
+                        let msg = format!("{:#}", e);
+                        let _ = sqlx::query!(
+                            "UPDATE backfill_checkpoints SET error = $3, updated_at = NOW() WHERE job_id = $1 AND market_id = $2",
+                            job_id,
+                            market_id,
+                            msg
+                        )
+                        .execute(pool)
+                        .await;
+                        Some(format!("{}: {}", symbol, msg))
+                    }
+                }
+            }
+        })
+        .buffer_unordered(conc)
+        .filter_map(|r| async move { r })
+        .collect()
+        .await;
+
+    info!("{}", progress.line());
+    if failures.is_empty() {
+        sqlx::query!(
+            "UPDATE backfill_jobs SET status = 'done', updated_at = NOW(), finished_at = NOW() WHERE id = $1",
+            job_id
+        )
+        .execute(pool)
+        .await?;
+    } else {
+        let error = format!("{} market(s) failed; first: {}", failures.len(), failures[0]);
+        sqlx::query!(
+            "UPDATE backfill_jobs SET status = 'failed', error = $2, updated_at = NOW() WHERE id = $1",
+            job_id,
+            error
+        )
+        .execute(pool)
+        .await?;
+    }
+    job_summary(pool, job_id).await
+}
+
+/// Walk one market from its checkpoint to the end of its window. Each page is
+/// written in the same transaction as the checkpoint that moves past it.
+async fn run_market(
+    pool: &PgPool,
+    job_id: i64,
+    exchange_id: i32,
+    exchange: &str,
+    cp: Checkpoint,
+    chunk_ms: u64,
+    progress: &Progress,
+) -> Result<()> {
+    let mut page_cursor = cp.page_cursor;
+
+    for (cursor_ms, chunk_end) in funding_chunks(cp.cursor_ms, cp.end_ms, chunk_ms) {
+        let mut pages = 0;
+        loop {
+            let page = fetch_funding_page(exchange, &cp.market_symbol, cursor_ms, chunk_end, page_cursor.as_deref())
+                .await
+                .with_context(|| format!("fetch {}..{}", cursor_ms, chunk_end))?;
+            let n = page.rows.len();
+            let rows: Vec<_> = page.rows.iter().map(|r| (cp.market_id, r)).collect();
+            page_cursor = page.next;
+            pages += 1;
+            if pages >= MAX_PAGES_PER_WINDOW && page_cursor.is_some() {
+                warn!("{} {}: stopped after {} pages for chunk {}..{}", exchange, cp.market_symbol, pages, cursor_ms, chunk_end);
+                page_cursor = None;
+            }
+            // The chunk's last page moves the checkpoint on to the next chunk
+            let next_ms = if page_cursor.is_none() { chunk_end.saturating_add(1) } else { cursor_ms };
+
+            let mut tx = pool.begin().await?;
+            insert::insert_funding_rates_tx(&mut tx, exchange_id, &rows).await?;
+            sqlx::query!(
+                r#"
+                UPDATE backfill_checkpoints
+                SET cursor_ms = $3, page_cursor = $4, rows = rows + $5, requests = requests + 1, updated_at = NOW()
+                WHERE job_id = $1 AND market_id = $2
+                "#,
+                job_id,
+                cp.market_id,
+                next_ms as i64,
+                page_cursor,
+                n as i64
+            )
+            .execute(&mut *tx)
+            .await?;
+            tx.commit().await?;
+            progress.rows.fetch_add(n as u64, Ordering::Relaxed);
+            progress.requests.fetch_add(1, Ordering::Relaxed);
+
+            if page_cursor.is_none() {
+                break;
+            }
+        }
+        progress.done_ms.fetch_add(chunk_end + 1 - cursor_ms, Ordering::Relaxed);
+        progress.tick();
+    }
+
+    sqlx::query!(
+        "UPDATE backfill_checkpoints SET done = true, error = NULL, updated_at = NOW() WHERE job_id = $1 AND market_id = $2",
+        job_id,
+        cp.market_id
+    )
+    .execute(pool)
+    .await?;
+    progress.markets_done.fetch_add(1, Ordering::Relaxed);
+    Ok(())
+}
+
+pub async fn job_summary(pool: &PgPool, job_id: i64) -> Result<JobSummary> {
+    list_jobs(pool, Some(job_id), 1)
+        .await?
+        .pop()
+        .ok_or_else(|| anyhow!("backfill job {} not found", job_id))
+}
+
+/// Newest first.
+pub async fn list_jobs(pool: &PgPool, job_id: Option<i64>, limit: i64) -> Result<Vec<JobSummary>> {
+    let rows = sqlx::query_as!(
+        JobSummary,
+        r#"
+        SELECT
+          j.id,
+          e.name AS exchange,
+          j.status,
+          j.error,
+          COUNT(c.market_id) AS "markets!",
+          COUNT(c.market_id) FILTER (WHERE c.done) AS "markets_done!",
+          COALESCE(SUM(c.rows), 0)::bigint AS "rows!",
+          COALESCE(SUM(c.requests), 0)::bigint AS "requests!",
+          COALESCE(SUM(c.cursor_ms - c.start_ms)::float8 / NULLIF(SUM(c.end_ms - c.start_ms + 1), 0), 1.0) AS "progress!",
+          j.created_at,
+          j.updated_at
+        FROM backfill_jobs j
+        JOIN exchanges e ON e.id = j.exchange_id
+        LEFT JOIN backfill_checkpoints c ON c.job_id = j.id
+        WHERE $1::bigint IS NULL OR j.id = $1
+        GROUP BY j.id, e.name
+        ORDER BY j.id DESC
+        LIMIT $2
+        "#,
+        job_id,
+        limit
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
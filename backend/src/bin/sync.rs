@@ -1,14 +1,31 @@
 
 use std::env;
+use std::io::Write;
+use std::path::PathBuf;
 
 use anyhow::{anyhow, Context, Result};
+use clap::{Args, CommandFactory, Parser, Subcommand};
+use clap_complete::Shell;
 use sqlx::PgPool;
+use time::{Duration, OffsetDateTime};
 use tracing::{error, info, warn};
 
-use backend::db::migrations;
+use backend::db::insert::{self, ConflictPolicy};
+use backend::store::{FundingStore, PgStore};
+use backend::db::migrations::{self, MigrationMode};
+use backend::db::partitions;
+use backend::db::export::{self, Column, CsvSink, Dataset, ExportFilter, ExportFormat, ExportRow, ExportSink, ParquetSink};
+use backend::db::retention::{self, PruneStep, RetentionPolicy};
 use backend::data::coin::{refresh_all_markets, refresh_markets_for_exchange};
-use backend::data::stats::collect_daily_market_stats;
-use backend::data::funding::{collect_funding_for_exchange_with_spec, collect_funding_for_exchange, TimeSpec};
+use backend::data::filter::{check_selectors, MarketFilter};
+use backend::data::stats::{collect_daily_market_stats, collect_market_stats, collect_stats_for_exchange, fetch_market_stats};
+use backend::data::predicted::collect_predicted_funding;
+use backend::data::funding::{collect_funding_for_exchange_with_spec, collect_funding_for_exchange, plan_funding_for_exchange, FundingPlan, TimeSpec};
+use backend::backfill::{self, JobSummary};
+use backend::import::{self, ImportFormat, ImportOptions, ImportReport};
+use backend::alerts::evaluate_alerts;
+use backend::analytics::events::{detect_funding_events, EventConfig};
+use backend::analytics::backtest::{backtest, BacktestConfig, FeeSchedule};
 
 
 #[global_allocator]
@@ -22,6 +39,11 @@ enum RunMode {
 }
 
 
+/// `BTC-USD-PERP` -> `BTC`, for symbols that aren't stored yet.
+fn base_token(market_symbol: &str) -> &str {
+    market_symbol.split(['-', '_', '/']).next().unwrap_or(market_symbol)
+}
+
 async fn fetch_market_symbols_from_api(exchange_name: &str) -> anyhow::Result<Vec<String>> {
     match lower(exchange_name).as_str() {
         "paradex" => {
@@ -48,45 +70,573 @@ async fn fetch_market_symbols_from_api(exchange_name: &str) -> anyhow::Result<Ve
 
 
 
+/* ------------------------- CLI ------------------------- */
+
+/// Sync markets, funding and stats into Postgres, plus maintenance, backtests
+/// and import/export. With no command, runs `init`.
+#[derive(Parser, Debug)]
+#[command(name = "sync", version, about, long_about = None)]
+struct Cli {
+    /// Don't apply pending migrations; fail unless the schema is already up to date.
+    #[arg(long, global = true)]
+    no_migrate: bool,
+
+    /// What to do with rows that already exist: ignore | overwrite | overwrite-if-different
+    /// (default SYNC_CONFLICT_POLICY, else ignore).
+    ///
+    /// overwrite replaces stored funding/stats with what the exchange returns now;
+    /// overwrite-if-different only touches rows whose values changed. Every changed value
+    /// is logged in data_revisions and the affected rollup buckets are recomputed.
+    #[arg(long, global = true, value_name = "POLICY")]
+    on_conflict: Option<ConflictPolicy>,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Refresh markets, then funding, then stats for all active exchanges.
+    ///
+    /// Without a time flag, funding defaults to --since-last 24h. --dry-run plans funding
+    /// for the markets already stored; it does not refresh markets or collect stats.
+    Init {
+        #[command(flatten)]
+        window: Window,
+        #[command(flatten)]
+        plan: PlanArgs,
+    },
+
+    /// Sync markets for all active exchanges or a single exchange.
+    Markets {
+        /// Exchange name (case-insensitive).
+        #[arg(short, long, value_name = "NAME")]
+        exchange: Option<String>,
+    },
+
+    /// Collect latest market stats (OI USD + 24h volume) for all or one exchange.
+    Stats {
+        /// Exchange name (case-insensitive).
+        #[arg(short, long, value_name = "NAME")]
+        exchange: Option<String>,
+        #[command(flatten)]
+        select: Select,
+    },
+
+    /// Collect funding rates over a window (default --since-last 24h).
+    Funding {
+        /// Exchange name (case-insensitive).
+        #[arg(short, long, value_name = "NAME")]
+        exchange: Option<String>,
+        #[command(flatten)]
+        select: Select,
+        #[command(flatten)]
+        window: Window,
+        #[command(flatten)]
+        plan: PlanArgs,
+    },
+
+    /// Manage exchanges.
+    Exchange {
+        #[command(subcommand)]
+        command: ExchangeCommand,
+    },
+
+    /// Backfill one active exchange end-to-end (markets -> funding -> stats).
+    ///
+    /// Funding runs as a resumable job: each market's window is fixed when the job
+    /// starts and its progress is checkpointed after every page written, with progress
+    /// and ETA logged along the way. A killed or failed job continues where it stopped
+    /// with --resume <JOB_ID>; --jobs lists recent jobs.
+    Backfill {
+        /// Exchange name (case-insensitive).
+        #[arg(short, long, value_name = "NAME", required_unless_present_any = ["resume", "jobs"])]
+        exchange: Option<String>,
+        #[command(flatten)]
+        window: Window,
+        #[command(flatten)]
+        plan: PlanArgs,
+        /// Continue a job from its checkpoints.
+        #[arg(long, value_name = "JOB_ID",
+              conflicts_with_all = ["exchange", "last", "between", "from", "to", "since_last", "dry_run"])]
+        resume: Option<i64>,
+        /// List recent backfill jobs and their progress.
+        #[arg(long, conflicts_with_all = ["exchange", "resume", "dry_run"])]
+        jobs: bool,
+    },
+
+    /// Replay stored funding through the cross-exchange spread strategy.
+    ///
+    /// Works on 8h buckets with spreads in bps per 8h, and prints the trade log and
+    /// summary. Defaults: last 30 days, entry 5 / exit 1 bps, 10000 per leg,
+    /// 5 positions, min hold 3 periods, taker fees 5 bps.
+    Backtest(BacktestArgs),
+
+    /// Apply pending database migrations and list every migration's state.
+    Migrate {
+        /// Only list, don't apply.
+        #[arg(long)]
+        status: bool,
+    },
+
+    /// Snapshot the predicted rate for the upcoming funding interval on all active exchanges.
+    Predicted,
+
+    /// Detect funding sign flips, z-score outliers and regime changes and store them in
+    /// funding_events.
+    ///
+    /// Works on native funding periods (8h on Paradex, 1h on Extended). Thresholds:
+    /// FUNDING_EVENT_ZSCORE, FUNDING_EVENT_HISTORY_PERIODS, FUNDING_EVENT_MIN_PERIODS,
+    /// FUNDING_EVENT_REGIME_PERIODS.
+    Events {
+        /// How far back to look: 48 (hours), 12h, 7d, 2w.
+        #[arg(long, visible_alias = "hours", value_name = "DURATION", value_parser = parse_duration, default_value = "24h")]
+        last: Duration,
+    },
+
+    /// Evaluate all active alert rules once and deliver the ones that fire.
+    Alerts,
+
+    /// Apply the retention policy: report what will be removed, then remove it
+    ///
+    /// Each setting is a number of days; unset keeps everything:
+    ///   RETENTION_FUNDING_DAYS                raw funding_rates
+    ///   RETENTION_FUNDING_1H_DAYS / _8H_DAYS / _1D_DAYS   funding rollups
+    ///   RETENTION_STATS_HOURLY_AFTER_DAYS     market_stats thinned to one row per hour
+    ///   RETENTION_STATS_DAILY_AFTER_DAYS      market_stats thinned to one row per day
+    ///   RETENTION_STATS_DAYS                  market_stats
+    ///   RETENTION_PRICES_DAYS                 price_snapshots
+    ///   RETENTION_PREDICTED_DAYS              predicted_funding_rates
+    /// The backend applies the same policy daily at 00:20 UTC.
+    #[command(verbatim_doc_comment)]
+    Prune {
+        /// Only report.
+        #[arg(long)]
+        dry_run: bool,
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Recompute the funding rollups and latest tables from raw rows.
+    ///
+    /// Rebuilds the hourly / 8h / daily rollups (for the last DURATION, default all raw
+    /// history) and the latest funding / stats tables. Ingestion keeps them up to date;
+    /// this is only needed after writing raw rows outside the sync/backend.
+    Rollups {
+        /// Only rebuild this far back: 48 (hours), 12h, 7d, 2w.
+        #[arg(long, visible_alias = "hours", value_name = "DURATION", value_parser = parse_duration)]
+        last: Option<Duration>,
+    },
+
+    /// Create upcoming monthly partitions and list every partition.
+    ///
+    /// Lists funding_rates / market_stats partitions with their range and approximate
+    /// row count.
+    Partitions {
+        /// Months ahead to create.
+        #[arg(long, value_name = "MONTHS", default_value_t = partitions::DEFAULT_MONTHS_AHEAD,
+              value_parser = clap::value_parser!(i32).range(0..=120))]
+        ahead: i32,
+    },
+
+    /// Write stored funding / stats history to Parquet or CSV files.
+    ///
+    /// Rows are (token, exchange, market_symbol, interval, rate, ts); stats rows carry
+    /// open_interest and volume_24h instead of interval and rate. funding is raw
+    /// funding_rates at the native interval, funding_8h the 8h rollup quoted per 8h.
+    /// Parquet goes to DIR/<dataset>/exchange=NAME/date=YYYY-MM-DD/part-0.parquet (hive
+    /// partitions, replaced on re-export); CSV to DIR/<dataset>.csv, or stdout with
+    /// --out - for a single dataset. Defaults to the full history.
+    Export(ExportArgs),
+
+    /// Load funding history from CSV, Parquet or JSONL files.
+    ///
+    /// PATH is a file or a directory (every csv/parquet/jsonl file under it; format from
+    /// the extension unless --format is given). Columns default to the `export` schema:
+    /// exchange, market_symbol, token, rate, ts, interval. token and interval are
+    /// optional (token comes from the market symbol), and hive directories
+    /// (exchange=NAME/) fill in missing columns. Rates are native per-interval fractions;
+    /// timestamps are RFC 3339, `YYYY-MM-DD HH:MM:SS` (UTC) or unix s/ms/us/ns; intervals
+    /// are like 8h, 30m or bare minutes. Invalid rows are counted and skipped. Unknown
+    /// exchanges and markets are added inactive, existing markets only get a missing
+    /// funding interval filled in, and rows are written with the bulk insert (honouring
+    /// --on-conflict).
+    Import(ImportArgs),
+
+    /// Print a shell completion script, e.g. `sync completions bash > /etc/bash_completion.d/sync`.
+    Completions {
+        shell: Shell,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum ExchangeCommand {
+    /// Ensure the exchange row exists and run markets -> funding -> stats for it.
+    Add {
+        #[arg(long, value_name = "NAME")]
+        name: String,
+        #[command(flatten)]
+        window: Window,
+    },
+}
+
+/// A time range over stored or fetched data. TIME is unix ms, a date (`2025-09-01`,
+/// UTC midnight), a UTC date-time (`2025-09-01T08:00`) or RFC 3339.
+#[derive(Args, Debug)]
+struct Range {
+    /// Look back from now: 48 (hours), 90m, 12h, 7d, 2w.
+    #[arg(long, visible_alias = "hours", value_name = "DURATION", value_parser = parse_duration,
+          conflicts_with_all = ["between", "from", "to"])]
+    last: Option<Duration>,
+
+    /// Explicit window.
+    #[arg(short, long, num_args = 2, value_names = ["START", "END"], value_parser = parse_instant,
+          conflicts_with_all = ["from", "to"])]
+    between: Option<Vec<OffsetDateTime>>,
+
+    /// Window start.
+    #[arg(long, value_name = "TIME", value_parser = parse_instant)]
+    from: Option<OffsetDateTime>,
+
+    /// Window end (default now).
+    #[arg(long, value_name = "TIME", value_parser = parse_instant)]
+    to: Option<OffsetDateTime>,
+}
+
+impl Range {
+    /// (from, to); `None` means unbounded on that side.
+    fn bounds(&self) -> Result<(Option<OffsetDateTime>, Option<OffsetDateTime>)> {
+        let (from, to) = match (&self.last, &self.between) {
+            (Some(d), _) => (Some(OffsetDateTime::now_utc() - *d), None),
+            (_, Some(b)) => (Some(b[0]), Some(b[1])),
+            _ => (self.from, self.to),
+        };
+        if let (Some(f), Some(t)) = (from, to)
+            && f >= t
+        {
+            let fmt = |ts: OffsetDateTime| ts.format(&time::format_description::well_known::Rfc3339).unwrap_or_default();
+            return Err(anyhow!("the window start ({}) must be before its end ({})", fmt(f), fmt(t)));
+        }
+        Ok((from, to))
+    }
+}
+
+/// Which funding to fetch. `--since-last` resumes each market from its newest
+/// stored row.
+#[derive(Args, Debug)]
+struct Window {
+    #[command(flatten)]
+    range: Range,
+
+    /// Fetch from each market's newest stored row; markets with none look back DURATION.
+    #[arg(short, long, value_name = "DURATION", value_parser = parse_duration,
+          conflicts_with_all = ["last", "between", "from", "to"])]
+    since_last: Option<Duration>,
+}
+
+impl Window {
+    fn spec(&self) -> Result<Option<TimeSpec>> {
+        if let Some(d) = self.since_last {
+            return Ok(Some(TimeSpec::SinceLastOrLookbackHours(whole_hours(d))));
+        }
+        if let Some(d) = self.range.last {
+            return Ok(Some(TimeSpec::LookbackHours(whole_hours(d))));
+        }
+        match self.range.bounds()? {
+            (None, None) => Ok(None),
+            (None, Some(_)) => Err(anyhow!("--to needs --from")),
+            (Some(from), to) => Ok(Some(TimeSpec::Between {
+                start_ms: unix_ms(from)?,
+                end_ms: unix_ms(to.unwrap_or_else(OffsetDateTime::now_utc))?,
+            })),
+        }
+    }
+}
+
+/// Market selection for `funding` and `stats`. Each flag is repeatable and takes
+/// comma-separated lists; matching is case-insensitive.
+#[derive(Args, Debug)]
+struct Select {
+    /// Only these market symbols, e.g. BTC-USD-PERP.
+    #[arg(short, long = "market", value_name = "SYMBOL", value_delimiter = ',')]
+    market: Vec<String>,
+    /// Only markets on these base tokens, e.g. BTC,ETH (added to --market).
+    #[arg(short, long, value_name = "TOKEN", value_delimiter = ',')]
+    token: Vec<String>,
+    /// Leave out these market symbols or tokens.
+    #[arg(short = 'x', long, value_name = "SYMBOL|TOKEN", value_delimiter = ',')]
+    exclude: Vec<String>,
+}
+
+impl Select {
+    fn filter(self) -> MarketFilter {
+        MarketFilter { markets: self.market, tokens: self.token, exclude: self.exclude }
+    }
+}
+
+/// Funding fetch plan instead of a run.
+#[derive(Args, Debug)]
+struct PlanArgs {
+    /// Print the per-exchange / per-market fetch plan (windows resolved against each
+    /// market's last stored row, estimated rows and requests) and exit without writing.
+    #[arg(long)]
+    dry_run: bool,
+    /// Print the plan as JSON.
+    #[arg(long, requires = "dry_run")]
+    json: bool,
+}
+
+impl PlanArgs {
+    /// A dry run only verifies the schema, it never migrates.
+    fn mode(&self, mode: MigrationMode) -> MigrationMode {
+        if self.dry_run { MigrationMode::VerifyOnly } else { mode }
+    }
+}
+
+#[derive(Args, Debug)]
+struct BacktestArgs {
+    #[command(flatten)]
+    range: Range,
+    /// Open when short - long >= X bps per 8h.
+    #[arg(long, value_name = "BPS")]
+    entry_bps: Option<f64>,
+    /// Close when short - long < X bps per 8h.
+    #[arg(long, value_name = "BPS")]
+    exit_bps: Option<f64>,
+    /// Notional per leg.
+    #[arg(long, value_name = "USD")]
+    notional: Option<f64>,
+    #[arg(long, value_name = "N")]
+    max_positions: Option<usize>,
+    /// Minimum hold, in 8h periods.
+    #[arg(long, value_name = "N")]
+    min_hold: Option<u32>,
+    /// Maximum hold, in 8h periods.
+    #[arg(long, value_name = "N")]
+    max_hold: Option<u32>,
+    /// Per-exchange fees, repeatable.
+    #[arg(long, value_name = "NAME=MAKER_BPS:TAKER_BPS", value_parser = parse_fee)]
+    fee: Vec<(String, FeeSchedule)>,
+    /// Pay maker instead of taker fees.
+    #[arg(long)]
+    maker: bool,
+    /// Slippage in bps per 1% of min(OI, 24h volume) taken.
+    #[arg(long, value_name = "BPS")]
+    impact_bps: Option<f64>,
+    #[arg(long, value_name = "BPS")]
+    max_slippage_bps: Option<f64>,
+    #[arg(long, value_name = "A,B", value_delimiter = ',')]
+    tokens: Option<Vec<String>>,
+    #[arg(long, value_name = "A,B", value_delimiter = ',')]
+    exchanges: Option<Vec<String>>,
+    #[arg(long)]
+    json: bool,
+}
+
+impl BacktestArgs {
+    fn config(self) -> Result<BacktestConfig> {
+        let mut cfg = BacktestConfig::default();
+        (cfg.from, cfg.to) = self.range.bounds()?;
+        cfg.entry_spread_bps = self.entry_bps.unwrap_or(cfg.entry_spread_bps);
+        cfg.exit_spread_bps = self.exit_bps.unwrap_or(cfg.exit_spread_bps);
+        cfg.notional = self.notional.unwrap_or(cfg.notional);
+        cfg.max_concurrent = self.max_positions.unwrap_or(cfg.max_concurrent);
+        cfg.min_hold_periods = self.min_hold.unwrap_or(cfg.min_hold_periods);
+        cfg.max_hold_periods = self.max_hold.or(cfg.max_hold_periods);
+        cfg.impact_bps_per_pct = self.impact_bps.unwrap_or(cfg.impact_bps_per_pct);
+        cfg.max_slippage_bps = self.max_slippage_bps.unwrap_or(cfg.max_slippage_bps);
+        cfg.use_maker = self.maker;
+        cfg.tokens = self.tokens;
+        cfg.exchanges = self.exchanges;
+        cfg.fees.extend(self.fee);
+        cfg.validate().map_err(|e| anyhow!(e))?;
+        Ok(cfg)
+    }
+}
+
+#[derive(Args, Debug)]
+struct ExportArgs {
+    /// Datasets to write: funding, funding_8h, stats (comma-separated) or all.
+    #[arg(long = "dataset", value_name = "LIST", value_parser = parse_datasets, default_value = "all")]
+    datasets: Datasets,
+    /// parquet | csv
+    #[arg(long, value_name = "FORMAT", default_value = "parquet")]
+    format: ExportFormat,
+    /// Output directory, or `-` for CSV on stdout.
+    #[arg(short, long, value_name = "DIR", default_value = "export")]
+    out: PathBuf,
+    /// Only this token.
+    #[arg(long, value_name = "SYMBOL")]
+    token: Option<String>,
+    /// Only this exchange.
+    #[arg(short, long, value_name = "NAME")]
+    exchange: Option<String>,
+    #[command(flatten)]
+    range: Range,
+}
+
+#[derive(Clone, Debug)]
+struct Datasets(Vec<Dataset>);
+
+#[derive(Args, Debug)]
+struct ImportArgs {
+    /// File or directory to import.
+    path: PathBuf,
+    /// csv | parquet | jsonl (default: from each file's extension).
+    #[arg(long, value_name = "FORMAT")]
+    format: Option<ImportFormat>,
+    /// Source column names, e.g. market=symbol,rate=funding_rate,timestamp=time. Repeatable.
+    #[arg(long, value_name = "FIELD=COLUMN,...")]
+    map: Vec<String>,
+    /// Exchange of every row (instead of an exchange column).
+    #[arg(short, long, value_name = "NAME")]
+    exchange: Option<String>,
+    /// Only validate.
+    #[arg(long)]
+    dry_run: bool,
+    /// Print the report as JSON.
+    #[arg(long)]
+    json: bool,
+}
+
+impl ImportArgs {
+    fn options(&self) -> Result<ImportOptions> {
+        let mut opts = ImportOptions {
+            format: self.format,
+            exchange: self.exchange.clone(),
+            dry_run: self.dry_run,
+            ..Default::default()
+        };
+        for m in &self.map {
+            opts.columns.apply(m)?;
+        }
+        Ok(opts)
+    }
+}
+
+/// `7d`, `12h`, `90m`, `2w`; a bare number is hours (as the old `--hours N`).
+fn parse_duration(s: &str) -> Result<Duration, String> {
+    let s = s.trim();
+    let digits = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (n, unit) = s.split_at(digits);
+    let n: i64 = n
+        .parse()
+        .map_err(|_| format!("invalid duration {s:?}, expected e.g. 48, 12h, 7d or 2w"))?;
+    if n <= 0 {
+        return Err("duration must be positive".into());
+    }
+    let unit_secs: i64 = match unit.trim() {
+        "" | "h" => 3600,
+        "m" | "min" => 60,
+        "d" => 86_400,
+        "w" => 7 * 86_400,
+        other => return Err(format!("unknown duration unit {other:?} (m | h | d | w)")),
+    };
+    // Reaching back further than a date can represent would panic later on
+    n.checked_mul(unit_secs)
+        .map(Duration::seconds)
+        .filter(|d| OffsetDateTime::now_utc().checked_sub(*d).is_some())
+        .ok_or_else(|| format!("duration {s:?} is too long"))
+}
+
+/// Unix ms, `YYYY-MM-DD` (UTC midnight), `YYYY-MM-DD[T ]HH:MM[:SS]` (UTC) or RFC 3339.
+fn parse_instant(s: &str) -> Result<OffsetDateTime, String> {
+    use time::format_description::well_known::Rfc3339;
+    use time::macros::format_description;
+
+    let s = s.trim();
+    if !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit()) {
+        return s
+            .parse::<i64>()
+            .ok()
+            .and_then(|ms| OffsetDateTime::from_unix_timestamp_nanos(ms as i128 * 1_000_000).ok())
+            .ok_or_else(|| format!("timestamp out of range: {s}"));
+    }
+    if let Ok(t) = OffsetDateTime::parse(s, &Rfc3339) {
+        return Ok(t);
+    }
+    let t = s.replacen(' ', "T", 1);
+    let with_secs = format_description!("[year]-[month]-[day]T[hour]:[minute]:[second]");
+    let no_secs = format_description!("[year]-[month]-[day]T[hour]:[minute]");
+    if let Ok(dt) = time::PrimitiveDateTime::parse(&t, with_secs).or_else(|_| time::PrimitiveDateTime::parse(&t, no_secs)) {
+        return Ok(dt.assume_utc());
+    }
+    time::Date::parse(s, format_description!("[year]-[month]-[day]"))
+        .map(|d| d.midnight().assume_utc())
+        .map_err(|_| format!("invalid time {s:?}, expected unix ms, 2025-09-01, 2025-09-01T08:00 or RFC 3339"))
+}
+
+/// NAME=MAKER_BPS:TAKER_BPS
+fn parse_fee(s: &str) -> Result<(String, FeeSchedule), String> {
+    s.split_once('=')
+        .and_then(|(name, fees)| {
+            let (m, t) = fees.split_once(':')?;
+            Some((name.to_string(), FeeSchedule { maker_bps: m.parse().ok()?, taker_bps: t.parse().ok()? }))
+        })
+        .ok_or_else(|| format!("invalid fee {s:?}, expected NAME=MAKER_BPS:TAKER_BPS"))
+}
+
+fn parse_datasets(s: &str) -> Result<Datasets> {
+    if s.trim().eq_ignore_ascii_case("all") {
+        return Ok(Datasets(vec![Dataset::Funding, Dataset::Funding8h, Dataset::Stats]));
+    }
+    s.split(',').map(str::parse).collect::<Result<_>>().map(Datasets)
+}
+
+/// Rounded up, since the funding collectors work in whole hours.
+fn whole_hours(d: Duration) -> u64 {
+    (d.whole_minutes() as u64).div_ceil(60)
+}
+
+fn unix_ms(ts: OffsetDateTime) -> Result<u64> {
+    u64::try_from(ts.unix_timestamp_nanos() / 1_000_000).map_err(|_| anyhow!("time before 1970: {ts}"))
+}
 
 #[tokio::main]
 async fn main() -> Result<()> {
     dotenv::dotenv().ok();
+    let cli = Cli::parse();
 
+    // stderr, so `--json` / `export --out -` output on stdout stays clean
     tracing_subscriber::fmt()
         .with_target(false)
+        .with_writer(std::io::stderr)
         .compact()
         .init();
 
-    let mut args = env::args().skip(1).collect::<Vec<_>>();
-    if args.is_empty() {
-        // default to "init"
-        return run_init(None).await;
-    }
-
-    let subcmd = args.remove(0).to_ascii_lowercase();
-    match subcmd.as_str() {
-        "init" => {
-            // optional time flags
-            let spec = parse_time_spec(&args)?;
-            run_init(spec).await
+    let mode = if cli.no_migrate { MigrationMode::VerifyOnly } else { MigrationMode::Apply };
+    insert::init_conflict_policy(cli.on_conflict)?;
+
+    let Some(command) = cli.command else {
+        return run_init(None, mode).await;
+    };
+    match command {
+        Command::Init { window, plan } => {
+            let spec = window.spec()?;
+            if plan.dry_run {
+                let store = PgStore::new(migrations::create_pool_with_mode(plan.mode(mode)).await);
+                let spec = spec.unwrap_or(TimeSpec::SinceLastOrLookbackHours(24));
+                let exchanges = store.active_exchanges().await?.into_iter().map(|e| (e.id, e.name)).collect::<Vec<_>>();
+                return print_funding_plan(&store, &exchanges, &spec, &MarketFilter::default(), plan.json).await;
+            }
+            run_init(spec, mode).await
         }
 
-        "markets" => {
-            let (exchange_opt, _spec_unused) = parse_exchange_and_spec(&args)?;
-            let pool = migrations::create_pool().await;
+        Command::Markets { exchange } => {
+            let pool = migrations::create_pool_with_mode(mode).await;
+            let store = PgStore::new(pool.clone());
 
-            match exchange_opt {
+            match exchange {
                 Some(ex) => {
                     let (id, dbname) = ensure_exchange_row(&pool, &ex).await?;
                     info!("markets: syncing {} (id={})", dbname, id);
-                    refresh_markets_for_exchange(&pool, id, &dbname)
+                    refresh_markets_for_exchange(&store, id, &dbname)
                         .await
                         .context("refresh_markets_for_exchange failed")?;
                 }
                 None => {
                     info!("markets: syncing all active exchanges");
-                    refresh_all_markets(&pool)
+                    refresh_all_markets(&store)
                         .await
                         .context("refresh_all_markets failed")?;
                 }
@@ -94,128 +644,356 @@ async fn main() -> Result<()> {
             Ok(())
         }
 
-        "stats" => {
-            let (exchange_opt, _spec_unused) = parse_exchange_and_spec(&args)?;
-            let pool = migrations::create_pool().await;
+        Command::Stats { exchange, select } => {
+            let pool = migrations::create_pool_with_mode(mode).await;
+            let filter = select.filter();
 
-            match exchange_opt {
+            match exchange {
                 Some(ex) => {
                     let (id, dbname) = lookup_exchange_id_case_insensitive(&pool, &ex).await?
                         .ok_or_else(|| anyhow!("exchange not found or inactive: {}", ex))?;
                     info!("stats: syncing {} (id={})", dbname, id);
-                    collect_stats_for_single_exchange(&pool, id, &dbname, RunMode::Normal).await?;
+                    check_selectors(&PgStore::new(pool.clone()), &[id], &filter).await?;
+                    collect_stats_for_single_exchange(&pool, id, &dbname, RunMode::Normal, &filter).await?;
                 }
                 None => {
                     info!("stats: syncing all active exchanges");
-                    collect_daily_market_stats(&pool)
+                    collect_market_stats(&PgStore::new(pool.clone()), &filter)
                         .await
-                        .context("collect_daily_market_stats failed")?;
+                        .context("collect_market_stats failed")?;
                 }
             }
             Ok(())
         }
 
-        "funding" => {
-            let (exchange_opt, spec) = parse_exchange_and_spec(&args)?;
-            let spec = spec.unwrap_or(TimeSpec::SinceLastOrLookbackHours(24));
-            let pool = migrations::create_pool().await;
+        Command::Funding { exchange, select, window, plan } => {
+            let spec = window.spec()?.unwrap_or(TimeSpec::SinceLastOrLookbackHours(24));
+            let filter = select.filter();
+            let pool = migrations::create_pool_with_mode(plan.mode(mode)).await;
+
+            if plan.dry_run {
+                let exchanges = match exchange {
+                    Some(ex) => vec![lookup_exchange_id_case_insensitive(&pool, &ex).await?
+                        .ok_or_else(|| anyhow!("exchange not found or inactive: {}", ex))?],
+                    None => PgStore::new(pool.clone()).active_exchanges().await?.into_iter().map(|e| (e.id, e.name)).collect(),
+                };
+                return print_funding_plan(&PgStore::new(pool), &exchanges, &spec, &filter, plan.json).await;
+            }
 
-            match exchange_opt {
+            match exchange {
                 Some(ex) => {
                     let (id, dbname) = lookup_exchange_id_case_insensitive(&pool, &ex).await?
                         .ok_or_else(|| anyhow!("exchange not found or inactive: {}", ex))?;
                     info!("funding: {} window={:?}", dbname, spec);
-                    collect_funding_for_exchange_with_spec(&pool, id, &dbname, spec)
+                    check_selectors(&PgStore::new(pool.clone()), &[id], &filter).await?;
+                    collect_funding_for_exchange_with_spec(&PgStore::new(pool.clone()), id, &dbname, spec, &filter)
                         .await
                         .with_context(|| format!("funding sync failed for {}", dbname))?;
                 }
                 None => {
                     info!("funding: all active exchanges, window={:?}", spec);
-                    run_funding_all(&pool, spec).await?;
+                    run_funding_all(&PgStore::new(pool), spec, &filter).await?;
                 }
             }
             Ok(())
         }
 
         // Ensure + backfill one exchange end-to-end
-        "exchange" => {
-            if args.is_empty() {
-                return Err(anyhow!("usage: exchange add --name <NAME> [--hours N | --between START_MS END_MS | --since-last N]"));
-            }
-            let sub = args[0].to_ascii_lowercase();
-            if sub != "add" {
-                return Err(anyhow!("unknown: exchange {}", sub));
-            }
-            let (name, spec) = parse_exchange_add(&args[1..])?;
-            let spec = spec.unwrap_or(TimeSpec::SinceLastOrLookbackHours(24));
+        Command::Exchange { command: ExchangeCommand::Add { name, window } } => {
+            let spec = window.spec()?.unwrap_or(TimeSpec::SinceLastOrLookbackHours(24));
 
-            let pool = migrations::create_pool().await;
+            let pool = migrations::create_pool_with_mode(mode).await;
             let (id, dbname) = ensure_exchange_row(&pool, &name).await?;
 
             info!("exchange add: {} (id={}) window={:?}", dbname, id, spec);
-            refresh_markets_for_exchange(&pool, id, &dbname)
+            let store = PgStore::new(pool.clone());
+            refresh_markets_for_exchange(&store, id, &dbname)
                 .await
                 .context("refresh_markets_for_exchange failed")?;
-            collect_funding_for_exchange_with_spec(&pool, id, &dbname, spec)
+            collect_funding_for_exchange_with_spec(&store, id, &dbname, spec, &MarketFilter::default())
                 .await
                 .context("funding backfill failed")?;
 
-            collect_stats_for_single_exchange(&pool, id, &dbname, RunMode::Backfill)
+            collect_stats_for_single_exchange(&pool, id, &dbname, RunMode::Backfill, &MarketFilter::default())
                 .await
                 .context("stats collection failed")?;
-                
+
             Ok(())
         }
 
         // Backfill one exchange end-to-end (expects already present & active)
-        "backfill" => {
-            let (exchange_opt, spec) = parse_exchange_and_spec(&args)?;
-            let ex = exchange_opt.ok_or_else(|| anyhow!("backfill requires --exchange <name>"))?;
-            let spec = spec.unwrap_or(TimeSpec::SinceLastOrLookbackHours(24));
+        Command::Backfill { exchange, window, plan, resume, jobs } => {
+            let pool = migrations::create_pool_with_mode(plan.mode(mode)).await;
+            if jobs {
+                print_backfill_jobs(&backfill::list_jobs(&pool, None, 20).await?);
+                return Ok(());
+            }
 
-            let pool = migrations::create_pool().await;
-            let (id, dbname) = lookup_exchange_id_case_insensitive(&pool, &ex).await?
-                .ok_or_else(|| anyhow!("exchange not found or inactive: {}", ex))?;
+            let (id, dbname, job_id) = match resume {
+                Some(job_id) => {
+                    let job = backfill::job_summary(&pool, job_id).await?;
+                    let (id, dbname) = lookup_exchange_id_case_insensitive(&pool, &job.exchange).await?
+                        .ok_or_else(|| anyhow!("exchange not found or inactive: {}", job.exchange))?;
+                    info!("backfill: resuming #{} for {} ({:.1}% done)", job_id, dbname, job.progress * 100.0);
+                    (id, dbname, job_id)
+                }
+                None => {
+                    let exchange = exchange.unwrap_or_default();
+                    let spec = window.spec()?.unwrap_or(TimeSpec::SinceLastOrLookbackHours(24));
+                    let (id, dbname) = lookup_exchange_id_case_insensitive(&pool, &exchange).await?
+                        .ok_or_else(|| anyhow!("exchange not found or inactive: {}", exchange))?;
+                    if plan.dry_run {
+                        return print_funding_plan(&PgStore::new(pool), &[(id, dbname)], &spec, &MarketFilter::default(), plan.json).await;
+                    }
 
-            info!("backfill: {} (id={}) window={:?}", dbname, id, spec);
-            refresh_markets_for_exchange(&pool, id, &dbname)
+                    info!("backfill: {} (id={}) window={:?}", dbname, id, spec);
+                    refresh_markets_for_exchange(&PgStore::new(pool.clone()), id, &dbname)
+                        .await
+                        .context("refresh_markets_for_exchange failed")?;
+                    let job_id = backfill::create_job(&pool, id, &dbname, &spec).await?;
+                    info!("backfill: job #{} (continue with `sync backfill --resume {}`)", job_id, job_id);
+                    (id, dbname, job_id)
+                }
+            };
+
+            let summary = backfill::run_job(&pool, job_id).await.context("funding backfill failed")?;
+            if summary.status != "done" {
+                return Err(anyhow!(
+                    "backfill #{} {}: {}; continue with `sync backfill --resume {}`",
+                    job_id,
+                    summary.status,
+                    summary.error.unwrap_or_default(),
+                    job_id
+                ));
+            }
+            collect_stats_for_single_exchange(&pool, id, &dbname, RunMode::Backfill, &MarketFilter::default())
                 .await
-                .context("refresh_markets_for_exchange failed")?;
-            collect_funding_for_exchange_with_spec(&pool, id, &dbname, spec)
+                .context("stats collection failed")?;
+
+            Ok(())
+        }
+
+        // Replay stored funding through the spread strategy
+        Command::Backtest(args) => {
+            let json = args.json;
+            let cfg = args.config()?;
+            let pool = migrations::create_pool_with_mode(mode).await;
+
+            let report = backtest(&pool, &cfg).await.context("backtest failed")?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&report)?);
+                return Ok(());
+            }
+
+            println!(
+                "{:<10} {:<10} {:<10} {:<25} {:<25} {:>4} {:>9} {:>12} {:>10} {:>12}  reason",
+                "token", "long", "short", "entry", "exit", "n", "entry_bps", "funding", "costs", "net"
+            );
+            let fmt = |ts: time::OffsetDateTime| ts.format(&time::format_description::well_known::Rfc3339).unwrap_or_default();
+            for t in &report.trades {
+                println!(
+                    "{:<10} {:<10} {:<10} {:<25} {:<25} {:>4} {:>9.2} {:>12.2} {:>10.2} {:>12.2}  {}",
+                    t.token, t.long_exchange, t.short_exchange, fmt(t.entry_ts), fmt(t.exit_ts), t.periods,
+                    t.entry_spread_bps, t.funding_pnl, t.fees + t.slippage, t.net_pnl, t.exit_reason
+                );
+            }
+            let s = &report.summary;
+            println!(
+                "\ntrades={} win_rate={:.1}% funding={:.2} fees={:.2} slippage={:.2} net={:.2} return={:.2}% avg_hold={:.1} max_dd={:.2}",
+                s.trades, s.win_rate * 100.0, s.funding_pnl, s.fees, s.slippage, s.net_pnl,
+                s.return_on_capital * 100.0, s.avg_hold_periods, s.max_drawdown
+            );
+            Ok(())
+        }
+
+        // Apply pending migrations explicitly (or just list them with --status)
+        Command::Migrate { status } => {
+            let db_url = env::var("DATABASE_URL").context("DATABASE_URL must be set")?;
+            let pool = migrations::connect(&db_url).await;
+            if !status {
+                migrations::apply(&pool).await.context("migration failed")?;
+            }
+            for m in migrations::status(&pool).await? {
+                let state = match (m.applied, m.checksum_ok) {
+                    (true, true) => "applied",
+                    (true, false) => "MODIFIED",
+                    (false, _) => "pending",
+                };
+                println!("{:<16} {:<9} {}", m.version, state, m.description);
+            }
+            Ok(())
+        }
+
+        // One alert evaluation pass (the backend runs this after every ingestion job)
+        Command::Alerts => {
+            let pool = migrations::create_pool_with_mode(mode).await;
+            let sent = evaluate_alerts(&pool).await.context("alert evaluation failed")?;
+            info!("alerts: {} notification(s) sent", sent);
+            Ok(())
+        }
+
+        // Snapshot the venues' predicted next funding
+        Command::Predicted => {
+            let pool = migrations::create_pool_with_mode(mode).await;
+            info!("predicted: all active exchanges");
+            collect_predicted_funding(&pool)
                 .await
-                .context("funding backfill failed")?;
-            collect_stats_for_single_exchange(&pool, id, &dbname, RunMode::Backfill)
+                .context("collect_predicted_funding failed")?;
+            Ok(())
+        }
+
+        // Funding event detection over recent history (idempotent)
+        Command::Events { last } => {
+            let hours = whole_hours(last) as i64;
+            let pool = migrations::create_pool_with_mode(mode).await;
+            let n = detect_funding_events(&pool, &EventConfig::from_env(), hours)
                 .await
-                .context("stats collection failed")?;
-    
+                .context("funding event detection failed")?;
+            info!("events: {} new event(s) over the last {}h", n, hours);
+            Ok(())
+        }
+
+        // Apply the RETENTION_* policy; always report first, --dry-run stops there
+        Command::Prune { dry_run, json } => {
+            let policy = RetentionPolicy::from_env();
+            if policy.is_empty() {
+                return Err(anyhow!("no retention configured; set RETENTION_* (see `sync help prune`)"));
+            }
+            let pool = migrations::create_pool_with_mode(mode).await;
+
+            let plan = retention::prune(&pool, &policy, true).await.context("prune dry run failed")?;
+            if dry_run {
+                if json {
+                    println!("{}", serde_json::to_string_pretty(&plan)?);
+                } else {
+                    print_prune_steps("would remove", &plan);
+                }
+                return Ok(());
+            }
+            if !json {
+                print_prune_steps("will remove", &plan);
+            }
+            let done = retention::prune(&pool, &policy, false).await.context("prune failed")?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&done)?);
+            } else {
+                print_prune_steps("removed", &done);
+            }
+            Ok(())
+        }
+
+        // Recompute funding rollups / latest tables from raw rows
+        Command::Rollups { last } => {
+            let since = last.map(|d| OffsetDateTime::now_utc() - d);
+            let pool = migrations::create_pool_with_mode(mode).await;
+            // refresh_rollups() aligns `since` down to a UTC day; NULL = oldest raw row
+            sqlx::query("SELECT refresh_rollups($1)")
+                .bind(since)
+                .execute(&pool)
+                .await
+                .context("rollup rebuild failed")?;
+            info!("rollups: rebuilt {}", if since.is_some() { "recent buckets" } else { "all buckets" });
+            Ok(())
+        }
+
+        // Create upcoming monthly partitions and list them
+        Command::Partitions { ahead } => {
+            let pool = migrations::create_pool_with_mode(mode).await;
+            let created = partitions::ensure_partitions(&pool, ahead).await?;
+            info!("partitions: {} created", created);
+            let fmt_ts = |ts: time::OffsetDateTime| ts.format(&time::format_description::well_known::Rfc3339).unwrap_or_default();
+            for table in partitions::PARTITIONED_TABLES {
+                for p in partitions::list_partitions(&pool, table).await? {
+                    let from = p.from.map(fmt_ts).unwrap_or_else(|| "MINVALUE".into());
+                    println!("{:<14} {:<28} {:<26} {:<26} ~{}", p.table, p.partition, from, fmt_ts(p.to), p.approx_rows);
+                }
+            }
+            Ok(())
+        }
+
+        // Write funding / stats history to Parquet (hive-partitioned) or CSV files
+        Command::Export(ex) => {
+            let datasets = ex.datasets.0;
+            if ex.out.as_os_str() == "-" && (ex.format != ExportFormat::Csv || datasets.len() != 1) {
+                return Err(anyhow!("--out - writes a single CSV dataset to stdout"));
+            }
+            let (from, to) = ex.range.bounds()?;
+            let filter = ExportFilter { token: ex.token, exchange: ex.exchange, from, to, limit: None };
+            let pool = migrations::create_pool_with_mode(mode).await;
+
+            for dataset in &datasets {
+                let columns = dataset.columns();
+                let (rows, target) = match ex.format {
+                    ExportFormat::Parquet => {
+                        let dir = ex.out.join(dataset.name());
+                        let mut sink = ParquetDirSink::new(dir.clone(), columns);
+                        let rows = export::export(&pool, *dataset, &filter, &mut sink).await?;
+                        let files = sink.finish()?;
+                        (rows, format!("{} ({} file(s))", dir.display(), files))
+                    }
+                    ExportFormat::Csv if ex.out.as_os_str() == "-" => {
+                        let mut sink = CsvSink::new(std::io::stdout(), columns)?;
+                        let rows = export::export(&pool, *dataset, &filter, &mut sink).await?;
+                        sink.finish()?;
+                        (rows, "stdout".to_string())
+                    }
+                    ExportFormat::Csv => {
+                        std::fs::create_dir_all(&ex.out)?;
+                        let path = ex.out.join(format!("{}.csv", dataset.name()));
+                        let file = std::fs::File::create(&path).with_context(|| format!("create {}", path.display()))?;
+                        let mut sink = CsvSink::new(std::io::BufWriter::new(file), columns)?;
+                        let rows = export::export(&pool, *dataset, &filter, &mut sink).await?;
+                        sink.finish()?.flush()?;
+                        (rows, path.display().to_string())
+                    }
+                };
+                info!("export: {} -> {}: {} row(s)", dataset.name(), target, rows);
+            }
             Ok(())
         }
 
-        "help" | "-h" | "--help" => {
-            print_help();
+        // Load funding history from CSV / Parquet / JSONL files
+        Command::Import(args) => {
+            let opts = args.options()?;
+            let pool = migrations::create_pool_with_mode(mode).await;
+            let report = import::import(&pool, &args.path, &opts)
+                .await
+                .with_context(|| format!("import of {} failed", args.path.display()))?;
+            if args.json {
+                println!("{}", serde_json::to_string_pretty(&report)?);
+            } else {
+                print_import_report(&report, opts.dry_run);
+            }
+            if report.valid == 0 {
+                return Err(anyhow!("no valid rows in {}", args.path.display()));
+            }
             Ok(())
         }
 
-        other => Err(anyhow!("unknown subcommand: {}\n\n{}", other, HELP)),
+        Command::Completions { shell } => {
+            clap_complete::generate(shell, &mut Cli::command(), "sync", &mut std::io::stdout());
+            Ok(())
+        }
     }
 }
 
 /* ----------------- Helpers (DB & runs) ----------------- */
 
-async fn run_init(spec: Option<TimeSpec>) -> Result<()> {
-    let pool = migrations::create_pool().await;
+async fn run_init(spec: Option<TimeSpec>, mode: MigrationMode) -> Result<()> {
+    let pool = migrations::create_pool_with_mode(mode).await;
+    let store = PgStore::new(pool);
     let spec = spec.unwrap_or(TimeSpec::SinceLastOrLookbackHours(24));
 
     info!("init: markets (all active)");
-    refresh_all_markets(&pool)
+    refresh_all_markets(&store)
         .await
         .context("refresh_all_markets failed")?;
 
     info!("init: funding (all active), window={:?}", spec);
-    run_funding_all(&pool, spec).await?;
+    run_funding_all(&store, spec, &MarketFilter::default()).await?;
 
     info!("init: stats (all active)");
-    collect_daily_market_stats(&pool)
+    collect_daily_market_stats(&store)
         .await
         .context("collect_daily_market_stats failed")?;
 
@@ -228,138 +1006,37 @@ async fn collect_stats_for_single_exchange(
     exchange_id: i32,
     exchange_name: &str,
     mode: RunMode, // NEW
+    filter: &MarketFilter,
 ) -> anyhow::Result<()> {
-    use backend::db::insert::{insert_market_stats, insert_market_stats_by_symbol};
+    use backend::db::insert::insert_market_stats_by_symbol;
     use backend::exchanges::shared::types::NormalizedMarketStats;
-    use tracing::{info, warn};
-
-    match lower(exchange_name).as_str() {
-        "paradex" => {
-            use backend::exchanges::paradex::api::{client::ParadexClient, endpoints::ApiEnvironment as ParadexEnv};
-            use backend::exchanges::paradex::handler::handler::parse_paradex_market_stats;
-
-            let client = ParadexClient::new(ParadexEnv::Mainnet);
-
-            match mode {
-                RunMode::Backfill => {
-                    // No DB SELECT — get symbols straight from the API
-                    let symbols = fetch_market_symbols_from_api(exchange_name).await?;
-                    let mut owned: Vec<(String, NormalizedMarketStats)> = Vec::with_capacity(symbols.len());
-
-                    for sym in symbols {
-                        let raw = client.get_markets_summary(&sym).await?;
-                        let stats = parse_paradex_market_stats(&raw)?;
-                        if let Some(stat) = stats.into_iter().find(|s| s.market_symbol == sym) {
-                            owned.push((sym, stat));
-                        }
-                    }
 
-                    if owned.is_empty() {
-                        info!("stats/backfill: no rows for {} (exchange_id={})", exchange_name, exchange_id);
-                        return Ok(());
-                    }
-
-                    let borrowed: Vec<(String, &NormalizedMarketStats)> =
-                        owned.iter().map(|(sym, s)| (sym.clone(), s)).collect();
-                    insert_market_stats_by_symbol(pool, exchange_id, &borrowed).await?;
-                    info!("stats/backfill: inserted {} rows for {} (exchange_id={})", borrowed.len(), exchange_name, exchange_id);
-                }
-
-                RunMode::Normal => {
-                    // Existing path — read markets from DB
-                    let markets = sqlx::query!(
-                        "SELECT id, market_symbol
-                         FROM markets
-                         WHERE exchange_id = $1 AND is_active = true
-                         ORDER BY market_symbol",
-                        exchange_id
-                    )
-                    .fetch_all(pool)
-                    .await?;
-
-                    let mut owned: Vec<(i32, NormalizedMarketStats)> = Vec::with_capacity(markets.len());
-                    for m in markets {
-                        let raw = client.get_markets_summary(&m.market_symbol).await?;
-                        let stats = parse_paradex_market_stats(&raw)?;
-                        if let Some(stat) = stats.into_iter().find(|s| s.market_symbol == m.market_symbol) {
-                            owned.push((m.id, stat));
-                        }
-                    }
-
-                    if owned.is_empty() {
-                        info!("stats: no rows for {} (exchange_id={})", exchange_name, exchange_id);
-                        return Ok(());
-                    }
-
-                    let borrowed: Vec<(i32, &NormalizedMarketStats)> =
-                        owned.iter().map(|(mid, s)| (*mid, s)).collect();
-                    insert_market_stats(pool, &borrowed).await?;
-                    info!("stats: inserted {} rows for {} (exchange_id={})", borrowed.len(), exchange_name, exchange_id);
-                }
+    match mode {
+        RunMode::Backfill => {
+            // No DB SELECT — get symbols straight from the API
+            let symbols: Vec<String> = fetch_market_symbols_from_api(exchange_name)
+                .await?
+                .into_iter()
+                .filter(|sym| filter.matches(sym, base_token(sym)))
+                .collect();
+            let fetched = fetch_market_stats(exchange_name, &symbols).await?;
+            for (sym, e) in &fetched.failed {
+                warn!("stats/backfill: {} {} failed: {:#}", exchange_name, sym, e);
             }
-        }
-
-        "extended" => {
-            use backend::exchanges::extended::api::{client::ExtendedClient, endpoints::ApiEnvironment as ExtendedEnv};
-            use backend::exchanges::extended::handler::handler::parse_extended_market_stats;
 
-            let client = ExtendedClient::new(ExtendedEnv::Mainnet);
-
-            match mode {
-                RunMode::Backfill => {
-                    let symbols = fetch_market_symbols_from_api(exchange_name).await?;
-                    let mut owned: Vec<(String, NormalizedMarketStats)> = Vec::with_capacity(symbols.len());
-
-                    for sym in symbols {
-                        let raw = client.get_market_stats(&sym).await?;
-                        let stat = parse_extended_market_stats(&raw, &sym)?;
-                        owned.push((sym, stat));
-                    }
-
-                    if owned.is_empty() {
-                        info!("stats/backfill: no rows for {} (exchange_id={})", exchange_name, exchange_id);
-                        return Ok(());
-                    }
-
-                    let borrowed: Vec<(String, &NormalizedMarketStats)> =
-                        owned.iter().map(|(sym, s)| (sym.clone(), s)).collect();
-                    insert_market_stats_by_symbol(pool, exchange_id, &borrowed).await?;
-                    info!("stats/backfill: inserted {} rows for {} (exchange_id={})", borrowed.len(), exchange_name, exchange_id);
-                }
-
-                RunMode::Normal => {
-                    let markets = sqlx::query!(
-                        "SELECT id, market_symbol
-                         FROM markets
-                         WHERE exchange_id = $1 AND is_active = true
-                         ORDER BY market_symbol",
-                        exchange_id
-                    )
-                    .fetch_all(pool)
-                    .await?;
-
-                    let mut owned: Vec<(i32, NormalizedMarketStats)> = Vec::with_capacity(markets.len());
-                    for m in markets {
-                        let raw = client.get_market_stats(&m.market_symbol).await?;
-                        let stat = parse_extended_market_stats(&raw, &m.market_symbol)?;
-                        owned.push((m.id, stat));
-                    }
-
-                    if owned.is_empty() {
-                        info!("stats: no rows for {} (exchange_id={})", exchange_name, exchange_id);
-                        return Ok(());
-                    }
-
-                    let borrowed: Vec<(i32, &NormalizedMarketStats)> =
-                        owned.iter().map(|(mid, s)| (*mid, s)).collect();
-                    insert_market_stats(pool, &borrowed).await?;
-                    info!("stats: inserted {} rows for {} (exchange_id={})", borrowed.len(), exchange_name, exchange_id);
-                }
+            if fetched.stats.is_empty() {
+                info!("stats/backfill: no rows for {} (exchange_id={})", exchange_name, exchange_id);
+                return Ok(());
             }
+
+            let borrowed: Vec<(String, &NormalizedMarketStats)> =
+                fetched.stats.iter().map(|s| (s.market_symbol.clone(), s)).collect();
+            insert_market_stats_by_symbol(pool, exchange_id, &borrowed).await?;
+            info!("stats/backfill: inserted {} rows for {} (exchange_id={})", borrowed.len(), exchange_name, exchange_id);
         }
 
-        _ => {
-            warn!("stats: unsupported exchange '{}'", exchange_name);
+        RunMode::Normal => {
+            collect_stats_for_exchange(&PgStore::new(pool.clone()), exchange_id, exchange_name, filter).await?;
         }
     }
 
@@ -368,15 +1045,13 @@ async fn collect_stats_for_single_exchange(
 
 
 
-async fn run_funding_all(pool: &PgPool, spec: TimeSpec) -> Result<()> {
-    let exchanges = sqlx::query!(
-        "SELECT id, name FROM exchanges WHERE is_active = true ORDER BY name"
-    )
-    .fetch_all(pool)
-    .await?;
+async fn run_funding_all(store: &PgStore, spec: TimeSpec, filter: &MarketFilter) -> Result<()> {
+    let exchanges = store.active_exchanges().await?;
+    let ids: Vec<i32> = exchanges.iter().map(|e| e.id).collect();
+    check_selectors(store, &ids, filter).await?;
 
     for ex in exchanges {
-        if let Err(e) = collect_funding_for_exchange_with_spec(pool, ex.id, &ex.name, spec.clone()).await {
+        if let Err(e) = collect_funding_for_exchange_with_spec(store, ex.id, &ex.name, spec.clone(), filter).await {
             error!("funding failed for {}: {:?}", ex.name, e);
         }
     }
@@ -421,123 +1096,192 @@ async fn ensure_exchange_row(pool: &PgPool, name: &str) -> Result<(i32, String)>
     Ok((inserted.id, inserted.name))
 }
 
-/* ----------------- Arg parsing helpers ----------------- */
+/* ------------------- Output helpers ------------------- */
 
 fn lower(s: &str) -> String { s.trim().to_ascii_lowercase() }
 
-fn parse_time_spec(args: &[String]) -> Result<Option<TimeSpec>> {
-    let mut i = 0;
-    let mut spec: Option<TimeSpec> = None;
-
-    while i < args.len() {
-        match args[i].as_str() {
-            "--hours" | "-h" => {
-                if i + 1 >= args.len() { return Err(anyhow!("--hours requires a value")); }
-                let hours: u64 = args[i + 1].parse().map_err(|_| anyhow!("invalid --hours value"))?;
-                spec = Some(TimeSpec::LookbackHours(hours));
-                i += 2;
-            }
-            "--between" | "-b" => {
-                if i + 2 >= args.len() { return Err(anyhow!("--between requires START_MS END_MS")); }
-                let start_ms: u64 = args[i + 1].parse().map_err(|_| anyhow!("invalid START_MS"))?;
-                let end_ms: u64 = args[i + 2].parse().map_err(|_| anyhow!("invalid END_MS"))?;
-                spec = Some(TimeSpec::Between { start_ms, end_ms });
-                i += 3;
-            }
-            "--since-last" | "-s" => {
-                if i + 1 >= args.len() { return Err(anyhow!("--since-last requires a value")); }
-                let hours: u64 = args[i + 1].parse().map_err(|_| anyhow!("invalid --since-last value"))?;
-                spec = Some(TimeSpec::SinceLastOrLookbackHours(hours));
-                i += 2;
+/// `--dry-run`: plan every exchange, then print the plans (or JSON) and totals.
+async fn print_funding_plan(
+    store: &PgStore,
+    exchanges: &[(i32, String)],
+    spec: &TimeSpec,
+    filter: &MarketFilter,
+    json: bool,
+) -> Result<()> {
+    let mut plans: Vec<FundingPlan> = Vec::with_capacity(exchanges.len());
+    for (id, name) in exchanges {
+        plans.push(plan_funding_for_exchange(store, *id, name, spec, filter).await?);
+    }
+    if json {
+        println!("{}", serde_json::to_string_pretty(&plans)?);
+        return Ok(());
+    }
+
+    // whole seconds keep the columns aligned; --json has the exact values
+    let fmt = |ms: u64| {
+        time::OffsetDateTime::from_unix_timestamp((ms / 1000) as i64)
+            .ok()
+            .and_then(|ts| ts.format(&time::format_description::well_known::Rfc3339).ok())
+            .unwrap_or_else(|| ms.to_string())
+    };
+    println!("funding plan, window={:?}", spec);
+    for p in &plans {
+        println!(
+            "\n{} (id={}): {} market(s), ~{} row(s), ~{} request(s)",
+            p.exchange, p.exchange_id, p.markets.len(), p.est_rows, p.est_requests
+        );
+        println!("  {:<24} {:<22} {:<22} {:<22} {:>10} {:>9}", "market", "last stored", "from", "to", "~rows", "~requests");
+        for m in &p.markets {
+            let last = m.last_ts.map(|ts| fmt(ts.unix_timestamp() as u64 * 1000)).unwrap_or_else(|| "-".into());
+            match &m.skip {
+                Some(reason) => println!("  {:<24} {:<22} skip: {}", m.market_symbol, last, reason),
+                None => println!(
+                    "  {:<24} {:<22} {:<22} {:<22} {:>10} {:>9}",
+                    m.market_symbol, last, fmt(m.start_ms), fmt(m.end_ms), m.est_rows, m.est_requests
+                ),
             }
-            _other => i += 1, // ignore unrelated flags here
         }
     }
+    println!(
+        "\ntotal: {} exchange(s), {} market(s), ~{} row(s), ~{} request(s); nothing written",
+        plans.len(),
+        plans.iter().map(|p| p.markets.len()).sum::<usize>(),
+        plans.iter().map(|p| p.est_rows).sum::<u64>(),
+        plans.iter().map(|p| p.est_requests).sum::<u64>()
+    );
+    Ok(())
+}
 
-    Ok(spec)
+fn print_backfill_jobs(jobs: &[JobSummary]) {
+    let fmt = |ts: time::OffsetDateTime| ts.format(&time::format_description::well_known::Rfc3339).unwrap_or_default();
+    println!(
+        "{:>6} {:<12} {:<8} {:>7} {:>9} {:>12} {:>9}  {:<30} error",
+        "job", "exchange", "status", "done", "markets", "rows", "requests", "updated"
+    );
+    for j in jobs {
+        println!(
+            "{:>6} {:<12} {:<8} {:>6.1}% {:>4}/{:<4} {:>12} {:>9}  {:<30} {}",
+            j.id,
+            j.exchange,
+            j.status,
+            j.progress * 100.0,
+            j.markets_done,
+            j.markets,
+            j.rows,
+            j.requests,
+            fmt(j.updated_at),
+            j.error.as_deref().unwrap_or("")
+        );
+    }
 }
 
-fn parse_exchange_and_spec(args: &[String]) -> Result<(Option<String>, Option<TimeSpec>)> {
-    let mut i = 0;
-    let mut exchange: Option<String> = None;
-    let mut spec: Option<TimeSpec> = None;
+fn print_prune_steps(label: &str, steps: &[PruneStep]) {
+    let fmt = |ts: time::OffsetDateTime| ts.format(&time::format_description::well_known::Rfc3339).unwrap_or_default();
+    println!("{label}:");
+    for s in steps {
+        println!(
+            "  {:<24} {:<14} before {:<32} {:>10} row(s){}",
+            s.table,
+            s.action,
+            fmt(s.cutoff),
+            s.rows,
+            if s.partitions.is_empty() { String::new() } else { format!(" (drop {})", s.partitions.join(", ")) }
+        );
+    }
+}
 
-    while i < args.len() {
-        match args[i].as_str() {
-            "--exchange" | "-e" => {
-                if i + 1 >= args.len() { return Err(anyhow!("--exchange requires a value")); }
-                exchange = Some(args[i + 1].clone());
-                i += 2;
-            }
-            "--name" => {
-                if i + 1 >= args.len() { return Err(anyhow!("--name requires a value")); }
-                exchange = Some(args[i + 1].clone());
-                i += 2;
-            }
-            "--hours" | "-h" | "--between" | "-b" | "--since-last" | "-s" => {
-                // delegate to parse_time_spec on the tail
-                spec = parse_time_spec(&args[i..])?;
-                break;
-            }
-            _ => i += 1,
-        }
+fn print_import_report(r: &ImportReport, dry_run: bool) {
+    println!(
+        "{} file(s), {} row(s): {} valid, {} invalid",
+        r.files,
+        r.rows,
+        r.valid,
+        r.invalid.values().sum::<u64>()
+    );
+    for (reason, n) in &r.invalid {
+        println!("  invalid  {:<44} {:>10}", reason, n);
+    }
+    let verb = if dry_run { "would write" } else { "written" };
+    for (exchange, n) in &r.exchanges {
+        println!("  {verb:<8} {:<44} {:>10}", exchange, n);
     }
+    println!("{} market(s), {} new (added inactive)", r.markets, r.markets_created);
+}
 
-    Ok((exchange, spec))
+/// (exchange, UTC day)
+type PartitionKey = (String, time::Date);
+
+/// Parquet under `<dir>/exchange=<name>/date=<YYYY-MM-DD>/part-0.parquet` (hive
+/// layout, so pandas/polars/DuckDB read the partition keys back as columns).
+/// `exchange` is in the path and therefore left out of the files.
+struct ParquetDirSink {
+    dir: PathBuf,
+    columns: Vec<Column>,
+    current: Option<(PartitionKey, ParquetSink<std::io::BufWriter<std::fs::File>>)>,
+    files: usize,
 }
 
-fn parse_exchange_add(args: &[String]) -> Result<(String, Option<TimeSpec>)> {
-    let mut i = 0;
-    let mut name: Option<String> = None;
-    let mut spec: Option<TimeSpec> = None;
+impl ParquetDirSink {
+    fn new(dir: PathBuf, columns: &[Column]) -> Self {
+        let columns = columns.iter().copied().filter(|c| *c != Column::Exchange).collect();
+        Self { dir, columns, current: None, files: 0 }
+    }
 
-    while i < args.len() {
-        match args[i].as_str() {
-            "--name" => {
-                if i + 1 >= args.len() { return Err(anyhow!("--name requires a value")); }
-                name = Some(args[i + 1].clone());
-                i += 2;
-            }
-            "--hours" | "-h" | "--between" | "-b" | "--since-last" | "-s" => {
-                spec = parse_time_spec(&args[i..])?;
-                break;
-            }
-            _ => i += 1,
+    fn close_current(&mut self) -> Result<()> {
+        if let Some((_, sink)) = self.current.take() {
+            sink.finish()?.flush()?;
         }
+        Ok(())
     }
 
-    let name = name.ok_or_else(|| anyhow!("exchange add requires --name <NAME>"))?;
-    Ok((name, spec))
+    /// Close the last file; returns how many were written.
+    fn finish(mut self) -> Result<usize> {
+        self.close_current()?;
+        Ok(self.files)
+    }
 }
 
-/* ---------------------- Help text ---------------------- */
-
-const HELP: &str = r#"sync CLI
-
-Commands:
-  init [--hours N | --between START_MS END_MS | --since-last N]
-      Refresh markets, then funding, then stats for all active exchanges.
-      If no time flag is provided, defaults to --since-last 24.
-
-  markets [--exchange NAME]
-      Sync markets for all active exchanges or a single exchange.
-
-  stats [--exchange NAME]
-      Collect latest market stats (OI USD + 24h volume) for all or one exchange.
-
-  funding [--exchange NAME] [--hours N | --between START_MS END_MS | --since-last N]
-      Collect funding rates over a specified window (default --since-last 24).
-
-  exchange add --name NAME [--hours N | --between START_MS END_MS | --since-last N]
-      Ensure the exchange row exists and run markets -> funding -> stats for that exchange.
-
-  backfill --exchange NAME [--hours N | --between START_MS END_MS | --since-last N]
-      Backfill one exchange end-to-end over the given window.
+impl ExportSink for ParquetDirSink {
+    // Rows arrive ordered by exchange and UTC day, so each partition is one run
+    fn write(&mut self, rows: &[ExportRow]) -> Result<()> {
+        let day = |r: &ExportRow| r.ts.to_offset(time::UtcOffset::UTC).date();
+        for run in rows.chunk_by(|a, b| a.exchange == b.exchange && day(a) == day(b)) {
+            let key = (run[0].exchange.clone(), day(&run[0]));
+            if self.current.as_ref().map(|(k, _)| k) != Some(&key) {
+                self.close_current()?;
+                let dir = self.dir.join(format!("exchange={}", key.0)).join(format!("date={}", key.1));
+                std::fs::create_dir_all(&dir).with_context(|| format!("create {}", dir.display()))?;
+                let file = std::fs::File::create(dir.join("part-0.parquet"))?;
+                let sink = ParquetSink::new(std::io::BufWriter::new(file), &self.columns)?;
+                self.current = Some((key, sink));
+                self.files += 1;
+            }
+            if let Some((_, sink)) = self.current.as_mut() {
+                sink.write(run)?;
+            }
+        }
+        Ok(())
+    }
+}
 
-  help
-      Show this help.
-"#;
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_duration_units() {
+        assert_eq!(parse_duration("48"), Ok(Duration::hours(48)));
+        assert_eq!(parse_duration("90m"), Ok(Duration::minutes(90)));
+        assert_eq!(parse_duration(" 7d "), Ok(Duration::days(7)));
+        assert_eq!(parse_duration("2w"), Ok(Duration::weeks(2)));
+        assert!(parse_duration("0h").is_err());
+        assert!(parse_duration("3y").is_err());
+    }
 
-fn print_help() {
-    eprintln!("{HELP}");
+    #[test]
+    fn parse_duration_rejects_overflow() {
+        assert!(parse_duration("999999999999999w").is_err());
+        assert!(parse_duration("9223372036854775807m").is_err());
+        assert!(parse_duration("99999999999999999999").is_err());
+    }
 }
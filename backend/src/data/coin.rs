@@ -1,10 +1,9 @@
 // src/data/coin.rs
 use anyhow::Result;
-use sqlx::PgPool;
 use tracing::info;
 
-use crate::db::insert::upsert_markets;
 use crate::exchanges::shared::types::NormalizedMarket;
+use crate::store::FundingStore;
 
 use crate::exchanges::paradex::api::{client::ParadexClient, endpoints::ApiEnvironment as ParadexEnv};
 use crate::exchanges::extended::api::{client::ExtendedClient, endpoints::ApiEnvironment as ExtendedEnv};
@@ -55,16 +54,14 @@ fn make_adapter(name: &str) -> Option<ExchangeAdapter> {
 }
 
 
-pub async fn refresh_all_markets(pool: &PgPool) -> Result<()> {
-    let exchanges = sqlx::query!("SELECT id, name FROM exchanges WHERE is_active = true ORDER BY name")
-        .fetch_all(pool)
-        .await?;
+pub async fn refresh_all_markets(store: &dyn FundingStore) -> Result<()> {
+    let exchanges = store.active_exchanges().await?;
 
     for exch in exchanges {
         if let Some(adapter) = make_adapter(&exch.name) {
             let out = adapter.fetch_markets().await?;
             if !out.is_empty() {
-                upsert_markets(pool, exch.id, &out).await?;
+                store.upsert_markets(exch.id, &out).await?;
             }
             info!(
                 "upserted {} markets for {} (exchange_id={})",
@@ -82,14 +79,14 @@ pub async fn refresh_all_markets(pool: &PgPool) -> Result<()> {
 
 
 pub async fn refresh_markets_for_exchange(
-    pool: &PgPool,
+    store: &dyn FundingStore,
     exchange_id: i32,
     exchange_name: &str,
 ) -> Result<()> {
     if let Some(adapter) = make_adapter(exchange_name) {
         let out = adapter.fetch_markets().await?;
         if !out.is_empty() {
-            upsert_markets(pool, exchange_id, &out).await?;
+            store.upsert_markets(exchange_id, &out).await?;
         }
         info!(
             "upserted {} markets for {} (exchange_id={})",
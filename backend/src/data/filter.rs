@@ -0,0 +1,107 @@
+// src/data/filter.rs
+use anyhow::{bail, Result};
+
+use crate::store::FundingStore;
+
+/// Which markets a funding or stats run touches. With no `markets` and no
+/// `tokens` every market is selected; `exclude` always wins.
+#[derive(Debug, Clone, Default)]
+pub struct MarketFilter {
+    /// Market symbols, e.g. `BTC-USD-PERP`.
+    pub markets: Vec<String>,
+    /// Base tokens, e.g. `BTC`.
+    pub tokens: Vec<String>,
+    /// Market symbols or base tokens to leave out.
+    pub exclude: Vec<String>,
+}
+
+impl MarketFilter {
+    pub fn is_empty(&self) -> bool {
+        self.markets.is_empty() && self.tokens.is_empty() && self.exclude.is_empty()
+    }
+
+    /// Case-insensitive; `token` is the market's base token.
+    pub fn matches(&self, market_symbol: &str, token: &str) -> bool {
+        let any = |list: &[String], s: &str| list.iter().any(|x| x.trim().eq_ignore_ascii_case(s));
+        let selected = (self.markets.is_empty() && self.tokens.is_empty())
+            || any(&self.markets, market_symbol)
+            || any(&self.tokens, token);
+        selected && !any(&self.exclude, market_symbol) && !any(&self.exclude, token)
+    }
+
+    /// `--market` / `--token` selectors that match none of `markets`
+    /// (market symbol, base token), e.g. `--market ETHH-USD-PERP`.
+    pub fn unmatched<'a>(&self, markets: impl IntoIterator<Item = (&'a str, &'a str)>) -> Vec<String> {
+        let markets: Vec<(&str, &str)> = markets.into_iter().collect();
+        let symbols = self
+            .markets
+            .iter()
+            .filter(|x| !markets.iter().any(|(sym, _)| x.trim().eq_ignore_ascii_case(sym)))
+            .map(|x| format!("--market {}", x.trim()));
+        let tokens = self
+            .tokens
+            .iter()
+            .filter(|x| !markets.iter().any(|(_, token)| x.trim().eq_ignore_ascii_case(token)))
+            .map(|x| format!("--token {}", x.trim()));
+        symbols.chain(tokens).collect()
+    }
+}
+
+/// Fail if a `--market` / `--token` selector matches no active market on any
+/// of `exchange_ids`, so a typo isn't a silent no-op.
+pub async fn check_selectors(store: &dyn FundingStore, exchange_ids: &[i32], filter: &MarketFilter) -> Result<()> {
+    if filter.markets.is_empty() && filter.tokens.is_empty() {
+        return Ok(());
+    }
+    let mut markets = Vec::new();
+    for id in exchange_ids {
+        markets.extend(store.active_markets(*id).await?);
+    }
+    let unmatched = filter.unmatched(markets.iter().map(|m| (m.market_symbol.as_str(), m.token.as_str())));
+    if !unmatched.is_empty() {
+        bail!("no active market matches {}", unmatched.join(", "));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter(markets: &[&str], tokens: &[&str], exclude: &[&str]) -> MarketFilter {
+        let v = |xs: &[&str]| xs.iter().map(|s| s.to_string()).collect();
+        MarketFilter { markets: v(markets), tokens: v(tokens), exclude: v(exclude) }
+    }
+
+    #[test]
+    fn empty_selects_everything() {
+        assert!(MarketFilter::default().matches("BTC-USD-PERP", "BTC"));
+    }
+
+    #[test]
+    fn markets_and_tokens_are_a_union() {
+        let f = filter(&["eth-usd-perp"], &["btc"], &[]);
+        assert!(f.matches("BTC-USD-PERP", "BTC"));
+        assert!(f.matches("ETH-USD-PERP", "ETH"));
+        assert!(!f.matches("SOL-USD-PERP", "SOL"));
+    }
+
+    #[test]
+    fn unmatched_selectors() {
+        let f = filter(&["btc-usd-perp", "ETHH-USD-PERP"], &["sol", "XYZ"], &["BTC"]);
+        let markets = [("BTC-USD-PERP", "BTC"), ("SOL-USD-PERP", "SOL")];
+        assert_eq!(f.unmatched(markets), vec!["--market ETHH-USD-PERP", "--token XYZ"]);
+        assert!(MarketFilter::default().unmatched([]).is_empty());
+    }
+
+    #[test]
+    fn exclude_wins() {
+        let f = filter(&[], &["BTC", "ETH"], &[" eth "]);
+        assert!(f.matches("BTC-USD-PERP", "BTC"));
+        assert!(!f.matches("ETH-USD-PERP", "ETH"));
+
+        let f = filter(&[], &[], &["SOL-USD-PERP"]);
+        assert!(f.matches("SOL-USD", "SOL"));
+        assert!(!f.matches("SOL-USD-PERP", "SOL"));
+    }
+}
@@ -1,13 +1,16 @@
 // src/data/funding.rs
-use anyhow::Result;
+use anyhow::{bail, Result};
 use chrono::Utc;
-use sqlx::PgPool;
+use serde::Serialize;
+use time::OffsetDateTime;
 use tracing::{info, warn};
 
-use futures::{stream, StreamExt, TryStreamExt};
+use futures::{stream, StreamExt};
+use tokio::sync::mpsc;
 
-use crate::db::insert::insert_funding_rates;
-use crate::exchanges::shared::types::NormalizedFundingRate;
+use crate::data::filter::MarketFilter;
+use crate::exchanges::shared::types::{FundingPage, NormalizedFundingRate};
+use crate::store::FundingStore;
 
 use crate::exchanges::extended::api::{client::ExtendedClient, endpoints::ApiEnvironment as ExtendedEnv};
 use crate::exchanges::extended::handler::handler::parse_extended_funding;
@@ -18,7 +21,8 @@ use crate::exchanges::paradex::handler::handler::parse_paradex_funding;
 fn lower(s: &str) -> String { s.trim().to_ascii_lowercase() }
 
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum TimeSpec {
     Between { start_ms: u64, end_ms: u64 },
     LookbackHours(u64),
@@ -43,143 +47,354 @@ impl TimeSpec {
 }
 
 
-async fn fetch_funding_for_market(
+const DAY_MS: u64 = 86_400_000;
+
+/// Upper bound on pages followed for one window, in case a venue keeps
+/// returning a cursor.
+pub const MAX_PAGES_PER_WINDOW: usize = 10_000;
+
+/// One page of `[start_ms, end_ms]`; pass the previous page's `next` to continue.
+pub async fn fetch_funding_page(
     exchange_name: &str,
     market_symbol: &str,
     start_ms: u64,
     end_ms: u64,
-) -> Result<Vec<NormalizedFundingRate>> {
+    cursor: Option<&str>,
+) -> Result<FundingPage> {
     match lower(exchange_name).as_str() {
         "paradex" => {
             let client = ParadexClient::new(ParadexEnv::Mainnet);
             let raw = client
-                .get_funding_data(market_symbol, Some(start_ms), Some(end_ms))
+                .get_funding_data(market_symbol, Some(start_ms), Some(end_ms), cursor)
                 .await?;
             Ok(parse_paradex_funding(&raw)?)
         }
         "extended" => {
             let client = ExtendedClient::new(ExtendedEnv::Mainnet);
             let raw = client
-                .get_funding(market_symbol, Some(start_ms), Some(end_ms))
+                .get_funding(market_symbol, Some(start_ms), Some(end_ms), cursor)
                 .await?;
             Ok(parse_extended_funding(&raw)?)
         }
         other => {
-            warn!("fetch_funding_for_market: unsupported exchange '{}'", other);
-            Ok(Vec::new())
+            warn!("fetch_funding_page: unsupported exchange '{}'", other);
+            Ok(FundingPage::default())
+        }
+    }
+}
+
+/// Inclusive `[from, to]` windows of at most `chunk_ms` covering
+/// `[start_ms, end_ms]` in ascending order, each starting 1 ms after the
+/// previous one ends (venue bounds are inclusive).
+pub fn funding_chunks(start_ms: u64, end_ms: u64, chunk_ms: u64) -> impl Iterator<Item = (u64, u64)> {
+    let span = chunk_ms.max(1) - 1;
+    let window = move |from: u64| (from, from.saturating_add(span).min(end_ms));
+    std::iter::successors((start_ms <= end_ms).then(|| window(start_ms)), move |&(_, to)| {
+        (to < end_ms).then(|| window(to + 1))
+    })
+}
+
+/// Fetch `[start_ms, end_ms]` for one market in ascending chunks, following
+/// pages within each chunk, and send every completed chunk to `tx`. Pages come
+/// newest first, so only whole chunks are sent: whatever reaches the writer is
+/// a contiguous prefix of the window, and `SinceLastOrLookbackHours` resumes
+/// cleanly after a failure.
+async fn stream_funding_for_market(
+    exchange_name: &str,
+    m: &MarketFetch,
+    chunk_ms: u64,
+    tx: &mpsc::Sender<Vec<(i32, NormalizedFundingRate)>>,
+) -> Result<()> {
+    for (cursor_ms, chunk_end) in funding_chunks(m.start_ms, m.end_ms, chunk_ms) {
+        let mut rows = Vec::new();
+        let mut cursor: Option<String> = None;
+        let mut pages = 0;
+        loop {
+            let page = fetch_funding_page(exchange_name, &m.market_symbol, cursor_ms, chunk_end, cursor.as_deref()).await?;
+            rows.extend(page.rows.into_iter().map(|r| (m.market_id, r)));
+            cursor = page.next;
+            pages += 1;
+            if cursor.is_none() {
+                break;
+            }
+            if pages >= MAX_PAGES_PER_WINDOW {
+                warn!(
+                    "{} {}: stopped after {} pages for window {}..{}",
+                    exchange_name, m.market_symbol, pages, cursor_ms, chunk_end
+                );
+                break;
+            }
+        }
+        if !rows.is_empty() && tx.send(rows).await.is_err() {
+            bail!("funding writer stopped");
+        }
+    }
+    Ok(())
+}
+
+
+/// Shape of an exchange's funding history endpoint.
+#[derive(Debug, Clone, Copy)]
+pub struct FundingSource {
+    /// Approximate spacing of the rows it returns.
+    pub sample_ms: u64,
+    /// Rows per page.
+    pub page_rows: u64,
+}
+
+impl FundingSource {
+    /// Span fetched and written as one unit: at least a day, or one full page
+    /// where pages span longer.
+    pub fn chunk_ms(&self) -> u64 {
+        DAY_MS.max(self.sample_ms * self.page_rows)
+    }
+}
+
+pub fn funding_source(exchange_name: &str) -> Option<FundingSource> {
+    match lower(exchange_name).as_str() {
+        // funding index snapshots every ~5s, page_size=500
+        "paradex" => Some(FundingSource { sample_ms: 5_000, page_rows: 500 }),
+        // hourly rates
+        "extended" => Some(FundingSource {
+            sample_ms: 3_600_000,
+            page_rows: crate::exchanges::extended::api::client::FUNDING_PAGE_LIMIT as u64,
+        }),
+        _ => None,
+    }
+}
+
+/// One market's resolved fetch window.
+#[derive(Debug, Clone, Serialize)]
+pub struct MarketFetch {
+    pub market_id: i32,
+    pub market_symbol: String,
+    /// Newest stored funding row (what `SinceLastOrLookbackHours` resumes from).
+    #[serde(with = "time::serde::rfc3339::option")]
+    pub last_ts: Option<OffsetDateTime>,
+    pub start_ms: u64,
+    pub end_ms: u64,
+    pub est_rows: u64,
+    pub est_requests: u64,
+    /// Set when the window is empty and the market is not fetched.
+    pub skip: Option<String>,
+}
+
+/// What a funding collection run will fetch for one exchange.
+#[derive(Debug, Clone, Serialize)]
+pub struct FundingPlan {
+    pub exchange_id: i32,
+    pub exchange: String,
+    pub spec: TimeSpec,
+    pub markets: Vec<MarketFetch>,
+    pub est_rows: u64,
+    pub est_requests: u64,
+}
+
+/// Resolve `time_spec` against the last stored timestamp of every active market
+/// `filter` selects. Reads only; `collect_funding_for_exchange_with_spec` runs the
+/// same plan.
+pub async fn plan_funding_for_exchange(
+    store: &dyn FundingStore,
+    exchange_id: i32,
+    exchange_name: &str,
+    time_spec: &TimeSpec,
+    filter: &MarketFilter,
+) -> Result<FundingPlan> {
+    let source = funding_source(exchange_name);
+    let mut markets = Vec::new();
+
+    let active = store.active_markets(exchange_id).await?;
+    for sel in filter.unmatched(active.iter().map(|m| (m.market_symbol.as_str(), m.token.as_str()))) {
+        warn!("funding: {} matches no active market on {}", sel, exchange_name);
+    }
+    for m in active {
+        if !filter.matches(&m.market_symbol, &m.token) {
+            continue;
         }
+        let last_ts = store.last_funding_ts(m.id).await?;
+        let (start_ms, end_ms) = time_spec.resolve(last_ts.map(|odt| odt.unix_timestamp() * 1000));
+
+        let (skip, est_rows, est_requests) = if start_ms > end_ms {
+            (Some(format!("invalid window start_ms={} > end_ms={}", start_ms, end_ms)), 0, 0)
+        } else if let Some(src) = source {
+            let rows = (end_ms - start_ms) / src.sample_ms;
+            (None, rows, rows.div_ceil(src.page_rows).max(1))
+        } else {
+            (Some(format!("unsupported exchange '{}'", exchange_name)), 0, 0)
+        };
+
+        markets.push(MarketFetch {
+            market_id: m.id,
+            market_symbol: m.market_symbol,
+            last_ts,
+            start_ms,
+            end_ms,
+            est_rows,
+            est_requests,
+            skip,
+        });
     }
+
+    Ok(FundingPlan {
+        exchange_id,
+        exchange: exchange_name.to_string(),
+        spec: time_spec.clone(),
+        est_rows: markets.iter().map(|m| m.est_rows).sum(),
+        est_requests: markets.iter().map(|m| m.est_requests).sum(),
+        markets,
+    })
 }
 
+/// Interval each venue pays funding on: Paradex every 8h, Extended hourly.
+/// `None` for venues we don't know, whose interval is left alone.
+fn native_interval_minutes(exchange_name: &str) -> Option<i32> {
+    match exchange_name.trim().to_ascii_lowercase().as_str() {
+        "paradex" => Some(480),
+        "extended" => Some(60),
+        _ => None,
+    }
+}
 
 pub async fn collect_funding_for_exchange_with_spec(
-    pool: &PgPool,
+    store: &dyn FundingStore,
     exchange_id: i32,
     exchange_name: &str,
     time_spec: TimeSpec,
+    filter: &MarketFilter,
 ) -> Result<()> {
-    // NEW: force interval to 8h (480 minutes), idempotent
-    let res = sqlx::query!(
-        r#"
-        UPDATE exchanges
-        SET funding_interval_minutes = 480, updated_at = NOW()
-        WHERE id = $1 AND (funding_interval_minutes IS NULL OR funding_interval_minutes <> 480)
-        "#,
-        exchange_id
-    )
-    .execute(pool)
-    .await?;
-    if res.rows_affected() > 0 {
-        info!("funding interval for {} (id={}) set to 480m", exchange_name, exchange_id);
+    // The venue's own interval, idempotent
+    if let Some(minutes) = native_interval_minutes(exchange_name)
+        && store.set_funding_interval(exchange_id, minutes).await?
+    {
+        info!("funding interval for {} (id={}) set to {}m", exchange_name, exchange_id, minutes);
     }
 
-    // 1) markets
-    let markets = sqlx::query!(
-        r#"
-        SELECT id, market_symbol
-        FROM markets
-        WHERE exchange_id = $1 AND is_active = true
-        ORDER BY market_symbol
-        "#,
-        exchange_id
-    )
-    .fetch_all(pool)
-    .await?;
+    // 1) markets and their windows
+    let plan = plan_funding_for_exchange(store, exchange_id, exchange_name, &time_spec, filter).await?;
 
-    if markets.is_empty() {
-        info!("no active markets for {}", exchange_name);
+    if plan.markets.is_empty() {
+        if filter.is_empty() {
+            info!("no active markets for {}", exchange_name);
+        } else {
+            info!("no active markets for {} match {:?}", exchange_name, filter);
+        }
         return Ok(());
     }
 
-    
     let conc: usize = std::env::var("SYNC_CONC_MARKETS")
         .ok()
         .and_then(|v| v.parse().ok())
         .unwrap_or(16);
+    // rows per insert; the default reaches SYNC_COPY_THRESHOLD so big backfills still COPY
+    let batch_rows: usize = std::env::var("SYNC_INSERT_BATCH")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(50_000)
+        .max(1);
+    let chunk_ms = funding_source(exchange_name).map_or(u64::MAX, |src| src.chunk_ms());
+
+    // 2) fetchers -> bounded channel -> one writer; a full channel parks the fetchers
+    let (tx, mut rx) = mpsc::channel::<Vec<(i32, NormalizedFundingRate)>>(conc.max(1));
 
-    let per_market_batches: Vec<Vec<(i32, NormalizedFundingRate)>> =
-        stream::iter(markets.into_iter())
-            .map(|m| {
-                let pool = pool.clone();
-                let exchange_name = exchange_name.to_string();
-                let time_spec = time_spec.clone();
-
-                async move {
-                    let last_ts_ms_opt = sqlx::query!(
-                        r#"SELECT MAX(timestamp) AS last_ts FROM funding_rates WHERE market_id = $1"#,
-                        m.id
-                    )
-                    .fetch_optional(&pool)
-                    .await?
-                    .and_then(|r| r.last_ts.map(|odt| odt.unix_timestamp() * 1000));
-
-                    let (start_ms, end_ms) = time_spec.resolve(last_ts_ms_opt);
-                    if start_ms > end_ms {
-                        warn!(
-                            "skip {} {}: invalid window start_ms={} > end_ms={}",
-                            exchange_name, m.market_symbol, start_ms, end_ms
-                        );
-                        return Ok::<Vec<(i32, NormalizedFundingRate)>, anyhow::Error>(Vec::new());
-                    }
-
-                    let rows = fetch_funding_for_market(&exchange_name, &m.market_symbol, start_ms, end_ms).await?;
-                    let out: Vec<(i32, NormalizedFundingRate)> = rows.into_iter().map(|r| (m.id, r)).collect();
-                    Ok::<Vec<(i32, NormalizedFundingRate)>, anyhow::Error>(out)
+    let fetch = async move {
+        let tx = &tx;
+        // a failing market doesn't stop the others; errors are reported once all are done
+        stream::iter(plan.markets)
+            .map(|m| async move {
+                if let Some(reason) = &m.skip {
+                    warn!("skip {} {}: {}", exchange_name, m.market_symbol, reason);
+                    return None;
                 }
+                stream_funding_for_market(exchange_name, &m, chunk_ms, tx)
+                    .await
+                    .map_err(|e| {
+                        warn!("{} {}: funding fetch failed: {:#}", exchange_name, m.market_symbol, e);
+                        e.context(format!("{} {}", exchange_name, m.market_symbol))
+                    })
+                    .err()
             })
             .buffer_unordered(conc)
-            .try_collect::<Vec<Vec<(i32, NormalizedFundingRate)>>>()
-            .await?;
+            .filter_map(|e| async move { e })
+            .collect::<Vec<anyhow::Error>>()
+            .await
+        // tx drops here, which lets the writer drain and finish
+    };
 
-    
-    let owned: Vec<(i32, NormalizedFundingRate)> =
-        per_market_batches.into_iter().flat_map(|v| v.into_iter()).collect();
+    let write = async {
+        let mut buf: Vec<(i32, NormalizedFundingRate)> = Vec::new();
+        let mut written = 0usize;
+        while let Some(rows) = rx.recv().await {
+            buf.extend(rows);
+            if buf.len() >= batch_rows {
+                written += flush_funding(store, exchange_id, &mut buf).await?;
+            }
+        }
+        written += flush_funding(store, exchange_id, &mut buf).await?;
+        Ok::<usize, anyhow::Error>(written)
+    };
 
-    if owned.is_empty() {
+    let (fetched, written) = tokio::join!(fetch, write);
+    let written = written?;
+    if written == 0 {
         info!("no funding rows to insert for {}", exchange_name);
-        return Ok(());
+    } else {
+        info!("inserted {} funding rows for {}", written, exchange_name);
+    }
+    // rows that made it through are kept; the next since-last run picks up after them
+    let failed = fetched.len();
+    if let Some(first) = fetched.into_iter().next() {
+        return Err(first.context(format!("{} market(s) failed", failed)));
     }
-
-    let borrowed: Vec<(i32, &NormalizedFundingRate)> =
-        owned.iter().map(|(mid, r)| (*mid, r)).collect();
-
-    insert_funding_rates(pool, exchange_id, &borrowed).await?;
-    info!("inserted {} funding rows for {}", borrowed.len(), exchange_name);
 
     Ok(())
 }
 
+async fn flush_funding(
+    store: &dyn FundingStore,
+    exchange_id: i32,
+    buf: &mut Vec<(i32, NormalizedFundingRate)>,
+) -> Result<usize> {
+    if buf.is_empty() {
+        return Ok(0);
+    }
+    let borrowed: Vec<(i32, &NormalizedFundingRate)> = buf.iter().map(|(mid, r)| (*mid, r)).collect();
+    store.insert_funding_rates(exchange_id, &borrowed).await?;
+    let n = borrowed.len();
+    drop(borrowed);
+    buf.clear();
+    Ok(n)
+}
+
 
 pub async fn collect_funding_for_exchange(
-    pool: &PgPool,
+    store: &dyn FundingStore,
     exchange_id: i32,
     exchange_name: &str,
 ) -> Result<()> {
     collect_funding_for_exchange_with_spec(
-        pool,
+        store,
         exchange_id,
         exchange_name,
         TimeSpec::SinceLastOrLookbackHours(24),
+        &MarketFilter::default(),
     )
     .await
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chunks_cover_the_window_without_overlap() {
+        let chunks: Vec<_> = funding_chunks(0, 25, 10).collect();
+        assert_eq!(chunks, vec![(0, 9), (10, 19), (20, 25)]);
+        assert_eq!(funding_chunks(5, 5, 10).collect::<Vec<_>>(), vec![(5, 5)]);
+        assert_eq!(funding_chunks(6, 5, 10).count(), 0);
+    }
+
+    #[test]
+    fn chunks_stop_at_the_end_of_time() {
+        assert_eq!(funding_chunks(u64::MAX - 1, u64::MAX, 10).collect::<Vec<_>>(), vec![(u64::MAX - 1, u64::MAX)]);
+    }
+}
@@ -1,3 +1,5 @@
 pub mod coin;
+pub mod filter;
 pub mod stats;
-pub mod funding;
\ No newline at end of file
+pub mod funding;
+pub mod predicted;
\ No newline at end of file
@@ -0,0 +1,85 @@
+// src/data/predicted.rs
+use anyhow::Result;
+use sqlx::PgPool;
+use tracing::{info, warn};
+
+use crate::analytics::rates::DEFAULT_INTERVAL_MINUTES;
+use crate::db::insert::insert_predicted_funding_by_symbol;
+use crate::exchanges::shared::types::NormalizedPredictedFunding;
+
+// APIs & handlers
+use crate::exchanges::paradex::api::{client::ParadexClient, endpoints::ApiEnvironment as ParadexEnv};
+use crate::exchanges::extended::api::{client::ExtendedClient, endpoints::ApiEnvironment as ExtendedEnv};
+use crate::exchanges::paradex::handler::handler::parse_paradex_predicted_funding;
+use crate::exchanges::extended::handler::handler::parse_extended_predicted_funding;
+
+#[inline]
+fn lower(s: &str) -> String {
+    s.trim().to_ascii_lowercase()
+}
+
+/* ---------------- Exchange adapter ---------------- */
+
+enum PredictedAdapter {
+    Paradex(ParadexClient),
+    Extended(ExtendedClient),
+}
+
+impl PredictedAdapter {
+    /// One bulk request per venue covering every market.
+    async fn fetch_all(&self, interval_minutes: i32) -> Result<Vec<NormalizedPredictedFunding>> {
+        match self {
+            PredictedAdapter::Paradex(c) => {
+                let raw = c.get_markets_summary("ALL").await?;
+                parse_paradex_predicted_funding(&raw, interval_minutes)
+            }
+            PredictedAdapter::Extended(c) => {
+                let raw = c.get_markets(None).await?;
+                parse_extended_predicted_funding(&raw)
+            }
+        }
+    }
+}
+
+fn make_predicted_adapter(name: &str) -> Option<PredictedAdapter> {
+    match lower(name).as_str() {
+        "paradex" => Some(PredictedAdapter::Paradex(ParadexClient::new(ParadexEnv::Mainnet))),
+        "extended" => Some(PredictedAdapter::Extended(ExtendedClient::new(ExtendedEnv::Mainnet))),
+        _ => None,
+    }
+}
+
+/// Snapshot the predicted next funding for every active exchange.
+/// A failing venue is logged and skipped so the others still update.
+pub async fn collect_predicted_funding(pool: &PgPool) -> Result<()> {
+    let exchanges = sqlx::query!(
+        r#"
+        SELECT e.id, e.name,
+               (SELECT MIN(m.funding_interval_minutes) FROM markets m
+                 WHERE m.exchange_id = e.id AND m.is_active = true) AS interval_minutes
+        FROM exchanges e
+        WHERE e.is_active = true
+        ORDER BY e.name
+        "#
+    )
+    .fetch_all(pool)
+    .await?;
+
+    for exch in exchanges {
+        let Some(adapter) = make_predicted_adapter(&exch.name) else {
+            warn!("predicted: skipping unsupported exchange '{}'(id={})", exch.name, exch.id);
+            continue;
+        };
+
+        let interval = exch.interval_minutes.unwrap_or(DEFAULT_INTERVAL_MINUTES);
+        match adapter.fetch_all(interval).await {
+            Ok(rows) => {
+                insert_predicted_funding_by_symbol(pool, exch.id, &rows).await?;
+                info!("predicted: {} rows for {} (exchange_id={})", rows.len(), exch.name, exch.id);
+            }
+            Err(e) => warn!("predicted: fetch failed for {}: {e:?}", exch.name),
+        }
+    }
+
+    Ok(())
+}
@@ -1,16 +1,20 @@
 // src/data/stats.rs
-use anyhow::Result;
-use sqlx::PgPool;
-use tracing::{info, warn};
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
 
-use crate::db::insert::insert_market_stats;
+use anyhow::{anyhow, Result};
+use futures::{future, stream, StreamExt};
+use tracing::{error, info, warn};
+
+use crate::data::filter::{check_selectors, MarketFilter};
 use crate::exchanges::shared::types::NormalizedMarketStats;
+use crate::store::FundingStore;
 
 // APIs & handlers
 use crate::exchanges::paradex::api::{client::ParadexClient, endpoints::ApiEnvironment as ParadexEnv};
 use crate::exchanges::extended::api::{client::ExtendedClient, endpoints::ApiEnvironment as ExtendedEnv};
 use crate::exchanges::paradex::handler::handler::parse_paradex_market_stats;
-use crate::exchanges::extended::handler::handler::parse_extended_market_stats;
+use crate::exchanges::extended::handler::handler::{parse_extended_market_stats, parse_extended_markets_stats};
 
 #[inline]
 fn lower(s: &str) -> String {
@@ -25,6 +29,20 @@ enum StatsAdapter {
 }
 
 impl StatsAdapter {
+    /// One bulk request covering every market.
+    async fn fetch_all(&self) -> Result<Vec<NormalizedMarketStats>> {
+        match self {
+            StatsAdapter::Paradex(c) => {
+                let raw = c.get_markets_summary("ALL").await?;
+                parse_paradex_market_stats(&raw)
+            }
+            StatsAdapter::Extended(c) => {
+                let raw = c.get_markets(None).await?;
+                parse_extended_markets_stats(&raw)
+            }
+        }
+    }
+
     async fn fetch_one(&self, market_symbol: &str) -> Result<Option<NormalizedMarketStats>> {
         match self {
             StatsAdapter::Paradex(c) => {
@@ -58,53 +76,191 @@ fn make_stats_adapter(name: &str) -> Option<StatsAdapter> {
     }
 }
 
+/// Spaces request starts at least `period` apart, however many are in flight.
+struct Pacer {
+    period: Duration,
+    next: tokio::sync::Mutex<tokio::time::Instant>,
+}
 
-pub async fn collect_daily_market_stats(pool: &PgPool) -> Result<()> {
-    let exchanges = sqlx::query!(
-        "SELECT id, name FROM exchanges WHERE is_active = true ORDER BY name"
-    )
-    .fetch_all(pool)
-    .await?;
+impl Pacer {
+    fn per_second(rps: u32) -> Self {
+        Self {
+            period: Duration::from_secs(1) / rps.max(1),
+            next: tokio::sync::Mutex::new(tokio::time::Instant::now()),
+        }
+    }
 
-    for exch in exchanges {
-        let Some(adapter) = make_stats_adapter(&exch.name) else {
-            warn!("stats: skipping unsupported exchange '{}'(id={})", exch.name, exch.id);
-            continue;
+    async fn wait(&self) {
+        let slot = {
+            let mut next = self.next.lock().await;
+            let slot = (*next).max(tokio::time::Instant::now());
+            *next = slot + self.period;
+            slot
         };
+        tokio::time::sleep_until(slot).await;
+    }
+}
+
+/* ---------------- Collection ---------------- */
 
-        let markets = sqlx::query!(
-            "SELECT id, market_symbol FROM markets WHERE exchange_id = $1 AND is_active = true ORDER BY market_symbol",
-            exch.id
-        )
-        .fetch_all(pool)
-        .await?;
+/// Stats fetched for a set of markets on one exchange.
+#[derive(Debug, Default)]
+pub struct StatsFetch {
+    pub stats: Vec<NormalizedMarketStats>,
+    /// Markets whose own request failed, with the error.
+    pub failed: Vec<(String, anyhow::Error)>,
+    /// Per-market requests made after the bulk one.
+    pub fallback_requests: usize,
+}
 
-        let mut owned: Vec<(i32, NormalizedMarketStats)> = Vec::with_capacity(markets.len());
+/// Stats for `symbols`: one bulk request, then per-market requests for whatever
+/// it didn't cover (or everything, if it failed). Those run concurrently
+/// (`SYNC_CONC_MARKETS`) at most `SYNC_STATS_RPS` per second (default 10), and a
+/// failing market is reported in `failed` instead of aborting the rest.
+pub async fn fetch_market_stats(exchange_name: &str, symbols: &[String]) -> Result<StatsFetch> {
+    let adapter = make_stats_adapter(exchange_name)
+        .ok_or_else(|| anyhow!("stats: unsupported exchange '{}'", exchange_name))?;
+    if symbols.is_empty() {
+        return Ok(StatsFetch::default());
+    }
 
-        for m in markets {
-            if let Some(stat) = adapter.fetch_one(&m.market_symbol).await? {
-                owned.push((m.id, stat));
-            }
+    let wanted: HashSet<&str> = symbols.iter().map(String::as_str).collect();
+    let mut stats: Vec<NormalizedMarketStats> = match adapter.fetch_all().await {
+        Ok(all) => all
+            .into_iter()
+            .filter(|s| wanted.contains(s.market_symbol.as_str()))
+            .collect(),
+        Err(e) => {
+            warn!("stats: bulk request failed for {}, fetching per market: {:#}", adapter.canonical_name(), e);
+            Vec::new()
         }
+    };
+
+    let covered: HashSet<String> = stats.iter().map(|s| s.market_symbol.clone()).collect();
+    let missing: Vec<String> = symbols.iter().filter(|s| !covered.contains(*s)).cloned().collect();
+    let mut failed = Vec::new();
+
+    if !missing.is_empty() {
+        let conc: usize = std::env::var("SYNC_CONC_MARKETS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(16);
+        let rps: u32 = std::env::var("SYNC_STATS_RPS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(10);
+        let pacer = Pacer::per_second(rps);
+        let (adapter, pacer) = (&adapter, &pacer);
 
-        if !owned.is_empty() {
-            let borrowed: Vec<(i32, &NormalizedMarketStats)> =
-                owned.iter().map(|(mid, s)| (*mid, s)).collect();
-            insert_market_stats(pool, &borrowed).await?;
-            info!(
-                "stats: inserted {} rows for {} (exchange_id={})",
-                borrowed.len(),
-                adapter.canonical_name(),
-                exch.id
-            );
-        } else {
-            info!(
-                "stats: no rows to insert for {} (exchange_id={})",
-                adapter.canonical_name(),
-                exch.id
-            );
+        let results: Vec<(String, Result<Option<NormalizedMarketStats>>)> = stream::iter(missing.clone())
+            .map(|sym: String| async move {
+                pacer.wait().await;
+                let res = adapter.fetch_one(&sym).await;
+                (sym, res)
+            })
+            .buffer_unordered(conc.max(1))
+            .collect()
+            .await;
+
+        for (sym, res) in results {
+            match res {
+                Ok(Some(stat)) => stats.push(stat),
+                Ok(None) => {}
+                Err(e) => failed.push((sym, e)),
+            }
         }
     }
 
+    Ok(StatsFetch { stats, failed, fallback_requests: missing.len() })
+}
+
+/// Latest stats for the active markets of one exchange that `filter` selects.
+/// Returns the number of rows written; markets that failed are logged and left out.
+pub async fn collect_stats_for_exchange(
+    store: &dyn FundingStore,
+    exchange_id: i32,
+    exchange_name: &str,
+    filter: &MarketFilter,
+) -> Result<usize> {
+    let started = Instant::now();
+    let active = store.active_markets(exchange_id).await?;
+    for sel in filter.unmatched(active.iter().map(|m| (m.market_symbol.as_str(), m.token.as_str()))) {
+        warn!("stats: {} matches no active market on {}", sel, exchange_name);
+    }
+    let ids: HashMap<String, i32> = active
+        .into_iter()
+        .filter(|m| filter.matches(&m.market_symbol, &m.token))
+        .map(|m| (m.market_symbol, m.id))
+        .collect();
+    let symbols: Vec<String> = ids.keys().cloned().collect();
+
+    let fetched = fetch_market_stats(exchange_name, &symbols).await?;
+    for (sym, e) in &fetched.failed {
+        warn!("stats: {} {} failed: {:#}", exchange_name, sym, e);
+    }
+
+    let rows: Vec<(i32, &NormalizedMarketStats)> = fetched
+        .stats
+        .iter()
+        .filter_map(|s| ids.get(&s.market_symbol).map(|id| (*id, s)))
+        .collect();
+    if rows.is_empty() {
+        info!("stats: no rows to insert for {} (exchange_id={})", exchange_name, exchange_id);
+        return Ok(0);
+    }
+    store.insert_market_stats(exchange_id, &rows).await?;
+    info!(
+        "stats: inserted {} rows for {} (exchange_id={}) in {:.1}s, {} per-market request(s), {} failed",
+        rows.len(),
+        exchange_name,
+        exchange_id,
+        started.elapsed().as_secs_f64(),
+        fetched.fallback_requests,
+        fetched.failed.len()
+    );
+    Ok(rows.len())
+}
+
+pub async fn collect_daily_market_stats(store: &dyn FundingStore) -> Result<()> {
+    collect_market_stats(store, &MarketFilter::default()).await
+}
+
+/// Latest stats for the active markets `filter` selects, on every active exchange
+/// at once. A selector matching no market anywhere is an error up front; a
+/// failing exchange is logged and doesn't stop the others, and the run errors
+/// afterwards if any failed.
+pub async fn collect_market_stats(store: &dyn FundingStore, filter: &MarketFilter) -> Result<()> {
+    let exchanges: Vec<_> = store
+        .active_exchanges()
+        .await?
+        .into_iter()
+        .filter(|exch| {
+            let supported = make_stats_adapter(&exch.name).is_some();
+            if !supported {
+                warn!("stats: skipping unsupported exchange '{}'(id={})", exch.name, exch.id);
+            }
+            supported
+        })
+        .collect();
+    let ids: Vec<i32> = exchanges.iter().map(|e| e.id).collect();
+    check_selectors(store, &ids, filter).await?;
+
+    let results = future::join_all(
+        exchanges
+            .iter()
+            .map(|exch| collect_stats_for_exchange(store, exch.id, &exch.name, filter)),
+    )
+    .await;
+
+    let mut failed = Vec::new();
+    for (exch, res) in exchanges.iter().zip(results) {
+        if let Err(e) = res {
+            error!("stats failed for {}: {:#}", exch.name, e);
+            failed.push(exch.name.as_str());
+        }
+    }
+    if !failed.is_empty() {
+        return Err(anyhow!("stats failed for {}", failed.join(", ")));
+    }
     Ok(())
 }
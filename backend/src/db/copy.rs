@@ -0,0 +1,168 @@
+// backend/src/db/copy.rs
+// Encoder for `COPY ... FROM STDIN (FORMAT binary)`. Values are written
+// straight into the wire format: `Decimal` becomes a Postgres NUMERIC
+// (base-10000 digits) without going through a string or `BigDecimal`.
+use bytes::{BufMut, BytesMut};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+
+const SIGNATURE: &[u8] = b"PGCOPY\n\xff\r\n\0";
+const NUMERIC_POS: u16 = 0x0000;
+const NUMERIC_NEG: u16 = 0x4000;
+/// Seconds between the Unix epoch and the Postgres epoch (2000-01-01).
+const PG_EPOCH_OFFSET_SECS: i64 = 946_684_800;
+
+/// Batches at least this large go through COPY (`SYNC_COPY_THRESHOLD`, default 50 000).
+pub fn copy_threshold() -> usize {
+    std::env::var("SYNC_COPY_THRESHOLD")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(50_000)
+}
+
+/// Rows are buffered and handed to the connection once the buffer passes this size.
+pub const FLUSH_BYTES: usize = 1 << 20;
+
+pub struct BinaryCopyWriter {
+    buf: BytesMut,
+}
+
+impl Default for BinaryCopyWriter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BinaryCopyWriter {
+    pub fn new() -> Self {
+        let mut buf = BytesMut::with_capacity(FLUSH_BYTES + 4096);
+        buf.put_slice(SIGNATURE);
+        buf.put_i32(0); // flags
+        buf.put_i32(0); // header extension length
+        Self { buf }
+    }
+
+    pub fn row(&mut self, fields: i16) -> &mut Self {
+        self.buf.put_i16(fields);
+        self
+    }
+
+    pub fn int4(&mut self, v: i32) -> &mut Self {
+        self.buf.put_i32(4);
+        self.buf.put_i32(v);
+        self
+    }
+
+    /// Whole seconds, matching the UNNEST insert path.
+    pub fn timestamptz(&mut self, ts: &DateTime<Utc>) -> &mut Self {
+        self.buf.put_i32(8);
+        self.buf.put_i64((ts.timestamp() - PG_EPOCH_OFFSET_SECS) * 1_000_000);
+        self
+    }
+
+    pub fn numeric(&mut self, d: Option<&Decimal>) -> &mut Self {
+        let Some(d) = d else {
+            self.buf.put_i32(-1);
+            return self;
+        };
+
+        let scale = d.scale();
+        // Align the fraction to whole base-10000 digits
+        let pad = (4 - scale % 4) % 4;
+        let mut m = d.mantissa().unsigned_abs() * 10u128.pow(pad);
+        let frac_digits = ((scale + pad) / 4) as i16;
+
+        // Least significant first
+        let mut digits: Vec<i16> = Vec::with_capacity(8);
+        while m > 0 {
+            digits.push((m % 10_000) as i16);
+            m /= 10_000;
+        }
+        let weight = digits.len() as i16 - 1 - frac_digits;
+        let trailing_zeros = digits.iter().take_while(|g| **g == 0).count();
+        digits.drain(..trailing_zeros);
+
+        let (weight, sign) = if digits.is_empty() {
+            (0, NUMERIC_POS)
+        } else if d.is_sign_negative() {
+            (weight, NUMERIC_NEG)
+        } else {
+            (weight, NUMERIC_POS)
+        };
+
+        self.buf.put_i32(8 + 2 * digits.len() as i32);
+        self.buf.put_i16(digits.len() as i16);
+        self.buf.put_i16(weight);
+        self.buf.put_u16(sign);
+        self.buf.put_u16(scale as u16);
+        for g in digits.iter().rev() {
+            self.buf.put_i16(*g);
+        }
+        self
+    }
+
+    /// Take what is buffered if it is worth sending yet.
+    pub fn take_if_full(&mut self) -> Option<BytesMut> {
+        (self.buf.len() >= FLUSH_BYTES).then(|| self.buf.split())
+    }
+
+    /// Append the trailer and take the rest.
+    pub fn finish(mut self) -> BytesMut {
+        self.buf.put_i16(-1);
+        self.buf
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    /// The NUMERIC field `numeric` writes, without the file header.
+    fn encode(d: Option<&str>) -> Vec<u8> {
+        let d = d.map(|s| Decimal::from_str(s).unwrap());
+        let mut w = BinaryCopyWriter::new();
+        let header = w.buf.len();
+        w.numeric(d.as_ref());
+        w.buf[header..].to_vec()
+    }
+
+    /// Length, ndigits, weight, sign, dscale and digits as Postgres sends them.
+    fn field(weight: i16, sign: u16, dscale: u16, digits: &[i16]) -> Vec<u8> {
+        let mut b = BytesMut::new();
+        b.put_i32(8 + 2 * digits.len() as i32);
+        b.put_i16(digits.len() as i16);
+        b.put_i16(weight);
+        b.put_u16(sign);
+        b.put_u16(dscale);
+        for g in digits {
+            b.put_i16(*g);
+        }
+        b.to_vec()
+    }
+
+    #[test]
+    fn numeric_null() {
+        assert_eq!(encode(None), (-1i32).to_be_bytes());
+    }
+
+    #[test]
+    fn numeric_zero() {
+        assert_eq!(encode(Some("0")), field(0, NUMERIC_POS, 0, &[]));
+        assert_eq!(encode(Some("0.000")), field(0, NUMERIC_POS, 3, &[]));
+    }
+
+    #[test]
+    fn numeric_fractions_align_to_base_10000() {
+        assert_eq!(encode(Some("0.0001")), field(-1, NUMERIC_POS, 4, &[1]));
+        assert_eq!(encode(Some("0.00001")), field(-2, NUMERIC_POS, 5, &[1000]));
+        assert_eq!(encode(Some("12345.678")), field(1, NUMERIC_POS, 3, &[1, 2345, 6780]));
+        assert_eq!(encode(Some("-1.5")), field(0, NUMERIC_NEG, 1, &[1, 5000]));
+    }
+
+    #[test]
+    fn numeric_drops_trailing_zero_groups() {
+        assert_eq!(encode(Some("10000")), field(1, NUMERIC_POS, 0, &[1]));
+        assert_eq!(encode(Some("100000000.5")), field(2, NUMERIC_POS, 1, &[1, 0, 0, 5000]));
+    }
+}
@@ -0,0 +1,428 @@
+// backend/src/db/export.rs
+// Funding / stats history in a flat, DB-free shape for research tools:
+// every dataset is (token, exchange, market_symbol, ..., ts), streamed out
+// of Postgres in batches into a CSV or Parquet sink.
+use anyhow::Result;
+use arrow_array::{ArrayRef, Float64Array, RecordBatch, StringArray, TimestampMicrosecondArray};
+use arrow_schema::{DataType, Field, Schema, SchemaRef, TimeUnit};
+use futures::TryStreamExt;
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
+use sqlx::PgPool;
+use std::io::Write;
+use std::sync::Arc;
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+
+use crate::analytics::rates::{self, RateBasis, DEFAULT_INTERVAL_MINUTES};
+
+/// Rows handed to a sink at a time.
+const BATCH_ROWS: usize = 50_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dataset {
+    /// Raw `funding_rates`: native rate per the market's funding interval.
+    Funding,
+    /// `funding_rollup_8h`: average rate per 8h bucket, normalised to an 8h interval.
+    Funding8h,
+    /// `market_stats`: open interest and 24h volume.
+    Stats,
+}
+
+impl std::str::FromStr for Dataset {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_ascii_lowercase().replace('-', "_").as_str() {
+            "funding" | "funding_rates" => Ok(Self::Funding),
+            "funding_8h" | "8h" => Ok(Self::Funding8h),
+            "stats" | "market_stats" => Ok(Self::Stats),
+            other => Err(anyhow::anyhow!("unknown dataset {other:?} (funding | funding_8h | stats)")),
+        }
+    }
+}
+
+impl Dataset {
+    pub fn name(self) -> &'static str {
+        match self {
+            Dataset::Funding => "funding",
+            Dataset::Funding8h => "funding_8h",
+            Dataset::Stats => "stats",
+        }
+    }
+
+    pub fn columns(self) -> &'static [Column] {
+        use Column::*;
+        match self {
+            Dataset::Funding | Dataset::Funding8h => &[Token, Exchange, MarketSymbol, Interval, Rate, Ts],
+            Dataset::Stats => &[Token, Exchange, MarketSymbol, OpenInterest, Volume24h, Ts],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Csv,
+    Parquet,
+}
+
+impl std::str::FromStr for ExportFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "csv" => Ok(Self::Csv),
+            "parquet" => Ok(Self::Parquet),
+            other => Err(anyhow::anyhow!("unknown format {other:?} (csv | parquet)")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Column {
+    Token,
+    Exchange,
+    MarketSymbol,
+    /// Funding interval the rate is quoted for, e.g. `1h` or `8h`.
+    Interval,
+    Rate,
+    OpenInterest,
+    Volume24h,
+    Ts,
+}
+
+impl Column {
+    pub fn name(self) -> &'static str {
+        match self {
+            Column::Token => "token",
+            Column::Exchange => "exchange",
+            Column::MarketSymbol => "market_symbol",
+            Column::Interval => "interval",
+            Column::Rate => "rate",
+            Column::OpenInterest => "open_interest",
+            Column::Volume24h => "volume_24h",
+            Column::Ts => "ts",
+        }
+    }
+
+    fn field(self) -> Field {
+        let utc = Some(Arc::from("UTC"));
+        match self {
+            Column::Token | Column::Exchange | Column::MarketSymbol | Column::Interval => {
+                Field::new(self.name(), DataType::Utf8, false)
+            }
+            Column::Rate => Field::new(self.name(), DataType::Float64, false),
+            Column::OpenInterest | Column::Volume24h => Field::new(self.name(), DataType::Float64, true),
+            Column::Ts => Field::new(self.name(), DataType::Timestamp(TimeUnit::Microsecond, utc), false),
+        }
+    }
+}
+
+/// One exported row; the dataset's columns decide which fields are written.
+#[derive(Debug, Clone)]
+pub struct ExportRow {
+    pub token: String,
+    pub exchange: String,
+    pub market_symbol: String,
+    pub interval_minutes: Option<i32>,
+    pub rate: Option<f64>,
+    pub open_interest: Option<f64>,
+    pub volume_24h: Option<f64>,
+    pub ts: OffsetDateTime,
+}
+
+impl ExportRow {
+    fn text(&self, col: Column) -> String {
+        match col {
+            Column::Token => self.token.clone(),
+            Column::Exchange => self.exchange.clone(),
+            Column::MarketSymbol => self.market_symbol.clone(),
+            Column::Interval => interval_label(self.interval_minutes.unwrap_or(DEFAULT_INTERVAL_MINUTES)),
+            Column::Rate => self.rate.map(|v| v.to_string()).unwrap_or_default(),
+            Column::OpenInterest => self.open_interest.map(|v| v.to_string()).unwrap_or_default(),
+            Column::Volume24h => self.volume_24h.map(|v| v.to_string()).unwrap_or_default(),
+            Column::Ts => self.ts.format(&Rfc3339).unwrap_or_default(),
+        }
+    }
+}
+
+/// `480` -> `8h`, `90` -> `90m`
+pub fn interval_label(minutes: i32) -> String {
+    if minutes % 60 == 0 {
+        format!("{}h", minutes / 60)
+    } else {
+        format!("{minutes}m")
+    }
+}
+
+/// What to export; unset fields don't filter. `to` is exclusive.
+#[derive(Debug, Clone, Default)]
+pub struct ExportFilter {
+    pub token: Option<String>,
+    pub exchange: Option<String>,
+    pub from: Option<OffsetDateTime>,
+    pub to: Option<OffsetDateTime>,
+    pub limit: Option<i64>,
+}
+
+/// Destination for exported rows, fed in batches ordered by exchange, UTC day, token, ts.
+pub trait ExportSink: Send {
+    fn write(&mut self, rows: &[ExportRow]) -> Result<()>;
+}
+
+pub struct CsvSink<W: Write> {
+    writer: csv::Writer<W>,
+    columns: Vec<Column>,
+}
+
+impl<W: Write> CsvSink<W> {
+    /// Writes the header right away, so an empty export is still a valid file.
+    pub fn new(writer: W, columns: &[Column]) -> Result<Self> {
+        let mut writer = csv::Writer::from_writer(writer);
+        writer.write_record(columns.iter().map(|c| c.name()))?;
+        Ok(Self { writer, columns: columns.to_vec() })
+    }
+
+    pub fn finish(self) -> Result<W> {
+        self.writer.into_inner().map_err(|e| anyhow::anyhow!("csv flush failed: {}", e.error()))
+    }
+}
+
+impl<W: Write + Send> ExportSink for CsvSink<W> {
+    fn write(&mut self, rows: &[ExportRow]) -> Result<()> {
+        for row in rows {
+            self.writer.write_record(self.columns.iter().map(|c| row.text(*c)))?;
+        }
+        Ok(())
+    }
+}
+
+pub struct ParquetSink<W: Write + Send> {
+    writer: ArrowWriter<W>,
+    schema: SchemaRef,
+    columns: Vec<Column>,
+}
+
+impl<W: Write + Send> ParquetSink<W> {
+    pub fn new(writer: W, columns: &[Column]) -> Result<Self> {
+        let schema = Arc::new(Schema::new(columns.iter().map(|c| c.field()).collect::<Vec<_>>()));
+        let props = WriterProperties::builder().set_compression(Compression::SNAPPY).build();
+        let writer = ArrowWriter::try_new(writer, schema.clone(), Some(props))?;
+        Ok(Self { writer, schema, columns: columns.to_vec() })
+    }
+
+    /// Write the footer and hand back the underlying writer.
+    pub fn finish(self) -> Result<W> {
+        Ok(self.writer.into_inner()?)
+    }
+}
+
+impl<W: Write + Send> ExportSink for ParquetSink<W> {
+    fn write(&mut self, rows: &[ExportRow]) -> Result<()> {
+        let arrays: Vec<ArrayRef> = self
+            .columns
+            .iter()
+            .map(|col| -> ArrayRef {
+                match col {
+                    Column::Rate => Arc::new(Float64Array::from_iter(rows.iter().map(|r| r.rate))),
+                    Column::OpenInterest => Arc::new(Float64Array::from_iter(rows.iter().map(|r| r.open_interest))),
+                    Column::Volume24h => Arc::new(Float64Array::from_iter(rows.iter().map(|r| r.volume_24h))),
+                    Column::Ts => Arc::new(
+                        TimestampMicrosecondArray::from_iter_values(
+                            rows.iter().map(|r| (r.ts.unix_timestamp_nanos() / 1_000) as i64),
+                        )
+                        .with_timezone("UTC"),
+                    ),
+                    text => Arc::new(StringArray::from_iter_values(rows.iter().map(|r| r.text(*text)))),
+                }
+            })
+            .collect();
+        self.writer.write(&RecordBatch::try_new(self.schema.clone(), arrays)?)?;
+        Ok(())
+    }
+}
+
+/// Rows `export` would write for `filter`, counting no further than `filter.limit`.
+pub async fn count(pool: &PgPool, dataset: Dataset, filter: &ExportFilter) -> Result<i64> {
+    let token = filter.token.as_deref().map(str::trim);
+    let exchange = filter.exchange.as_deref().map(str::trim);
+    let n = match dataset {
+        Dataset::Funding => sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(*) AS "n!" FROM (
+              SELECT 1
+              FROM funding_rates fr
+              JOIN markets   m ON m.id = fr.market_id
+              JOIN tokens    t ON t.id = m.token_id
+              JOIN exchanges e ON e.id = m.exchange_id
+              WHERE ($1::text IS NULL OR t.symbol = upper($1))
+                AND ($2::text IS NULL OR lower(e.name) = lower($2))
+                AND ($3::timestamptz IS NULL OR fr.timestamp >= $3)
+                AND ($4::timestamptz IS NULL OR fr.timestamp < $4)
+              LIMIT $5
+            ) s
+            "#,
+            token,
+            exchange,
+            filter.from,
+            filter.to,
+            filter.limit
+        )
+        .fetch_one(pool)
+        .await?,
+        Dataset::Funding8h => sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(*) AS "n!" FROM (
+              SELECT 1
+              FROM funding_rollup_8h r
+              JOIN markets   m ON m.id = r.market_id
+              JOIN tokens    t ON t.id = m.token_id
+              JOIN exchanges e ON e.id = m.exchange_id
+              WHERE ($1::text IS NULL OR t.symbol = upper($1))
+                AND ($2::text IS NULL OR lower(e.name) = lower($2))
+                AND ($3::timestamptz IS NULL OR r.bucket_start >= $3)
+                AND ($4::timestamptz IS NULL OR r.bucket_start < $4)
+              LIMIT $5
+            ) s
+            "#,
+            token,
+            exchange,
+            filter.from,
+            filter.to,
+            filter.limit
+        )
+        .fetch_one(pool)
+        .await?,
+        Dataset::Stats => sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(*) AS "n!" FROM (
+              SELECT 1
+              FROM market_stats ms
+              JOIN markets   m ON m.id = ms.market_id
+              JOIN tokens    t ON t.id = m.token_id
+              JOIN exchanges e ON e.id = m.exchange_id
+              WHERE ($1::text IS NULL OR t.symbol = upper($1))
+                AND ($2::text IS NULL OR lower(e.name) = lower($2))
+                AND ($3::timestamptz IS NULL OR ms.timestamp >= $3)
+                AND ($4::timestamptz IS NULL OR ms.timestamp < $4)
+              LIMIT $5
+            ) s
+            "#,
+            token,
+            exchange,
+            filter.from,
+            filter.to,
+            filter.limit
+        )
+        .fetch_one(pool)
+        .await?,
+    };
+    Ok(n)
+}
+
+/// Stream `dataset` rows matching `filter` into `sink`; returns the row count.
+pub async fn export(pool: &PgPool, dataset: Dataset, filter: &ExportFilter, sink: &mut dyn ExportSink) -> Result<u64> {
+    let token = filter.token.as_deref().map(str::trim);
+    let exchange = filter.exchange.as_deref().map(str::trim);
+    let mut rows = match dataset {
+        Dataset::Funding => sqlx::query_as!(
+            ExportRow,
+            r#"
+            SELECT t.symbol AS "token!", e.name AS "exchange!", m.market_symbol AS "market_symbol!",
+                   COALESCE(m.funding_interval_minutes, e.funding_interval_minutes) AS interval_minutes,
+                   fr.rate::float8 AS rate,
+                   NULL::float8 AS open_interest, NULL::float8 AS volume_24h,
+                   fr.timestamp AS "ts!"
+            FROM funding_rates fr
+            JOIN markets   m ON m.id = fr.market_id
+            JOIN tokens    t ON t.id = m.token_id
+            JOIN exchanges e ON e.id = m.exchange_id
+            WHERE ($1::text IS NULL OR t.symbol = upper($1))
+              AND ($2::text IS NULL OR lower(e.name) = lower($2))
+              AND ($3::timestamptz IS NULL OR fr.timestamp >= $3)
+              AND ($4::timestamptz IS NULL OR fr.timestamp < $4)
+            ORDER BY e.name, date_bin('1 day', fr.timestamp, TIMESTAMPTZ 'epoch'), t.symbol, fr.timestamp
+            LIMIT $5
+            "#,
+            token,
+            exchange,
+            filter.from,
+            filter.to,
+            filter.limit
+        )
+        .fetch(pool),
+        Dataset::Funding8h => sqlx::query_as!(
+            ExportRow,
+            r#"
+            SELECT t.symbol AS "token!", e.name AS "exchange!", m.market_symbol AS "market_symbol!",
+                   COALESCE(m.funding_interval_minutes, e.funding_interval_minutes) AS interval_minutes,
+                   (r.rate_sum / r.samples)::float8 AS rate,
+                   NULL::float8 AS open_interest, NULL::float8 AS volume_24h,
+                   r.bucket_start AS "ts!"
+            FROM funding_rollup_8h r
+            JOIN markets   m ON m.id = r.market_id
+            JOIN tokens    t ON t.id = m.token_id
+            JOIN exchanges e ON e.id = m.exchange_id
+            WHERE ($1::text IS NULL OR t.symbol = upper($1))
+              AND ($2::text IS NULL OR lower(e.name) = lower($2))
+              AND ($3::timestamptz IS NULL OR r.bucket_start >= $3)
+              AND ($4::timestamptz IS NULL OR r.bucket_start < $4)
+            ORDER BY e.name, date_bin('1 day', r.bucket_start, TIMESTAMPTZ 'epoch'), t.symbol, r.bucket_start
+            LIMIT $5
+            "#,
+            token,
+            exchange,
+            filter.from,
+            filter.to,
+            filter.limit
+        )
+        .fetch(pool),
+        Dataset::Stats => sqlx::query_as!(
+            ExportRow,
+            r#"
+            SELECT t.symbol AS "token!", e.name AS "exchange!", m.market_symbol AS "market_symbol!",
+                   NULL::int4 AS interval_minutes, NULL::float8 AS rate,
+                   ms.open_interest::float8 AS open_interest, ms.volume_24h::float8 AS volume_24h,
+                   ms.timestamp AS "ts!"
+            FROM market_stats ms
+            JOIN markets   m ON m.id = ms.market_id
+            JOIN tokens    t ON t.id = m.token_id
+            JOIN exchanges e ON e.id = m.exchange_id
+            WHERE ($1::text IS NULL OR t.symbol = upper($1))
+              AND ($2::text IS NULL OR lower(e.name) = lower($2))
+              AND ($3::timestamptz IS NULL OR ms.timestamp >= $3)
+              AND ($4::timestamptz IS NULL OR ms.timestamp < $4)
+            ORDER BY e.name, date_bin('1 day', ms.timestamp, TIMESTAMPTZ 'epoch'), t.symbol, ms.timestamp
+            LIMIT $5
+            "#,
+            token,
+            exchange,
+            filter.from,
+            filter.to,
+            filter.limit
+        )
+        .fetch(pool),
+    };
+
+    let mut batch = Vec::with_capacity(BATCH_ROWS);
+    let mut total = 0u64;
+    while let Some(mut row) = rows.try_next().await? {
+        if dataset == Dataset::Funding8h {
+            // The rollup averages native rates; quote them per 8h like the matrix does
+            let interval = row.interval_minutes.unwrap_or(DEFAULT_INTERVAL_MINUTES);
+            row.rate = row.rate.map(|r| rates::convert(r, interval, RateBasis::EightHour));
+            row.interval_minutes = Some(8 * 60);
+        }
+        batch.push(row);
+        if batch.len() == BATCH_ROWS {
+            sink.write(&batch)?;
+            total += batch.len() as u64;
+            batch.clear();
+        }
+    }
+    if !batch.is_empty() {
+        sink.write(&batch)?;
+        total += batch.len() as u64;
+    }
+    Ok(total)
+}
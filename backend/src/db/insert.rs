@@ -4,14 +4,30 @@ use anyhow::Result;
 use sqlx::{PgPool, QueryBuilder};
 use sqlx::types::BigDecimal;
 use std::collections::{HashMap, HashSet};
-use std::str::FromStr;
+use tracing::info;
+use std::sync::atomic::{AtomicU8, Ordering};
 use time::OffsetDateTime;
 use chrono::{DateTime, Utc};
 use rust_decimal::Decimal;
 
 use crate::exchanges::shared::types::{
-    NormalizedFundingRate, NormalizedMarket, NormalizedMarketStats,
+    NormalizedFundingRate, NormalizedMarket, NormalizedMarketStats, NormalizedPredictedFunding,
 };
+use crate::db::copy::{self, BinaryCopyWriter};
+use crate::db::retention::RetentionPolicy;
+use crate::utils::metrics;
+
+/// Lowercase exchange name for metric labels, looked up once per id.
+async fn exchange_label<'e>(ex: impl sqlx::PgExecutor<'e>, exchange_id: i32) -> Result<String> {
+    if let Some(label) = metrics::exchange_label(exchange_id) {
+        return Ok(label);
+    }
+    let name: String = sqlx::query_scalar("SELECT name FROM exchanges WHERE id = $1")
+        .bind(exchange_id)
+        .fetch_one(ex)
+        .await?;
+    Ok(metrics::set_exchange_label(exchange_id, &name))
+}
 
 /// Upsert the exchange row and return its id.
 pub async fn upsert_exchange(pool: &PgPool, name: &str) -> Result<i32> {
@@ -73,7 +89,7 @@ pub async fn upsert_markets(
     {
         let mut qb = QueryBuilder::new(
             r#"
-            INSERT INTO markets (exchange_id, token_id, market_symbol, is_active)
+            INSERT INTO markets (exchange_id, token_id, market_symbol, is_active, funding_interval_minutes)
             "#,
         );
         qb.push_values(markets.iter(), |mut b, m| {
@@ -83,12 +99,15 @@ pub async fn upsert_markets(
             b.push_bind(exchange_id)
                 .push_bind(token_id)
                 .push_bind(&m.market_symbol)
-                .push_bind(m.is_active);
+                .push_bind(m.is_active)
+                .push_bind(m.funding_interval_minutes);
         });
         qb.push(
             r#"
             ON CONFLICT(exchange_id, market_symbol)
-            DO UPDATE SET is_active = EXCLUDED.is_active, updated_at = NOW()
+            DO UPDATE SET is_active = EXCLUDED.is_active,
+                          funding_interval_minutes = COALESCE(EXCLUDED.funding_interval_minutes, markets.funding_interval_minutes),
+                          updated_at = NOW()
             "#,
         );
         qb.build().execute(pool).await?;
@@ -98,134 +117,637 @@ pub async fn upsert_markets(
 }
 
 
+/// `Decimal` → `BigDecimal` from the mantissa and scale (no string round trip).
+fn dec(d: &Decimal) -> BigDecimal {
+    BigDecimal::new(d.mantissa().into(), d.scale() as i64)
+}
+
+fn opt_dec(d: &Option<Decimal>) -> Option<BigDecimal> {
+    d.as_ref().map(dec)
+}
+
+fn ts_secs(ts: &DateTime<Utc>) -> OffsetDateTime {
+    OffsetDateTime::from_unix_timestamp(ts.timestamp()).expect("valid ts")
+}
+
+/// What to do when a funding / stats row already exists for (market, timestamp).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ConflictPolicy {
+    /// Keep the stored row.
+    #[default]
+    Ignore,
+    /// Always write the incoming values.
+    Overwrite,
+    /// Write only rows whose values changed.
+    OverwriteIfDifferent,
+}
+
+impl std::str::FromStr for ConflictPolicy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_ascii_lowercase().replace('_', "-").as_str() {
+            "ignore" => Ok(Self::Ignore),
+            "overwrite" => Ok(Self::Overwrite),
+            "overwrite-if-different" => Ok(Self::OverwriteIfDifferent),
+            other => Err(anyhow::anyhow!(
+                "unknown conflict policy {other:?} (ignore | overwrite | overwrite-if-different)"
+            )),
+        }
+    }
+}
+
+impl ConflictPolicy {
+    /// Strip `--on-conflict POLICY` from the args, wherever it appears.
+    pub fn from_args(args: &mut Vec<String>) -> Result<Option<Self>> {
+        let Some(i) = args.iter().position(|a| a == "--on-conflict") else {
+            return Ok(None);
+        };
+        let policy = args
+            .get(i + 1)
+            .ok_or_else(|| anyhow::anyhow!("--on-conflict needs ignore | overwrite | overwrite-if-different"))?
+            .parse()?;
+        args.drain(i..i + 2);
+        Ok(Some(policy))
+    }
+}
+
+// 0 = not set: ignore
+static CONFLICT_POLICY: AtomicU8 = AtomicU8::new(0);
+
+/// Override the policy for this process (e.g. `sync --on-conflict`).
+pub fn set_conflict_policy(policy: ConflictPolicy) {
+    let v = match policy {
+        ConflictPolicy::Ignore => 1,
+        ConflictPolicy::Overwrite => 2,
+        ConflictPolicy::OverwriteIfDifferent => 3,
+    };
+    CONFLICT_POLICY.store(v, Ordering::Relaxed);
+    info!("conflict policy: {policy:?}");
+}
+
+/// Settle the policy once at startup: `flag` (`--on-conflict`), else
+/// `SYNC_CONFLICT_POLICY`, else `ignore`. An unparseable `SYNC_CONFLICT_POLICY`
+/// is an error rather than a silent `ignore`.
+pub fn init_conflict_policy(flag: Option<ConflictPolicy>) -> Result<()> {
+    let policy = match flag {
+        Some(p) => p,
+        None => match std::env::var("SYNC_CONFLICT_POLICY") {
+            Ok(v) => v.parse().map_err(|e| anyhow::anyhow!("SYNC_CONFLICT_POLICY: {e}"))?,
+            Err(_) => ConflictPolicy::default(),
+        },
+    };
+    set_conflict_policy(policy);
+    Ok(())
+}
+
+/// The policy settled by `init_conflict_policy` (`ignore` if it never ran).
+pub fn conflict_policy() -> ConflictPolicy {
+    match CONFLICT_POLICY.load(Ordering::Relaxed) {
+        2 => ConflictPolicy::Overwrite,
+        3 => ConflictPolicy::OverwriteIfDifferent,
+        _ => ConflictPolicy::Ignore,
+    }
+}
+
+const FUNDING_ROLLUPS: [(&str, &str); 3] = [
+    ("funding_rollup_1h", "1 hour"),
+    ("funding_rollup_8h", "8 hours"),
+    ("funding_rollup_1d", "1 day"),
+];
+
+/// Write funding rows from `source` (a FROM item aliased `s` with columns
+/// `market_id, rate, ts`; `$1` is the exchange id) under `policy`. Rows that
+/// were newly inserted are added to the hourly / 8h / daily rollups, every
+/// written row may advance `latest_funding`, and changed values are logged in
+/// `data_revisions`. Returns (inserted, revised).
+fn funding_merge_sql(source: &str, policy: ConflictPolicy) -> String {
+    let mut sql = match policy {
+        ConflictPolicy::Ignore => format!(
+            r#"
+        WITH up AS (
+            INSERT INTO funding_rates (exchange_id, market_id, rate, timestamp)
+            SELECT $1::int4, s.market_id, s.rate, s.ts FROM {source}
+            ON CONFLICT (market_id, timestamp) DO NOTHING
+            RETURNING market_id, rate, timestamp AS ts
+        ),
+        ins AS (SELECT * FROM up),
+        rev AS (SELECT 1 WHERE false)"#
+        ),
+        ConflictPolicy::Overwrite | ConflictPolicy::OverwriteIfDifferent => {
+            let only_changed = if policy == ConflictPolicy::OverwriteIfDifferent {
+                "WHERE funding_rates.rate IS DISTINCT FROM EXCLUDED.rate"
+            } else {
+                ""
+            };
+            format!(
+                r#"
+        WITH src AS (
+            -- DO UPDATE can't touch a key twice in one statement
+            SELECT DISTINCT ON (s.market_id, s.ts) s.market_id, s.rate::numeric(18,10) AS rate, s.ts
+            FROM {source}
+            ORDER BY s.market_id, s.ts
+        ),
+        old AS (
+            SELECT f.market_id, f.timestamp AS ts, f.rate
+            FROM funding_rates f
+            JOIN src ON f.market_id = src.market_id AND f.timestamp = src.ts
+        ),
+        up AS (
+            INSERT INTO funding_rates (exchange_id, market_id, rate, timestamp)
+            SELECT $1::int4, market_id, rate, ts FROM src
+            ON CONFLICT (market_id, timestamp) DO UPDATE SET rate = EXCLUDED.rate
+            {only_changed}
+            RETURNING market_id, rate, timestamp AS ts
+        ),
+        ins AS (
+            SELECT u.* FROM up u
+            WHERE NOT EXISTS (SELECT 1 FROM old o WHERE o.market_id = u.market_id AND o.ts = u.ts)
+        ),
+        rev AS (
+            INSERT INTO data_revisions (table_name, market_id, timestamp, old_values, new_values)
+            SELECT 'funding_rates', u.market_id, u.ts,
+                   jsonb_build_object('rate', o.rate), jsonb_build_object('rate', u.rate)
+            FROM up u
+            JOIN old o ON o.market_id = u.market_id AND o.ts = u.ts
+            WHERE o.rate IS DISTINCT FROM u.rate
+            RETURNING 1
+        )"#
+            )
+        }
+    };
+    for (table, width) in FUNDING_ROLLUPS {
+        sql.push_str(&format!(
+            r#",
+        {table} AS (
+            INSERT INTO {table} AS r (market_id, exchange_id, bucket_start, rate_sum, rate_min, rate_max, samples)
+            SELECT market_id, $1::int4, date_bin('{width}', ts, TIMESTAMPTZ 'epoch') AS b,
+                   SUM(rate), MIN(rate), MAX(rate), COUNT(*)
+            FROM ins GROUP BY market_id, b
+            ON CONFLICT (market_id, bucket_start) DO UPDATE
+               SET rate_sum = r.rate_sum + EXCLUDED.rate_sum,
+                   rate_min = LEAST(r.rate_min, EXCLUDED.rate_min),
+                   rate_max = GREATEST(r.rate_max, EXCLUDED.rate_max),
+                   samples  = r.samples + EXCLUDED.samples
+        )"#
+        ));
+    }
+    sql.push_str(
+        r#",
+        latest AS (
+            INSERT INTO latest_funding AS l (market_id, exchange_id, rate, timestamp)
+            SELECT DISTINCT ON (market_id) market_id, $1::int4, rate, ts
+            FROM up ORDER BY market_id, ts DESC
+            ON CONFLICT (market_id) DO UPDATE
+               SET exchange_id = EXCLUDED.exchange_id, rate = EXCLUDED.rate, timestamp = EXCLUDED.timestamp
+             WHERE EXCLUDED.timestamp >= l.timestamp
+        )
+        SELECT (SELECT COUNT(*) FROM ins), (SELECT COUNT(*) FROM rev)
+        "#,
+    );
+    sql
+}
+
+/// Rebuild the rollup buckets holding funding rows revised in the current
+/// transaction (their sums and min / max can't be patched incrementally).
+/// Buckets starting before the raw retention cutoff are left alone: their raw
+/// rows are (partly) pruned, so recomputing them would lose history.
+async fn refresh_revised_buckets(conn: &mut sqlx::PgConnection) -> Result<()> {
+    let raw_cutoff: Option<OffsetDateTime> = RetentionPolicy::from_env()
+        .funding_days
+        .map(|d| OffsetDateTime::now_utc() - time::Duration::days(d));
+    for (table, width) in FUNDING_ROLLUPS {
+        sqlx::query(&format!(
+            r#"
+            UPDATE {table} r
+               SET rate_sum = a.rate_sum, rate_min = a.rate_min, rate_max = a.rate_max, samples = a.samples
+            FROM (
+                SELECT f.market_id, t.b, SUM(f.rate) AS rate_sum, MIN(f.rate) AS rate_min,
+                       MAX(f.rate) AS rate_max, COUNT(*)::int AS samples
+                FROM (
+                    SELECT DISTINCT market_id, date_bin('{width}', timestamp, TIMESTAMPTZ 'epoch') AS b
+                    FROM data_revisions
+                    WHERE table_name = 'funding_rates' AND revised_at = now()
+                ) t
+                JOIN funding_rates f
+                  ON f.market_id = t.market_id
+                 AND f.timestamp >= t.b AND f.timestamp < t.b + interval '{width}'
+                WHERE $1::timestamptz IS NULL OR t.b >= $1
+                GROUP BY f.market_id, t.b
+            ) a
+            WHERE r.market_id = a.market_id AND r.bucket_start = a.b
+            "#
+        ))
+        .bind(raw_cutoff)
+        .execute(&mut *conn)
+        .await?;
+    }
+    Ok(())
+}
+
+/// Write stats rows from `source` (aliased `s`, columns `market_id, oi, vol,
+/// mark, idx, last, ts`) under `policy`, advance `latest_market_stats`, write
+/// the price snapshot for rows that carry prices (never overwritten) and log
+/// changed values. Returns (stats inserted, prices inserted, revised).
+fn stats_merge_sql(source: &str, policy: ConflictPolicy) -> String {
+    let head = match policy {
+        ConflictPolicy::Ignore => format!(
+            r#"
+        WITH up AS (
+            INSERT INTO market_stats (market_id, open_interest, volume_24h, timestamp)
+            SELECT s.market_id, s.oi, s.vol, s.ts FROM {source}
+            ON CONFLICT (market_id, timestamp) DO NOTHING
+            RETURNING market_id, open_interest, volume_24h, timestamp
+        ),
+        ins AS (SELECT * FROM up),
+        rev AS (SELECT 1 WHERE false)"#
+        ),
+        ConflictPolicy::Overwrite | ConflictPolicy::OverwriteIfDifferent => {
+            let only_changed = if policy == ConflictPolicy::OverwriteIfDifferent {
+                "WHERE (market_stats.open_interest, market_stats.volume_24h)
+                       IS DISTINCT FROM (EXCLUDED.open_interest, EXCLUDED.volume_24h)"
+            } else {
+                ""
+            };
+            format!(
+                r#"
+        WITH src AS (
+            SELECT DISTINCT ON (s.market_id, s.ts)
+                   s.market_id, s.oi::numeric(30,10) AS oi, s.vol::numeric(30,10) AS vol, s.ts
+            FROM {source}
+            ORDER BY s.market_id, s.ts
+        ),
+        old AS (
+            SELECT ms.market_id, ms.timestamp, ms.open_interest, ms.volume_24h
+            FROM market_stats ms
+            JOIN src ON ms.market_id = src.market_id AND ms.timestamp = src.ts
+        ),
+        up AS (
+            INSERT INTO market_stats (market_id, open_interest, volume_24h, timestamp)
+            SELECT market_id, oi, vol, ts FROM src
+            ON CONFLICT (market_id, timestamp) DO UPDATE
+               SET open_interest = EXCLUDED.open_interest, volume_24h = EXCLUDED.volume_24h
+            {only_changed}
+            RETURNING market_id, open_interest, volume_24h, timestamp
+        ),
+        ins AS (
+            SELECT u.* FROM up u
+            WHERE NOT EXISTS (SELECT 1 FROM old o WHERE o.market_id = u.market_id AND o.timestamp = u.timestamp)
+        ),
+        rev AS (
+            INSERT INTO data_revisions (table_name, market_id, timestamp, old_values, new_values)
+            SELECT 'market_stats', u.market_id, u.timestamp,
+                   jsonb_build_object('open_interest', o.open_interest, 'volume_24h', o.volume_24h),
+                   jsonb_build_object('open_interest', u.open_interest, 'volume_24h', u.volume_24h)
+            FROM up u
+            JOIN old o ON o.market_id = u.market_id AND o.timestamp = u.timestamp
+            WHERE (o.open_interest, o.volume_24h) IS DISTINCT FROM (u.open_interest, u.volume_24h)
+            RETURNING 1
+        )"#
+            )
+        }
+    };
+    format!(
+        r#"{head},
+        latest AS (
+            INSERT INTO latest_market_stats AS l (market_id, open_interest, volume_24h, timestamp)
+            SELECT DISTINCT ON (market_id) market_id, open_interest, volume_24h, timestamp
+            FROM up ORDER BY market_id, timestamp DESC
+            ON CONFLICT (market_id) DO UPDATE
+               SET open_interest = EXCLUDED.open_interest,
+                   volume_24h    = EXCLUDED.volume_24h,
+                   timestamp     = EXCLUDED.timestamp
+             WHERE EXCLUDED.timestamp >= l.timestamp
+        ),
+        prices AS (
+            INSERT INTO price_snapshots (market_id, mark_price, index_price, last_price, timestamp)
+            SELECT s.market_id, s.mark, s.idx, s.last, s.ts FROM {source}
+            WHERE COALESCE(s.mark, s.idx, s.last) IS NOT NULL
+            ON CONFLICT (market_id, timestamp) DO NOTHING
+            RETURNING 1
+        )
+        SELECT (SELECT COUNT(*) FROM ins), (SELECT COUNT(*) FROM prices), (SELECT COUNT(*) FROM rev)
+        "#
+    )
+}
+
+/// Funding rows for one exchange. Batches of `SYNC_COPY_THRESHOLD` rows or
+/// more are streamed with binary COPY; smaller ones go through UNNEST in
+/// `SYNC_DB_CHUNK`-sized statements.
 pub async fn insert_funding_rates(
     pool: &PgPool,
     exchange_id: i32,
     rows: &[(i32, &NormalizedFundingRate)],
 ) -> Result<()> {
     if rows.is_empty() { return Ok(()); }
+    let mut tx = pool.begin().await?;
+    insert_funding_rates_tx(&mut tx, exchange_id, rows).await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// `insert_funding_rates` inside the caller's transaction, so whatever else
+/// it writes (e.g. a backfill checkpoint) commits or rolls back with the rows.
+pub async fn insert_funding_rates_tx(
+    tx: &mut sqlx::PgConnection,
+    exchange_id: i32,
+    rows: &[(i32, &NormalizedFundingRate)],
+) -> Result<()> {
+    if rows.is_empty() { return Ok(()); }
+    sqlx::query!("SET LOCAL synchronous_commit = 'off'")
+        .execute(&mut *tx).await?;
+    let (inserted, revised) = if rows.len() >= copy::copy_threshold() {
+        copy_funding_rates(tx, exchange_id, rows).await?
+    } else {
+        unnest_funding_rates(tx, exchange_id, rows).await?
+    };
+
+    if revised > 0 {
+        refresh_revised_buckets(tx).await?;
+    }
+    let exchange = exchange_label(&mut *tx, exchange_id).await?;
+    metrics::inc_rows_inserted("funding_rates", &exchange, inserted);
+    metrics::inc_rows_inserted("data_revisions", &exchange, revised);
+    Ok(())
+}
 
+/// Merge `rows` through UNNEST. Returns (inserted, revised).
+async fn unnest_funding_rates(
+    tx: &mut sqlx::PgConnection,
+    exchange_id: i32,
+    rows: &[(i32, &NormalizedFundingRate)],
+) -> Result<(u64, u64)> {
     // Make chunk size tunable
     let chunk_rows: usize = std::env::var("SYNC_DB_CHUNK")
         .ok().and_then(|v| v.parse().ok())
         .unwrap_or(20_000); 
 
-    let mut tx = pool.begin().await?;
-    sqlx::query!("SET LOCAL synchronous_commit = 'off'")
-        .execute(&mut *tx).await?;
-
-    let mut start = 0;
-    while start < rows.len() {
-        let end = (start + chunk_rows).min(rows.len());
-        let slice = &rows[start..end];
+    let sql = funding_merge_sql(
+        "UNNEST($2::int4[], $3::numeric[], $4::timestamptz[]) AS s(market_id, rate, ts)",
+        conflict_policy(),
+    );
 
+    let (mut inserted, mut revised) = (0u64, 0u64);
+    for slice in rows.chunks(chunk_rows.max(1)) {
         let mut market_ids = Vec::with_capacity(slice.len());
         let mut rates_bd   = Vec::with_capacity(slice.len());
         let mut ts_time    = Vec::with_capacity(slice.len());
 
         for (mid, r) in slice {
             market_ids.push(*mid);
-            rates_bd.push(sqlx::types::BigDecimal::from_str(&r.rate.to_string()).unwrap());
-            let ts = time::OffsetDateTime::from_unix_timestamp(r.timestamp.timestamp()).unwrap();
-            ts_time.push(ts);
+            rates_bd.push(dec(&r.rate));
+            ts_time.push(ts_secs(&r.timestamp));
         }
 
-        sqlx::query!(
-            r#"
-            INSERT INTO funding_rates (exchange_id, market_id, rate, timestamp)
-            SELECT $1::int4, u.market_id, u.rate, u.ts
-            FROM UNNEST($2::int4[], $3::numeric[], $4::timestamptz[]) AS u(market_id, rate, ts)
-            ON CONFLICT (market_id, timestamp) DO NOTHING
-            "#,
-            exchange_id, &market_ids, &rates_bd, &ts_time
-        )
-        .execute(&mut *tx)
-        .await?;
-
-        start = end;
+        let (n, r): (i64, i64) = sqlx::query_as(&sql)
+            .bind(exchange_id)
+            .bind(&market_ids)
+            .bind(&rates_bd)
+            .bind(&ts_time)
+            .fetch_one(&mut *tx)
+            .await?;
+        inserted += n as u64;
+        revised += r as u64;
     }
+    Ok((inserted, revised))
+}
 
-    tx.commit().await?;
-    Ok(())
+/// COPY the batch into a per-connection staging table, then merge it with
+/// the same statement as the UNNEST path. Returns (inserted, revised).
+async fn copy_funding_rates(
+    tx: &mut sqlx::PgConnection,
+    exchange_id: i32,
+    rows: &[(i32, &NormalizedFundingRate)],
+) -> Result<(u64, u64)> {
+    sqlx::query(
+        "CREATE TEMP TABLE IF NOT EXISTS funding_rates_stage
+           (market_id int4, rate numeric, ts timestamptz) ON COMMIT DELETE ROWS",
+    )
+    .execute(&mut *tx)
+    .await?;
+    // The caller's transaction may already have staged an earlier batch
+    sqlx::query("TRUNCATE funding_rates_stage").execute(&mut *tx).await?;
+
+    let mut copy_in = tx
+        .copy_in_raw("COPY funding_rates_stage (market_id, rate, ts) FROM STDIN (FORMAT binary)")
+        .await?;
+    let mut w = BinaryCopyWriter::new();
+    for (mid, r) in rows {
+        w.row(3).int4(*mid).numeric(Some(&r.rate)).timestamptz(&r.timestamp);
+        if let Some(chunk) = w.take_if_full() {
+            copy_in.send(chunk).await?;
+        }
+    }
+    copy_in.send(w.finish()).await?;
+    copy_in.finish().await?;
+
+    let (inserted, revised): (i64, i64) =
+        sqlx::query_as(&funding_merge_sql("funding_rates_stage s", conflict_policy()))
+            .bind(exchange_id)
+            .fetch_one(&mut *tx)
+            .await?;
+    Ok((inserted as u64, revised as u64))
 }
 
 
+/// Stats rows (and their mark / index / last prices) for one exchange; large
+/// batches go through COPY like funding.
 pub async fn insert_market_stats(
     pool: &PgPool,
+    exchange_id: i32,
     rows: &[(i32, &NormalizedMarketStats)],
 ) -> Result<()> {
     if rows.is_empty() {
         return Ok(());
     }
+    if rows.len() >= copy::copy_threshold() {
+        return copy_market_stats(pool, exchange_id, rows).await;
+    }
+    let exchange = exchange_label(pool, exchange_id).await?;
+
+    let mut ids = Vec::with_capacity(rows.len());
+    let mut oi = Vec::with_capacity(rows.len());
+    let mut vol = Vec::with_capacity(rows.len());
+    let mut mark = Vec::with_capacity(rows.len());
+    let mut index = Vec::with_capacity(rows.len());
+    let mut last = Vec::with_capacity(rows.len());
+    let mut ts = Vec::with_capacity(rows.len());
+    for (mid, stat) in rows {
+        ids.push(*mid);
+        oi.push(opt_dec(&stat.open_interest));
+        vol.push(opt_dec(&stat.volume_24h));
+        mark.push(opt_dec(&stat.mark_price));
+        index.push(opt_dec(&stat.index_price));
+        last.push(opt_dec(&stat.last_price));
+        ts.push(ts_secs(&stat.timestamp));
+    }
 
-    let mut qb = QueryBuilder::new(
-        r#"
-        INSERT INTO market_stats (market_id, open_interest, volume_24h, timestamp)
-        "#,
-    );
+    let (stats_n, prices_n, revised): (i64, i64, i64) = sqlx::query_as(&stats_merge_sql(
+        "UNNEST($1::int4[], $2::numeric[], $3::numeric[], $4::numeric[], $5::numeric[], $6::numeric[], $7::timestamptz[])
+         AS s(market_id, oi, vol, mark, idx, last, ts)",
+        conflict_policy(),
+    ))
+    .bind(&ids)
+    .bind(&oi)
+    .bind(&vol)
+    .bind(&mark)
+    .bind(&index)
+    .bind(&last)
+    .bind(&ts)
+    .fetch_one(pool)
+    .await?;
 
-    qb.push_values(rows.iter(), |mut b, (market_id, stat)| {
-        let oi = stat
-            .open_interest
-            .as_ref()
-            .map(|d| BigDecimal::from_str(&d.to_string()).expect("decimal oi"));
-        let vol = stat
-            .volume_24h
-            .as_ref()
-            .map(|d| BigDecimal::from_str(&d.to_string()).expect("decimal vol"));
-        let ts = OffsetDateTime::from_unix_timestamp(stat.timestamp.timestamp())
-            .expect("valid ts");
-
-        b.push_bind(*market_id)
-            .push_bind(oi)
-            .push_bind(vol)
-            .push_bind(ts);
-    });
-
-    qb.push(" ON CONFLICT (market_id, timestamp) DO NOTHING");
-    qb.build().execute(pool).await?;
+    metrics::inc_rows_inserted("market_stats", &exchange, stats_n as u64);
+    metrics::inc_rows_inserted("price_snapshots", &exchange, prices_n as u64);
+    metrics::inc_rows_inserted("data_revisions", &exchange, revised as u64);
     Ok(())
 }
 
+async fn copy_market_stats(
+    pool: &PgPool,
+    exchange_id: i32,
+    rows: &[(i32, &NormalizedMarketStats)],
+) -> Result<()> {
+    let mut tx = pool.begin().await?;
+    sqlx::query("SET LOCAL synchronous_commit = 'off'").execute(&mut *tx).await?;
+    sqlx::query(
+        "CREATE TEMP TABLE IF NOT EXISTS market_stats_stage
+           (market_id int4, oi numeric, vol numeric, mark numeric, idx numeric, last numeric, ts timestamptz)
+           ON COMMIT DELETE ROWS",
+    )
+    .execute(&mut *tx)
+    .await?;
 
+    let mut copy_in = tx
+        .copy_in_raw(
+            "COPY market_stats_stage (market_id, oi, vol, mark, idx, last, ts) FROM STDIN (FORMAT binary)",
+        )
+        .await?;
+    let mut w = BinaryCopyWriter::new();
+    for (mid, stat) in rows {
+        w.row(7)
+            .int4(*mid)
+            .numeric(stat.open_interest.as_ref())
+            .numeric(stat.volume_24h.as_ref())
+            .numeric(stat.mark_price.as_ref())
+            .numeric(stat.index_price.as_ref())
+            .numeric(stat.last_price.as_ref())
+            .timestamptz(&stat.timestamp);
+        if let Some(chunk) = w.take_if_full() {
+            copy_in.send(chunk).await?;
+        }
+    }
+    copy_in.send(w.finish()).await?;
+    copy_in.finish().await?;
+
+    let (stats_n, prices_n, revised): (i64, i64, i64) = sqlx::query_as(&stats_merge_sql("market_stats_stage s", conflict_policy()))
+        .fetch_one(&mut *tx)
+        .await?;
+
+    let exchange = exchange_label(&mut *tx, exchange_id).await?;
+    tx.commit().await?;
+    metrics::inc_rows_inserted("market_stats", &exchange, stats_n as u64);
+    metrics::inc_rows_inserted("price_snapshots", &exchange, prices_n as u64);
+    metrics::inc_rows_inserted("data_revisions", &exchange, revised as u64);
+    Ok(())
+}
 
+
+/// Stats keyed by venue market symbol (unknown symbols are skipped).
 pub async fn insert_market_stats_by_symbol(
     pool: &PgPool,
     exchange_id: i32,
     rows: &[(String, &NormalizedMarketStats)],
 ) -> anyhow::Result<()> {
     if rows.is_empty() { return Ok(()); }
+    let exchange = exchange_label(pool, exchange_id).await?;
 
     let mut symbols: Vec<String>              = Vec::with_capacity(rows.len());
     let mut oi:      Vec<Option<BigDecimal>>  = Vec::with_capacity(rows.len());
     let mut vol:     Vec<Option<BigDecimal>>  = Vec::with_capacity(rows.len());
+    let mut mark:    Vec<Option<BigDecimal>>  = Vec::with_capacity(rows.len());
+    let mut index:   Vec<Option<BigDecimal>>  = Vec::with_capacity(rows.len());
+    let mut last:    Vec<Option<BigDecimal>>  = Vec::with_capacity(rows.len());
     let mut ts:      Vec<OffsetDateTime>      = Vec::with_capacity(rows.len());
 
     for (sym, stat) in rows {
         symbols.push(sym.clone());
-        oi.push(stat.open_interest.as_ref().map(|d| BigDecimal::from_str(&d.to_string()).unwrap()));
-        vol.push(stat.volume_24h.as_ref().map(|d| BigDecimal::from_str(&d.to_string()).unwrap()));
-        ts.push(OffsetDateTime::from_unix_timestamp(stat.timestamp.timestamp()).unwrap());
+        oi.push(opt_dec(&stat.open_interest));
+        vol.push(opt_dec(&stat.volume_24h));
+        mark.push(opt_dec(&stat.mark_price));
+        index.push(opt_dec(&stat.index_price));
+        last.push(opt_dec(&stat.last_price));
+        ts.push(ts_secs(&stat.timestamp));
     }
 
-    sqlx::query(
+    let (stats_n, prices_n, revised): (i64, i64, i64) = sqlx::query_as(&stats_merge_sql(
+        "(SELECT m.id AS market_id, u.oi, u.vol, u.mark, u.idx, u.last, u.ts
+          FROM UNNEST($1::text[], $2::numeric[], $3::numeric[], $4::numeric[], $5::numeric[], $6::numeric[], $7::timestamptz[])
+               AS u(market_symbol, oi, vol, mark, idx, last, ts)
+          JOIN markets m
+            ON m.exchange_id = $8
+           AND m.market_symbol = u.market_symbol) s",
+        conflict_policy(),
+    ))
+    .bind(&symbols)
+    .bind(&oi)
+    .bind(&vol)
+    .bind(&mark)
+    .bind(&index)
+    .bind(&last)
+    .bind(&ts)
+    .bind(exchange_id)
+    .fetch_one(pool)
+    .await?;
+
+    metrics::inc_rows_inserted("market_stats", &exchange, stats_n as u64);
+    metrics::inc_rows_inserted("price_snapshots", &exchange, prices_n as u64);
+    metrics::inc_rows_inserted("data_revisions", &exchange, revised as u64);
+    Ok(())
+}
+
+
+/// Predicted next-interval funding, keyed by venue market symbol (unknown symbols are skipped).
+pub async fn insert_predicted_funding_by_symbol(
+    pool: &PgPool,
+    exchange_id: i32,
+    rows: &[NormalizedPredictedFunding],
+) -> anyhow::Result<()> {
+    if rows.is_empty() { return Ok(()); }
+    let exchange = exchange_label(pool, exchange_id).await?;
+
+    let mut symbols: Vec<String>                 = Vec::with_capacity(rows.len());
+    let mut rates:   Vec<BigDecimal>             = Vec::with_capacity(rows.len());
+    let mut next:    Vec<Option<OffsetDateTime>> = Vec::with_capacity(rows.len());
+    let mut ts:      Vec<OffsetDateTime>         = Vec::with_capacity(rows.len());
+
+    for p in rows {
+        symbols.push(p.market_symbol.clone());
+        rates.push(dec(&p.rate));
+        next.push(p.next_funding_time.map(|t| OffsetDateTime::from_unix_timestamp(t.timestamp()).unwrap()));
+        ts.push(OffsetDateTime::from_unix_timestamp(p.timestamp.timestamp()).unwrap());
+    }
+
+    let inserted = sqlx::query(
         r#"
-        INSERT INTO market_stats (market_id, open_interest, volume_24h, timestamp)
-        SELECT m.id, u.oi, u.vol, u.ts
-        FROM UNNEST($1::text[], $2::numeric[], $3::numeric[], $4::timestamptz[]) AS u(market_symbol, oi, vol, ts)
+        INSERT INTO predicted_funding_rates (exchange_id, market_id, rate, next_funding_time, timestamp)
+        SELECT $5, m.id, u.rate, u.next_ts, u.ts
+        FROM UNNEST($1::text[], $2::numeric[], $3::timestamptz[], $4::timestamptz[]) AS u(market_symbol, rate, next_ts, ts)
         JOIN markets m
           ON m.exchange_id = $5
          AND m.market_symbol = u.market_symbol
         ON CONFLICT (market_id, timestamp) DO NOTHING
         "#
     )
-    .bind(&symbols)   
-    .bind(&oi)        
-    .bind(&vol)      
-    .bind(&ts)        
-    .bind(exchange_id) 
+    .bind(&symbols)
+    .bind(&rates)
+    .bind(&next)
+    .bind(&ts)
+    .bind(exchange_id)
     .execute(pool)
-    .await?;
+    .await?
+    .rows_affected();
 
+    metrics::inc_rows_inserted("predicted_funding_rates", &exchange, inserted);
     Ok(())
 }
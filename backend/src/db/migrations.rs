@@ -1,27 +1,189 @@
 // backend/src/db/migrations.rs
+use anyhow::{anyhow, bail, Result};
+use sqlx::migrate::Migrator;
 use sqlx::{postgres::PgPoolOptions, PgPool};
 use std::time::Duration;
 use tracing::info;
 
-pub async fn create_pool() -> PgPool {
+pub static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
+
+/// Migrations whose file was rewritten after release (the baseline lost its
+/// `DROP TABLE`s). A database that recorded the old checksum is still
+/// considered up to date; `apply` swaps in the new checksum.
+const REWRITTEN: &[(i64, &str)] = &[
+    (
+        20250826235450,
+        "41362f9b3056471e2c28be1ff202fa0763145728746fb159ed2ca1daa39ece207a9afebc1f6ef06f4b9d707ceb37eade",
+    ),
+    // Gained the backfill of existing markets' intervals
+    (
+        20250915000000,
+        "f2480fefba8b72082a1d54e677fbc7f2a08d42fc53e99044decee7578be317dc8d01b19260d56eedb787a6feed166562",
+    ),
+];
+
+/// What to do with pending migrations when a pool is created.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MigrationMode {
+    #[default]
+    Apply,
+    /// `--no-migrate`: refuse to start unless every migration is already applied.
+    VerifyOnly,
+}
+
+impl MigrationMode {
+    /// Strip `--no-migrate` from the args, wherever it appears.
+    pub fn from_args(args: &mut Vec<String>) -> Self {
+        let before = args.len();
+        args.retain(|a| a != "--no-migrate");
+        if args.len() != before { MigrationMode::VerifyOnly } else { MigrationMode::Apply }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MigrationStatus {
+    pub version: i64,
+    pub description: String,
+    pub applied: bool,
+    pub checksum_ok: bool,
+}
+
+pub async fn create_pool_with_mode(mode: MigrationMode) -> PgPool {
     let db_url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set");
-    create_pool_with_url(&db_url).await
+    create_pool_with_url_and_mode(&db_url, mode).await
 }
 
-pub async fn create_pool_with_url(db_url: &str) -> PgPool {
-    let pool = PgPoolOptions::new()
+pub async fn create_pool_with_url_and_mode(db_url: &str, mode: MigrationMode) -> PgPool {
+    let pool = connect(db_url).await;
+
+    match mode {
+        MigrationMode::Apply => {
+            info!("Running database migrations...");
+            apply(&pool).await.expect("Failed to run database migrations");
+            info!("Database migrations completed.");
+        }
+        MigrationMode::VerifyOnly => {
+            verify(&pool).await.expect("Database schema is not up to date");
+            info!("Database migrations verified (--no-migrate).");
+        }
+    }
+
+    pool
+}
+
+/// Connect without touching the schema.
+pub async fn connect(db_url: &str) -> PgPool {
+    PgPoolOptions::new()
         .max_connections(10)
         .acquire_timeout(Duration::from_secs(5))
         .connect(db_url)
         .await
-        .expect("Failed to connect to the database");
+        .expect("Failed to connect to the database")
+}
 
-    info!("Running database migrations...");
-    sqlx::migrate!("./migrations")
-        .run(&pool)
-        .await
-        .expect("Failed to run database migrations");
-    info!("Database migrations completed.");
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
 
-    pool
+fn rewritten_from(version: i64, applied_checksum: &str) -> bool {
+    REWRITTEN
+        .iter()
+        .any(|(v, old)| *v == version && old.eq_ignore_ascii_case(applied_checksum))
+}
+
+/// Applied versions with their checksum (hex) and success flag; empty when the
+/// database has never been migrated.
+async fn applied(pool: &PgPool) -> Result<Vec<(i64, String, bool)>> {
+    let exists: bool = sqlx::query_scalar("SELECT to_regclass('_sqlx_migrations') IS NOT NULL")
+        .fetch_one(pool)
+        .await?;
+    if !exists {
+        return Ok(Vec::new());
+    }
+    let rows: Vec<(i64, Vec<u8>, bool)> =
+        sqlx::query_as("SELECT version, checksum, success FROM _sqlx_migrations ORDER BY version")
+            .fetch_all(pool)
+            .await?;
+    Ok(rows.into_iter().map(|(v, c, s)| (v, hex(&c), s)).collect())
+}
+
+pub async fn status(pool: &PgPool) -> Result<Vec<MigrationStatus>> {
+    let applied = applied(pool).await?;
+    Ok(MIGRATOR
+        .iter()
+        .filter(|m| m.migration_type.is_up_migration())
+        .map(|m| {
+            let row = applied.iter().find(|(v, _, ok)| *v == m.version && *ok);
+            MigrationStatus {
+                version: m.version,
+                description: m.description.to_string(),
+                applied: row.is_some(),
+                checksum_ok: row.is_none_or(|(v, c, _)| {
+                    *c == hex(&m.checksum) || rewritten_from(*v, c)
+                }),
+            }
+        })
+        .collect())
+}
+
+/// Read-only check that every migration is applied with a known checksum.
+pub async fn verify(pool: &PgPool) -> Result<()> {
+    if let Some((v, _, _)) = applied(pool).await?.into_iter().find(|(_, _, ok)| !ok) {
+        bail!("migration {v} is recorded as failed; fix it, then run `sync migrate`");
+    }
+    let st = status(pool).await?;
+    if let Some(m) = st.iter().find(|m| !m.checksum_ok) {
+        bail!("migration {} ({}) was modified after it was applied", m.version, m.description);
+    }
+    let pending: Vec<String> = st
+        .iter()
+        .filter(|m| !m.applied)
+        .map(|m| format!("{} {}", m.version, m.description))
+        .collect();
+    if !pending.is_empty() {
+        return Err(anyhow!(
+            "{} pending migration(s): {}; run `sync migrate`",
+            pending.len(),
+            pending.join(", ")
+        ));
+    }
+    Ok(())
+}
+
+/// Apply pending migrations, first accepting known rewrites of applied ones.
+pub async fn apply(pool: &PgPool) -> Result<()> {
+    for (version, checksum, _) in applied(pool).await? {
+        if !rewritten_from(version, &checksum) {
+            continue;
+        }
+        if let Some(m) = MIGRATOR.iter().find(|m| m.version == version) {
+            sqlx::query("UPDATE _sqlx_migrations SET checksum = $1 WHERE version = $2")
+                .bind(m.checksum.as_ref())
+                .bind(version)
+                .execute(pool)
+                .await?;
+            info!("migration {version}: recorded checksum updated for the rewritten file");
+        }
+    }
+    MIGRATOR.run(pool).await?;
+    Ok(())
+}
+
+/// A freshly migrated `funding_test_<name>` database on the server
+/// `DATABASE_URL` points at, or `None` when it isn't PostgreSQL.
+#[cfg(test)]
+pub(crate) async fn scratch_db(name: &str) -> Option<PgPool> {
+    use sqlx::postgres::PgConnectOptions;
+
+    let url = std::env::var("DATABASE_URL").ok().filter(|u| u.starts_with("postgres"))?;
+    let admin = PgPool::connect(&url).await.unwrap();
+    let db = format!("funding_test_{name}");
+    sqlx::query(&format!("DROP DATABASE IF EXISTS {db} WITH (FORCE)")).execute(&admin).await.unwrap();
+    sqlx::query(&format!("CREATE DATABASE {db}")).execute(&admin).await.unwrap();
+    admin.close().await;
+
+    let opts = url.parse::<PgConnectOptions>().unwrap().database(&db);
+    let pool = PgPool::connect_with(opts).await.unwrap();
+    MIGRATOR.run(&pool).await.unwrap();
+    Some(pool)
 }
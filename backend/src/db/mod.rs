@@ -1,2 +1,6 @@
+pub mod copy;
 pub mod insert;
 pub mod migrations;
+pub mod partitions;
+pub mod retention;
+pub mod export;
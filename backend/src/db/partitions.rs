@@ -0,0 +1,187 @@
+// backend/src/db/partitions.rs
+use anyhow::{bail, Result};
+use sqlx::PgPool;
+use time::{Duration, OffsetDateTime};
+use tracing::{info, warn};
+
+use crate::db::retention::RetentionPolicy;
+
+/// Tables range-partitioned by month on `timestamp`.
+pub const PARTITIONED_TABLES: &[&str] = &["funding_rates", "market_stats"];
+
+/// Months of partitions kept ready ahead of the current one.
+pub const DEFAULT_MONTHS_AHEAD: i32 = 3;
+
+#[derive(Debug, Clone)]
+pub struct PartitionInfo {
+    pub table: String,
+    pub partition: String,
+    /// `None` for the MINVALUE lower bound of the legacy partition.
+    pub from: Option<OffsetDateTime>,
+    pub to: OffsetDateTime,
+    pub approx_rows: i64,
+}
+
+fn check_table(table: &str) -> Result<()> {
+    if !PARTITIONED_TABLES.contains(&table) {
+        bail!("{table} is not a partitioned table");
+    }
+    Ok(())
+}
+
+/// Create any missing monthly partitions from last month through
+/// `months_ahead` months from now. Months retention has already dropped are
+/// not brought back. Returns how many were created.
+pub async fn ensure_partitions(pool: &PgPool, months_ahead: i32) -> Result<i32> {
+    let policy = RetentionPolicy::from_env();
+    let mut created = 0;
+    for table in PARTITIONED_TABLES {
+        let retained_days = match *table {
+            "funding_rates" => policy.funding_days,
+            _ => policy.stats_days,
+        };
+        let cutoff = retained_days.map(|d| OffsetDateTime::now_utc() - Duration::days(d));
+        let n: i32 = sqlx::query_scalar(
+            "SELECT ensure_monthly_partitions($1, GREATEST(now() - interval '1 month', $3), $2)",
+        )
+        .bind(table)
+        .bind(months_ahead)
+        .bind(cutoff)
+        .fetch_one(pool)
+        .await?;
+        if n > 0 {
+            info!("{table}: created {n} partition(s)");
+        }
+        created += n;
+    }
+    Ok(created)
+}
+
+/// Range partitions of `table` ordered by range. The `*_default` partition has
+/// no range and is left out; it is never dropped.
+pub async fn list_partitions(pool: &PgPool, table: &str) -> Result<Vec<PartitionInfo>> {
+    check_table(table)?;
+    let rows: Vec<(String, Option<OffsetDateTime>, OffsetDateTime, i64)> = sqlx::query_as(
+        r#"
+        SELECT c.relname::text,
+               substring(pg_get_expr(c.relpartbound, c.oid) FROM 'FROM \(''([^'']+)''\)')::timestamptz,
+               substring(pg_get_expr(c.relpartbound, c.oid) FROM 'TO \(''([^'']+)''\)')::timestamptz,
+               GREATEST(c.reltuples, 0)::bigint
+        FROM pg_inherits i
+        JOIN pg_class c ON c.oid = i.inhrelid
+        WHERE i.inhparent = $1::regclass
+          AND pg_get_expr(c.relpartbound, c.oid) <> 'DEFAULT'
+        ORDER BY 3
+        "#,
+    )
+    .bind(table)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|(partition, from, to, approx_rows)| PartitionInfo {
+            table: table.to_string(),
+            partition,
+            from,
+            to,
+            approx_rows,
+        })
+        .collect())
+}
+
+/// Drop every partition of `table` whose range ends at or before `cutoff`.
+/// Rows in the partition straddling the cutoff are kept, so retention is
+/// month-granular and never scans or deletes row by row.
+pub async fn drop_partitions_before(
+    pool: &PgPool,
+    table: &str,
+    cutoff: OffsetDateTime,
+) -> Result<Vec<String>> {
+    let mut dropped = Vec::new();
+    for p in list_partitions(pool, table).await? {
+        if p.to > cutoff {
+            continue;
+        }
+        let mut tx = pool.begin().await?;
+        sqlx::query(&format!(r#"ALTER TABLE "{}" DETACH PARTITION "{}""#, table, p.partition))
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query(&format!(r#"DROP TABLE "{}""#, p.partition))
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        info!(
+            "{}: dropped partition {} [{:?}, {}) ~{} rows",
+            p.table, p.partition, p.from, p.to, p.approx_rows
+        );
+        dropped.push(p.partition);
+    }
+    Ok(dropped)
+}
+
+/// Scheduled maintenance: create the current and upcoming partitions.
+/// Expired partitions are dropped by `db::retention`, which is the only
+/// retention setting (`RETENTION_FUNDING_DAYS`, `RETENTION_STATS_DAYS`).
+pub async fn maintain_partitions(pool: &PgPool) -> Result<()> {
+    if std::env::var_os("PARTITION_RETENTION_MONTHS").is_some() {
+        warn!("PARTITION_RETENTION_MONTHS is no longer read; set RETENTION_FUNDING_DAYS / RETENTION_STATS_DAYS instead");
+    }
+    ensure_partitions(pool, DEFAULT_MONTHS_AHEAD).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::migrations::scratch_db;
+
+    // Retention drops last month, a backfill writes into it again (the row
+    // lands in the default partition), then maintenance recreates the month
+    #[tokio::test]
+    async fn ensure_after_retention_moves_default_rows() {
+        let Some(pool) = scratch_db("partitions_retention_then_ensure").await else {
+            return;
+        };
+        let last_month: OffsetDateTime =
+            sqlx::query_scalar("SELECT date_trunc('month', now()) - interval '1 month'")
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        let market_id: i32 = sqlx::query_scalar(
+            "WITH e AS (INSERT INTO exchanges (name) VALUES ('Paradex') RETURNING id),
+                  t AS (INSERT INTO tokens (symbol) VALUES ('BTC') RETURNING id)
+             INSERT INTO markets (exchange_id, token_id, market_symbol)
+             SELECT e.id, t.id, 'BTC-USD-PERP' FROM e, t RETURNING id",
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+
+        // On a fresh database the legacy partition reaches past last month
+        let cutoff = list_partitions(&pool, "funding_rates").await.unwrap()[0].to;
+        assert!(cutoff > last_month);
+        drop_partitions_before(&pool, "funding_rates", cutoff).await.unwrap();
+
+        sqlx::query(
+            "INSERT INTO funding_rates (exchange_id, market_id, rate, timestamp)
+             SELECT exchange_id, id, 0.0001, $2 FROM markets WHERE id = $1",
+        )
+        .bind(market_id)
+        .bind(last_month + Duration::days(1))
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        assert!(ensure_partitions(&pool, 1).await.unwrap() >= 1);
+        let partition: String = sqlx::query_scalar("SELECT tableoid::regclass::text FROM funding_rates")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        let expected = format!("funding_rates_y{}m{:02}", last_month.year(), u8::from(last_month.month()));
+        assert_eq!(partition, expected);
+
+        // Nothing left to move: a second run is a no-op
+        assert_eq!(ensure_partitions(&pool, 1).await.unwrap(), 0);
+    }
+}
@@ -0,0 +1,231 @@
+// backend/src/db/retention.rs
+use anyhow::Result;
+use serde::Serialize;
+use sqlx::PgPool;
+use time::{Duration, OffsetDateTime};
+use tracing::info;
+
+use crate::db::partitions;
+
+/// How long each table keeps its rows. `None` keeps everything; every field
+/// is off unless its env var is set to a positive number of days.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RetentionPolicy {
+    /// Raw `funding_rates` rows (`RETENTION_FUNDING_DAYS`).
+    pub funding_days: Option<i64>,
+    /// Funding rollups (`RETENTION_FUNDING_1H_DAYS`, `_8H_DAYS`, `_1D_DAYS`).
+    pub funding_1h_days: Option<i64>,
+    pub funding_8h_days: Option<i64>,
+    pub funding_1d_days: Option<i64>,
+    /// `market_stats` older than this keep one row per market and UTC hour
+    /// (`RETENTION_STATS_HOURLY_AFTER_DAYS`) ...
+    pub stats_hourly_after_days: Option<i64>,
+    /// ... and older than this one row per UTC day (`RETENTION_STATS_DAILY_AFTER_DAYS`).
+    pub stats_daily_after_days: Option<i64>,
+    /// `market_stats` dropped entirely (`RETENTION_STATS_DAYS`).
+    pub stats_days: Option<i64>,
+    /// `price_snapshots` (`RETENTION_PRICES_DAYS`).
+    pub prices_days: Option<i64>,
+    /// `predicted_funding_rates` (`RETENTION_PREDICTED_DAYS`).
+    pub predicted_days: Option<i64>,
+}
+
+impl RetentionPolicy {
+    pub fn from_env() -> Self {
+        fn days(name: &str) -> Option<i64> {
+            std::env::var(name)
+                .ok()
+                .and_then(|v| v.parse::<i64>().ok())
+                .filter(|d| *d > 0)
+        }
+        Self {
+            funding_days: days("RETENTION_FUNDING_DAYS"),
+            funding_1h_days: days("RETENTION_FUNDING_1H_DAYS"),
+            funding_8h_days: days("RETENTION_FUNDING_8H_DAYS"),
+            funding_1d_days: days("RETENTION_FUNDING_1D_DAYS"),
+            stats_hourly_after_days: days("RETENTION_STATS_HOURLY_AFTER_DAYS"),
+            stats_daily_after_days: days("RETENTION_STATS_DAILY_AFTER_DAYS"),
+            stats_days: days("RETENTION_STATS_DAYS"),
+            prices_days: days("RETENTION_PRICES_DAYS"),
+            predicted_days: days("RETENTION_PREDICTED_DAYS"),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        [
+            self.funding_days,
+            self.funding_1h_days,
+            self.funding_8h_days,
+            self.funding_1d_days,
+            self.stats_hourly_after_days,
+            self.stats_daily_after_days,
+            self.stats_days,
+            self.prices_days,
+            self.predicted_days,
+        ]
+        .iter()
+        .all(Option::is_none)
+    }
+}
+
+/// One thing the prune removed (or, in a dry run, would remove).
+#[derive(Debug, Clone, Serialize)]
+pub struct PruneStep {
+    pub table: &'static str,
+    /// `delete` or `downsample_1h` / `downsample_1d`
+    pub action: &'static str,
+    #[serde(with = "time::serde::rfc3339")]
+    pub cutoff: OffsetDateTime,
+    pub rows: u64,
+    /// Whole partitions dropped instead of deleting their rows one by one.
+    pub partitions: Vec<String>,
+}
+
+/// Apply `policy` as of now. With `dry_run`, count what would go and change nothing.
+pub async fn prune(pool: &PgPool, policy: &RetentionPolicy, dry_run: bool) -> Result<Vec<PruneStep>> {
+    let now = OffsetDateTime::now_utc();
+    let cutoff = |days: i64| now - Duration::days(days);
+    let mut steps = Vec::new();
+
+    if let Some(d) = policy.funding_days {
+        steps.push(delete_partitioned(pool, "funding_rates", cutoff(d), dry_run).await?);
+    }
+    for (table, days) in [
+        ("funding_rollup_1h", policy.funding_1h_days),
+        ("funding_rollup_8h", policy.funding_8h_days),
+        ("funding_rollup_1d", policy.funding_1d_days),
+    ] {
+        if let Some(d) = days {
+            steps.push(delete_before(pool, table, "bucket_start", cutoff(d), dry_run).await?);
+        }
+    }
+
+    if let Some(d) = policy.stats_days {
+        steps.push(delete_partitioned(pool, "market_stats", cutoff(d), dry_run).await?);
+    }
+    // Daily first so the hourly pass doesn't thin rows the daily pass removes anyway
+    let daily = policy.stats_daily_after_days.map(cutoff);
+    if let Some(c) = daily {
+        steps.push(downsample_stats(pool, None, c, "1 day", dry_run).await?);
+    }
+    if let Some(c) = policy.stats_hourly_after_days.map(cutoff) {
+        // Rows already past the daily cutoff were handled above
+        let from = daily.filter(|d| *d < c);
+        steps.push(downsample_stats(pool, from, c, "1 hour", dry_run).await?);
+    }
+
+    if let Some(d) = policy.prices_days {
+        steps.push(delete_before(pool, "price_snapshots", "timestamp", cutoff(d), dry_run).await?);
+    }
+    if let Some(d) = policy.predicted_days {
+        steps.push(delete_before(pool, "predicted_funding_rates", "timestamp", cutoff(d), dry_run).await?);
+    }
+
+    if !dry_run {
+        for s in &steps {
+            info!(
+                "retention: {} {} {} row(s) before {} ({} partition(s) dropped)",
+                s.table, s.action, s.rows, s.cutoff, s.partitions.len()
+            );
+        }
+    }
+    Ok(steps)
+}
+
+/// `table` and `column` are always one of the literals above, never user input.
+async fn delete_before(
+    pool: &PgPool,
+    table: &'static str,
+    column: &str,
+    cutoff: OffsetDateTime,
+    dry_run: bool,
+) -> Result<PruneStep> {
+    let rows = if dry_run {
+        let n: i64 = sqlx::query_scalar(&format!("SELECT COUNT(*) FROM {table} WHERE {column} < $1"))
+            .bind(cutoff)
+            .fetch_one(pool)
+            .await?;
+        n as u64
+    } else {
+        sqlx::query(&format!("DELETE FROM {table} WHERE {column} < $1"))
+            .bind(cutoff)
+            .execute(pool)
+            .await?
+            .rows_affected()
+    };
+    Ok(PruneStep { table, action: "delete", cutoff, rows, partitions: Vec::new() })
+}
+
+/// Drop the partitions that end before `cutoff`, then delete what is left
+/// before it in the partition straddling the cutoff and the default partition.
+async fn delete_partitioned(
+    pool: &PgPool,
+    table: &'static str,
+    cutoff: OffsetDateTime,
+    dry_run: bool,
+) -> Result<PruneStep> {
+    let expired: Vec<String> = partitions::list_partitions(pool, table)
+        .await?
+        .into_iter()
+        .filter(|p| p.to <= cutoff)
+        .map(|p| p.partition)
+        .collect();
+
+    let mut rows = 0u64;
+    for p in &expired {
+        let n: i64 = sqlx::query_scalar(&format!(r#"SELECT COUNT(*) FROM "{p}""#))
+            .fetch_one(pool)
+            .await?;
+        rows += n as u64;
+    }
+    if !dry_run {
+        partitions::drop_partitions_before(pool, table, cutoff).await?;
+    }
+
+    // In a dry run the expired partitions are still there; don't count them twice
+    let rest = delete_before(pool, table, "timestamp", cutoff, dry_run).await?;
+    let rows = if dry_run { rest.rows } else { rows + rest.rows };
+    Ok(PruneStep { table, action: "delete", cutoff, rows, partitions: expired })
+}
+
+/// Keep the newest `market_stats` row per market and `width` bucket for rows
+/// in [`from`, `to`); `from = None` means from the beginning.
+async fn downsample_stats(
+    pool: &PgPool,
+    from: Option<OffsetDateTime>,
+    to: OffsetDateTime,
+    width: &str,
+    dry_run: bool,
+) -> Result<PruneStep> {
+    let ranked = r#"
+        SELECT market_id, timestamp,
+               ROW_NUMBER() OVER (
+                 PARTITION BY market_id, date_bin($3::interval, timestamp, TIMESTAMPTZ 'epoch')
+                 ORDER BY timestamp DESC
+               ) AS rn
+        FROM market_stats
+        WHERE ($1::timestamptz IS NULL OR timestamp >= $1) AND timestamp < $2
+    "#;
+    let rows = if dry_run {
+        let n: i64 = sqlx::query_scalar(&format!("SELECT COUNT(*) FROM ({ranked}) r WHERE r.rn > 1"))
+            .bind(from)
+            .bind(to)
+            .bind(width)
+            .fetch_one(pool)
+            .await?;
+        n as u64
+    } else {
+        sqlx::query(&format!(
+            "DELETE FROM market_stats ms USING ({ranked}) r
+             WHERE r.rn > 1 AND ms.market_id = r.market_id AND ms.timestamp = r.timestamp"
+        ))
+        .bind(from)
+        .bind(to)
+        .bind(width)
+        .execute(pool)
+        .await?
+        .rows_affected()
+    };
+    let action = if width == "1 day" { "downsample_1d" } else { "downsample_1h" };
+    Ok(PruneStep { table: "market_stats", action, cutoff: to, rows, partitions: Vec::new() })
+}
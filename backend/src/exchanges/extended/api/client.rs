@@ -1,7 +1,12 @@
 // 
 use super::endpoints::{ApiEnvironment, PublicEndpoint, get_public_url};
+use crate::utils::metrics;
 use bytes::Bytes;
-use reqwest::Client;
+use reqwest::{Client, RequestBuilder};
+use std::time::Instant;
+
+/// Largest page the funding history endpoint serves.
+pub const FUNDING_PAGE_LIMIT: usize = 10_000;
 
 #[derive(Debug, Clone)]
 pub struct ExtendedClient {
@@ -22,6 +27,13 @@ impl ExtendedClient {
         }
     }
 
+    async fn send(&self, endpoint: &str, request: RequestBuilder) -> Result<Bytes, reqwest::Error> {
+        let started = Instant::now();
+        let res = request.send().await;
+        metrics::observe_http("extended", endpoint, &res, started.elapsed());
+        res?.error_for_status()?.bytes().await
+    }
+
 
     pub async fn get_markets(&self, market: Option<&str>) -> Result<Bytes, reqwest::Error> {
         let url = get_public_url(PublicEndpoint::Markets, self.environment);
@@ -29,15 +41,13 @@ impl ExtendedClient {
         if let Some(market) = market {
             request = request.query(&[("market", market)]);
         }
-        let res = request.send().await?;
-        res.error_for_status()?.bytes().await
+        self.send("markets", request).await
     }
     
 
     pub async fn get_market_stats(&self, market: &str) -> Result<Bytes, reqwest::Error> {
         let url = get_public_url(PublicEndpoint::MarketStats(market.to_string()), self.environment);
-        let res = self.client.get(&url).send().await?;
-        res.error_for_status()?.bytes().await
+        self.send("market_stats", self.client.get(&url)).await
     }
 
 
@@ -66,8 +76,7 @@ impl ExtendedClient {
             request_builder = request_builder.query(&query_params);
         }
 
-        let res = request_builder.send().await?;
-        res.error_for_status()?.bytes().await
+        self.send("open_interest", request_builder).await
     }
 
 
@@ -76,23 +85,22 @@ impl ExtendedClient {
         market: &str,
         start_time: Option<u64>,
         end_time: Option<u64>,
+        cursor: Option<&str>,
     ) -> Result<Bytes, reqwest::Error> {
         let url = get_public_url(PublicEndpoint::Funding(market.to_string()), self.environment);
 
-        let mut query_params = Vec::new();
+        let mut query_params = vec![("limit".to_string(), FUNDING_PAGE_LIMIT.to_string())];
         if let Some(st) = start_time {
             query_params.push(("startTime".to_string(), st.to_string()));
         }
         if let Some(et) = end_time {
             query_params.push(("endTime".to_string(), et.to_string()));
         }
-
-        let mut request_builder = self.client.get(&url);
-        if !query_params.is_empty() {
-            request_builder = request_builder.query(&query_params);
+        if let Some(c) = cursor {
+            query_params.push(("cursor".to_string(), c.to_string()));
         }
 
-        let res = request_builder.send().await?;
-        res.error_for_status()?.bytes().await
+        let request_builder = self.client.get(&url).query(&query_params);
+        self.send("funding", request_builder).await
     }
 }
\ No newline at end of file
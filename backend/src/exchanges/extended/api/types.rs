@@ -21,12 +21,33 @@ pub struct ExtendedInlineMarketStats {
     pub openInterest: Decimal,
     #[serde(deserialize_with = "deserialize_decimal_from_str")]
     pub dailyVolume: Decimal,
+    // current hourly rate, applied at the next funding
+    #[serde(default, deserialize_with = "deserialize_opt_decimal_from_str")]
+    pub fundingRate: Option<Decimal>,
+    // despite the name, the time (ms) of the next funding payment
+    #[serde(default)]
+    pub nextFundingRate: Option<i64>,
+    #[serde(default, deserialize_with = "deserialize_opt_decimal_from_str")]
+    pub markPrice: Option<Decimal>,
+    #[serde(default, deserialize_with = "deserialize_opt_decimal_from_str")]
+    pub indexPrice: Option<Decimal>,
+    #[serde(default, deserialize_with = "deserialize_opt_decimal_from_str")]
+    pub lastPrice: Option<Decimal>,
 }
 
 /// GET /funding
 #[derive(Debug, Deserialize)]
 pub struct ExtendedFundingResponse {
     pub data: Vec<ExtendedFundingData>,
+    #[serde(default)]
+    pub pagination: Option<ExtendedPagination>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ExtendedPagination {
+    // numeric on the wire; opaque to us
+    pub cursor: Option<serde_json::Value>,
+    pub count: Option<usize>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -49,6 +70,12 @@ pub struct ExtendedMarketStatsData {
     pub openInterest: Decimal,
     #[serde(deserialize_with = "deserialize_decimal_from_str")]
     pub dailyVolume: Decimal,
+    #[serde(default, deserialize_with = "deserialize_opt_decimal_from_str")]
+    pub markPrice: Option<Decimal>,
+    #[serde(default, deserialize_with = "deserialize_opt_decimal_from_str")]
+    pub indexPrice: Option<Decimal>,
+    #[serde(default, deserialize_with = "deserialize_opt_decimal_from_str")]
+    pub lastPrice: Option<Decimal>,
 }
 
 fn deserialize_decimal_from_str<'de, D>(deserializer: D) -> Result<Decimal, D::Error>
@@ -57,4 +84,15 @@ where
 {
     let s: &str = Deserialize::deserialize(deserializer)?;
     s.parse().map_err(serde::de::Error::custom)
-}
\ No newline at end of file
+}
+
+fn deserialize_opt_decimal_from_str<'de, D>(deserializer: D) -> Result<Option<Decimal>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let s: Option<&str> = Deserialize::deserialize(deserializer)?;
+    match s.map(str::trim) {
+        None | Some("") => Ok(None),
+        Some(s) => s.parse().map(Some).map_err(serde::de::Error::custom),
+    }
+}
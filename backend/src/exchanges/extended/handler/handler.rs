@@ -1,7 +1,7 @@
 use anyhow::Result;
 use bytes::Bytes;
 use chrono::{TimeZone, Utc, LocalResult};
-use crate::exchanges::shared::types::{NormalizedFundingRate, NormalizedMarketStats, NormalizedMarket};
+use crate::exchanges::shared::types::{FundingPage, NormalizedFundingRate, NormalizedMarketStats, NormalizedMarket, NormalizedPredictedFunding};
 use crate::exchanges::extended::api::types::{ExtendedFundingResponse, ExtendedMarketsResponse, ExtendedMarketStatsResponse};
 
 #[inline]
@@ -22,17 +22,61 @@ pub fn parse_extended_markets(raw: &Bytes) -> Result<Vec<NormalizedMarket>> {
         base_currency: m.assetName.to_string(),
         quote_currency: m.name.split('-').nth(1).unwrap_or("").to_string(),
         is_active: m.active,
+        funding_interval_minutes: Some(60), // Extended pays (and reports) hourly funding
+    }).collect())
+}
+
+/// Parse predicted funding from the inline marketStats of /info/markets
+pub fn parse_extended_predicted_funding(raw: &Bytes) -> Result<Vec<NormalizedPredictedFunding>> {
+    let resp: ExtendedMarketsResponse = serde_json::from_slice(raw)?;
+    let now = Utc::now();
+    Ok(resp.data.into_iter().filter(|m| m.active).filter_map(|m| {
+        let stats = m.marketStats?;
+        Some(NormalizedPredictedFunding {
+            market_symbol: m.name,
+            rate: stats.fundingRate?,
+            next_funding_time: stats.nextFundingRate.map(ts_utc),
+            timestamp: now,
+        })
+    }).collect())
+}
+
+/// Parse market stats for every active market from the inline marketStats of
+/// /info/markets (same units as /markets/{market}/stats)
+pub fn parse_extended_markets_stats(raw: &Bytes) -> Result<Vec<NormalizedMarketStats>> {
+    let resp: ExtendedMarketsResponse = serde_json::from_slice(raw)?;
+    let now = Utc::now();
+    Ok(resp.data.into_iter().filter(|m| m.active).filter_map(|m| {
+        let stats = m.marketStats?;
+        Some(NormalizedMarketStats {
+            market_symbol: m.name,
+            open_interest: Some(stats.openInterest),
+            volume_24h: Some(stats.dailyVolume),
+            mark_price: stats.markPrice,
+            index_price: stats.indexPrice,
+            last_price: stats.lastPrice,
+            timestamp: now,
+        })
     }).collect())
 }
 
 /// Parse /funding
-pub fn parse_extended_funding(raw: &Bytes) -> Result<Vec<NormalizedFundingRate>> {
+/// Only a full page continues: the cursor is also set on the last one.
+pub fn parse_extended_funding(raw: &Bytes) -> Result<FundingPage> {
+    use crate::exchanges::extended::api::client::FUNDING_PAGE_LIMIT;
+
     let resp: ExtendedFundingResponse = serde_json::from_slice(raw)?;
-    Ok(resp.data.into_iter().map(|f| NormalizedFundingRate {
+    let next = resp
+        .pagination
+        .filter(|p| p.count.unwrap_or(resp.data.len()) >= FUNDING_PAGE_LIMIT)
+        .and_then(|p| p.cursor)
+        .map(|c| c.as_str().map(str::to_string).unwrap_or_else(|| c.to_string()));
+    let rows = resp.data.into_iter().map(|f| NormalizedFundingRate {
         market_symbol: f.m.to_string(),
         rate: f.f,
         timestamp: ts_utc(f.T),
-    }).collect())
+    }).collect();
+    Ok(FundingPage { rows, next })
 }
 
 /// Parse /markets/{symbol}/stats
@@ -42,6 +86,9 @@ pub fn parse_extended_market_stats(raw: &Bytes, market: &str) -> Result<Normaliz
         market_symbol: market.to_string(),
         open_interest: Some(resp.data.openInterest),
         volume_24h: Some(resp.data.dailyVolume), 
+        mark_price: resp.data.markPrice,
+        index_price: resp.data.indexPrice,
+        last_price: resp.data.lastPrice,
         timestamp: Utc::now(),
     })
 }
@@ -1,6 +1,8 @@
 use super::endpoints::{ApiEnvironment, PublicEndpoint, get_public_url};
+use crate::utils::metrics;
 use bytes::Bytes;
-use reqwest::Client;
+use reqwest::{Client, RequestBuilder};
+use std::time::Instant;
 
 #[derive(Debug, Clone)]
 pub struct ParadexClient {
@@ -16,21 +18,22 @@ impl ParadexClient {
         }
     }
 
+    async fn send(&self, endpoint: &str, request: RequestBuilder) -> Result<Bytes, reqwest::Error> {
+        let started = Instant::now();
+        let res = request.send().await;
+        metrics::observe_http("paradex", endpoint, &res, started.elapsed());
+        res?.error_for_status()?.bytes().await
+    }
+
     pub async fn get_markets(&self) -> Result<Bytes, reqwest::Error> {
         let url = get_public_url(PublicEndpoint::Markets, self.environment);
-        let res = self.client.get(&url).send().await?;
-        res.error_for_status()?.bytes().await
+        self.send("markets", self.client.get(&url)).await
     }
     
     pub async fn get_markets_summary(&self, market: &str) -> Result<Bytes, reqwest::Error> {
         let url = get_public_url(PublicEndpoint::MarketsSummary, self.environment);
-        let res = self
-            .client
-            .get(&url)
-            .query(&[("market", market)])
-            .send()
-            .await?;
-        res.error_for_status()?.bytes().await
+        let request = self.client.get(&url).query(&[("market", market)]);
+        self.send("markets_summary", request).await
     }
 
     pub async fn get_funding_data(
@@ -38,6 +41,7 @@ impl ParadexClient {
         market: &str,
         start_time: Option<u64>,
         end_time: Option<u64>,
+        cursor: Option<&str>,
     ) -> Result<Bytes, reqwest::Error> {
         let url = get_public_url(PublicEndpoint::FundingData, self.environment);
 
@@ -52,15 +56,12 @@ impl ParadexClient {
         if let Some(et) = end_time {
             query_params.push(("end_at".to_string(), et.to_string()));
         }
+        if let Some(c) = cursor {
+            query_params.push(("cursor".to_string(), c.to_string()));
+        }
 
-        let res = self
-            .client
-            .get(&url)
-            .query(&query_params)
-            .send()
-            .await?;
-
-        res.error_for_status()?.bytes().await
+        let request = self.client.get(&url).query(&query_params);
+        self.send("funding_data", request).await
     }
 
 
@@ -77,6 +77,8 @@ pub struct ParadexMarket {
     pub quote_currency: String,
     #[serde(default)]
     pub asset_kind: Option<String>,
+    #[serde(default, deserialize_with = "de_opt_decimal")]
+    pub funding_period_hours: Option<Decimal>,
 }
 
 /* /markets/summary */
@@ -107,6 +109,10 @@ pub struct ParadexMarketSummary {
 
     #[serde(default, deserialize_with = "de_opt_decimal")]
     pub last_traded_price: Option<Decimal>,
+
+    // Current (predicted) rate for the running funding period
+    #[serde(default, deserialize_with = "de_opt_decimal")]
+    pub funding_rate: Option<Decimal>,
 }
 
 
@@ -5,7 +5,7 @@ use chrono::{LocalResult, TimeZone, Utc};
 use rust_decimal::Decimal;
 
 use crate::exchanges::shared::types::{
-    NormalizedFundingRate, NormalizedMarket, NormalizedMarketStats,
+    NormalizedFundingRate, NormalizedMarket, NormalizedMarketStats, NormalizedPredictedFunding,
 };
 use crate::exchanges::paradex::api::types::{
     ParadexFundingResponse, ParadexMarketsResponse, ParadexSummaryResponse,
@@ -40,6 +40,12 @@ pub fn parse_paradex_markets(raw: &Bytes) -> Result<Vec<NormalizedMarket>> {
             base_currency: m.base_currency,
             quote_currency: m.quote_currency,
             is_active: true,
+            // funding_period_hours is 8 on every perp today; default to that if absent
+            funding_interval_minutes: Some(
+                m.funding_period_hours
+                    .and_then(|h| (h * Decimal::from(60)).to_i32())
+                    .unwrap_or(480),
+            ),
         })
         .collect())
 }
@@ -73,6 +79,9 @@ pub fn parse_paradex_market_stats(raw: &Bytes) -> Result<Vec<NormalizedMarketSta
                 open_interest: oi_usd,
                 // volume_24h is already USD per API; keep as-is if present
                 volume_24h: s.volume_24h,
+                mark_price: s.mark_price,
+                index_price: s.underlying_price, // spot index of the underlying
+                last_price: s.last_traded_price,
                 timestamp: now, // summary doesn’t carry a per-row ts
             }
         })
@@ -82,15 +91,42 @@ pub fn parse_paradex_market_stats(raw: &Bytes) -> Result<Vec<NormalizedMarketSta
 }
 
 
+/// Predicted funding from /markets/summary. Paradex accrues funding continuously
+/// over `period_minutes`, so the "next" time is the end of the current period
+/// counted from 00:00 UTC.
+pub fn parse_paradex_predicted_funding(
+    raw: &Bytes,
+    period_minutes: i32,
+) -> Result<Vec<NormalizedPredictedFunding>> {
+    let resp: ParadexSummaryResponse = serde_json::from_slice(raw)?;
+    let now = Utc::now();
+    let period_ms = i64::from(period_minutes.max(1)) * 60_000;
+    let next_ms = (now.timestamp_millis() / period_ms + 1) * period_ms;
 
-pub fn parse_paradex_funding(raw: &bytes::Bytes) -> anyhow::Result<Vec<crate::exchanges::shared::types::NormalizedFundingRate>> {
+    Ok(resp
+        .results
+        .into_iter()
+        .filter(|s| perp_symbol(&s.symbol))
+        .filter_map(|s| {
+            s.funding_rate.map(|rate| NormalizedPredictedFunding {
+                market_symbol: s.symbol,
+                rate,
+                next_funding_time: Some(ts_utc(next_ms)),
+                timestamp: now,
+            })
+        })
+        .collect())
+}
+
+pub fn parse_paradex_funding(raw: &bytes::Bytes) -> anyhow::Result<crate::exchanges::shared::types::FundingPage> {
     use anyhow::Context;
     use crate::exchanges::paradex::api::types::ParadexFundingResponse;
 
     let text = std::str::from_utf8(raw).context("decode Paradex funding UTF-8")?;
     let resp: ParadexFundingResponse = serde_json::from_str(text).context("parse ParadexFundingResponse")?;
 
-    Ok(resp
+    let next = resp.next.filter(|c| !c.is_empty() && !resp.results.is_empty());
+    let rows = resp
         .results
         .into_iter()
         .filter_map(|f| {
@@ -100,5 +136,6 @@ pub fn parse_paradex_funding(raw: &bytes::Bytes) -> anyhow::Result<Vec<crate::ex
                 timestamp: ts_utc(f.created_at), // ms -> DateTime<Utc>
             })
         })
-        .collect())
+        .collect();
+    Ok(crate::exchanges::shared::types::FundingPage { rows, next })
 }
@@ -10,6 +10,8 @@ pub struct NormalizedMarket {
     pub base_currency: String,
     pub quote_currency: String,
     pub is_active: bool,
+    /// Native funding period, when the venue publishes one.
+    pub funding_interval_minutes: Option<i32>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -19,10 +21,31 @@ pub struct NormalizedFundingRate {
     pub timestamp: DateTime<Utc>,
 }
 
+/// One page of funding history. `next` is the venue's cursor for the rest of
+/// the same query, `None` on the last page.
+#[derive(Debug, Clone, Default)]
+pub struct FundingPage {
+    pub rows: Vec<NormalizedFundingRate>,
+    pub next: Option<String>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NormalizedMarketStats {
     pub market_symbol: String,
     pub open_interest: Option<Decimal>,
     pub volume_24h: Option<Decimal>,
+    // prices at the same instant; also written to price_snapshots
+    pub mark_price: Option<Decimal>,
+    pub index_price: Option<Decimal>,
+    pub last_price: Option<Decimal>,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Rate the venue currently predicts for the upcoming interval (not yet paid).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NormalizedPredictedFunding {
+    pub market_symbol: String,
+    pub rate: Decimal, // native per-interval fraction, like NormalizedFundingRate
+    pub next_funding_time: Option<DateTime<Utc>>,
     pub timestamp: DateTime<Utc>,
 }
@@ -0,0 +1,741 @@
+// backend/src/import.rs
+// Historical funding from files (venue dumps, our own archives, `sync export`
+// output): CSV / Parquet / JSONL rows are mapped onto (exchange, market, rate,
+// timestamp[, interval]), validated into `NormalizedFundingRate` and written
+// through the bulk insert path. Only the sync CLI uses this.
+use anyhow::{anyhow, bail, Context, Result};
+use arrow_array::cast::AsArray;
+use arrow_array::types::TimestampNanosecondType;
+use arrow_array::{Array, RecordBatch};
+use arrow_schema::{DataType, TimeUnit};
+use chrono::{DateTime, Utc};
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use rust_decimal::Decimal;
+use serde::Serialize;
+use sqlx::PgPool;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::BufRead;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use time::format_description::well_known::Rfc3339;
+use time::macros::format_description;
+use time::{OffsetDateTime, PrimitiveDateTime};
+use tracing::{info, warn};
+
+use crate::db::insert;
+use crate::exchanges::shared::types::{NormalizedFundingRate, NormalizedMarket};
+
+/// Valid rows written per round (markets resolved, then one bulk insert per exchange).
+const BATCH_ROWS: usize = 100_000;
+/// A rate beyond ±100% per interval is a unit mistake (percent, bps), not data.
+const MAX_ABS_RATE: Decimal = Decimal::ONE;
+/// Earliest funding timestamp accepted (perpetual swaps didn't exist before).
+const MIN_TS: i64 = 1_420_070_400; // 2015-01-01
+/// Invalid rows logged individually before only being counted.
+const MAX_REPORTED: usize = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportFormat {
+    Csv,
+    Parquet,
+    Jsonl,
+}
+
+impl FromStr for ImportFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "csv" => Ok(Self::Csv),
+            "parquet" | "pq" => Ok(Self::Parquet),
+            "jsonl" | "ndjson" | "json" => Ok(Self::Jsonl),
+            other => Err(anyhow!("unknown import format {other:?} (csv | parquet | jsonl)")),
+        }
+    }
+}
+
+impl ImportFormat {
+    /// From the file extension, e.g. `rates.csv` or `part-0.parquet`.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        path.extension()?.to_str()?.parse().ok()
+    }
+}
+
+/// Source column for each field. The defaults match `sync export`, so
+/// exported data imports as is.
+#[derive(Debug, Clone)]
+pub struct ColumnMap {
+    pub exchange: String,
+    pub market: String,
+    /// Optional; derived from the market symbol (`BTC-USD-PERP` -> `BTC`) when absent.
+    pub token: String,
+    pub rate: String,
+    pub timestamp: String,
+    /// Optional; `8h`, `30m` or bare minutes. Sets the interval of markets that have none.
+    pub interval: String,
+}
+
+impl Default for ColumnMap {
+    fn default() -> Self {
+        Self {
+            exchange: "exchange".into(),
+            market: "market_symbol".into(),
+            token: "token".into(),
+            rate: "rate".into(),
+            timestamp: "ts".into(),
+            interval: "interval".into(),
+        }
+    }
+}
+
+impl ColumnMap {
+    /// Apply `field=column[,field=column...]`, e.g. `market=symbol,timestamp=time`.
+    pub fn apply(&mut self, spec: &str) -> Result<()> {
+        for pair in spec.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            let (field, column) = pair
+                .split_once('=')
+                .ok_or_else(|| anyhow!("invalid column mapping {pair:?}, expected FIELD=COLUMN"))?;
+            let slot = match field.trim().to_ascii_lowercase().as_str() {
+                "exchange" => &mut self.exchange,
+                "market" | "market_symbol" => &mut self.market,
+                "token" => &mut self.token,
+                "rate" => &mut self.rate,
+                "timestamp" | "ts" => &mut self.timestamp,
+                "interval" => &mut self.interval,
+                other => bail!("unknown field {other:?} (exchange | market | token | rate | timestamp | interval)"),
+            };
+            *slot = column.trim().to_string();
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ImportOptions {
+    /// Forced format; otherwise taken from each file's extension.
+    pub format: Option<ImportFormat>,
+    pub columns: ColumnMap,
+    /// Every row belongs to this exchange (the file's exchange column is ignored).
+    pub exchange: Option<String>,
+    /// Validate and count only; nothing is written.
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct ImportReport {
+    pub files: usize,
+    pub rows: u64,
+    pub valid: u64,
+    /// Invalid row count per reason.
+    pub invalid: BTreeMap<&'static str, u64>,
+    /// Valid rows per exchange, by lowercase name (written, unless a dry run).
+    pub exchanges: BTreeMap<String, u64>,
+    pub markets: usize,
+    pub markets_created: usize,
+}
+
+/// Read `path` (a file, or a directory searched recursively) and ingest its rows.
+pub async fn import(pool: &PgPool, path: &Path, opts: &ImportOptions) -> Result<ImportReport> {
+    let files = input_files(path, opts.format)?;
+    if files.is_empty() {
+        bail!("no csv / parquet / jsonl files under {}", path.display());
+    }
+
+    let mut report = ImportReport { files: files.len(), ..Default::default() };
+    let mut markets = MarketResolver::default();
+    let mut seen_markets: HashSet<(String, String)> = HashSet::new();
+    let now = OffsetDateTime::now_utc().unix_timestamp();
+
+    for (file, format) in files {
+        info!("import: reading {} ({:?})", file.display(), format);
+        let hive = hive_values(&file);
+        let mut rows = read_rows(&file, format, &opts.columns, &hive)?;
+        let mut batch: Vec<ImportRow> = Vec::with_capacity(BATCH_ROWS);
+
+        loop {
+            let next = rows.next().transpose().with_context(|| format!("reading {}", file.display()))?;
+            let done = next.is_none();
+            if let Some(raw) = next {
+                report.rows += 1;
+                match raw.validate(opts.exchange.as_deref(), now) {
+                    Ok(row) => {
+                        report.valid += 1;
+                        *report.exchanges.entry(row.exchange.to_ascii_lowercase()).or_default() += 1;
+                        seen_markets.insert((row.exchange.to_ascii_lowercase(), row.rate.market_symbol.clone()));
+                        if !opts.dry_run {
+                            batch.push(row);
+                        }
+                    }
+                    Err(reason) => {
+                        *report.invalid.entry(reason).or_default() += 1;
+                        if report.invalid.values().sum::<u64>() <= MAX_REPORTED as u64 {
+                            warn!("import: {} row {}: {}", file.display(), raw.row, reason);
+                        }
+                    }
+                }
+            }
+            if batch.len() >= BATCH_ROWS || (done && !batch.is_empty()) {
+                write_batch(pool, &mut markets, &batch).await?;
+                batch.clear();
+            }
+            if done {
+                break;
+            }
+        }
+    }
+
+    report.markets = seen_markets.len();
+    report.markets_created = markets.created;
+    Ok(report)
+}
+
+/// Files to read with their format, sorted by path.
+fn input_files(path: &Path, forced: Option<ImportFormat>) -> Result<Vec<(PathBuf, ImportFormat)>> {
+    if path.is_file() {
+        let format = forced
+            .or_else(|| ImportFormat::from_path(path))
+            .ok_or_else(|| anyhow!("can't tell the format of {}; pass --format", path.display()))?;
+        return Ok(vec![(path.to_path_buf(), format)]);
+    }
+
+    let mut out = Vec::new();
+    let mut dirs = vec![path.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        for entry in std::fs::read_dir(&dir).with_context(|| format!("reading {}", dir.display()))? {
+            let p = entry?.path();
+            if p.is_dir() {
+                dirs.push(p);
+            } else if let Some(format) = ImportFormat::from_path(&p)
+                && forced.is_none_or(|f| f == format)
+            {
+                out.push((p, format));
+            }
+        }
+    }
+    out.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(out)
+}
+
+/// `key=value` directories in the file's path (hive partitions, as written by
+/// `sync export`), used for columns the file itself doesn't have.
+fn hive_values(file: &Path) -> HashMap<String, String> {
+    file.parent()
+        .into_iter()
+        .flat_map(|p| p.components())
+        .filter_map(|c| c.as_os_str().to_str()?.split_once('='))
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect()
+}
+
+/* ----------------- Reading ----------------- */
+
+enum TsValue {
+    Text(String),
+    At(OffsetDateTime),
+}
+
+/// One input row before validation; `row` is the 1-based data row in its file.
+struct RawRow {
+    row: u64,
+    exchange: Option<String>,
+    market: Option<String>,
+    token: Option<String>,
+    rate: Option<String>,
+    ts: Option<TsValue>,
+    interval: Option<String>,
+}
+
+type Rows = Box<dyn Iterator<Item = Result<RawRow>>>;
+
+fn read_rows(path: &Path, format: ImportFormat, cols: &ColumnMap, hive: &HashMap<String, String>) -> Result<Rows> {
+    match format {
+        ImportFormat::Csv => csv_rows(path, cols, hive),
+        ImportFormat::Jsonl => jsonl_rows(path, cols, hive),
+        ImportFormat::Parquet => parquet_rows(path, cols, hive),
+    }
+}
+
+/// Where a field comes from: a column position, a constant (hive path), or nowhere.
+#[derive(Clone)]
+enum Source {
+    Column(usize),
+    Fixed(String),
+    Missing,
+}
+
+impl Source {
+    fn resolve(name: &str, header: &[String], hive: &HashMap<String, String>) -> Self {
+        match header.iter().position(|h| h == name) {
+            Some(i) => Source::Column(i),
+            None => hive.get(name).map_or(Source::Missing, |v| Source::Fixed(v.clone())),
+        }
+    }
+}
+
+/// Field sources for one file; fails if a required column is missing.
+struct Layout {
+    exchange: Source,
+    market: Source,
+    token: Source,
+    rate: Source,
+    timestamp: Source,
+    interval: Source,
+}
+
+impl Layout {
+    fn new(cols: &ColumnMap, header: &[String], hive: &HashMap<String, String>) -> Result<Self> {
+        let layout = Self {
+            exchange: Source::resolve(&cols.exchange, header, hive),
+            market: Source::resolve(&cols.market, header, hive),
+            token: Source::resolve(&cols.token, header, hive),
+            rate: Source::resolve(&cols.rate, header, hive),
+            timestamp: Source::resolve(&cols.timestamp, header, hive),
+            interval: Source::resolve(&cols.interval, header, hive),
+        };
+        for (field, name, src) in [
+            ("market", &cols.market, &layout.market),
+            ("rate", &cols.rate, &layout.rate),
+            ("timestamp", &cols.timestamp, &layout.timestamp),
+        ] {
+            if matches!(src, Source::Missing) {
+                bail!("no {field} column {name:?} (columns: {}); map it with --map {field}=COLUMN", header.join(", "));
+            }
+        }
+        Ok(layout)
+    }
+
+    /// Whether column `i` feeds any field.
+    fn uses(&self, i: usize) -> bool {
+        [&self.exchange, &self.market, &self.token, &self.rate, &self.timestamp, &self.interval]
+            .into_iter()
+            .any(|src| matches!(src, Source::Column(c) if *c == i))
+    }
+
+    fn row(&self, row: u64, mut get: impl FnMut(usize) -> Option<String>) -> RawRow {
+        let mut text = |src: &Source| match src {
+            Source::Column(i) => get(*i).filter(|v| !v.trim().is_empty()),
+            Source::Fixed(v) => Some(v.clone()),
+            Source::Missing => None,
+        };
+        RawRow {
+            row,
+            exchange: text(&self.exchange),
+            market: text(&self.market),
+            token: text(&self.token),
+            rate: text(&self.rate),
+            ts: text(&self.timestamp).map(TsValue::Text),
+            interval: text(&self.interval),
+        }
+    }
+}
+
+fn csv_rows(path: &Path, cols: &ColumnMap, hive: &HashMap<String, String>) -> Result<Rows> {
+    let mut reader = csv::ReaderBuilder::new().flexible(true).from_path(path)?;
+    let header: Vec<String> = reader.headers()?.iter().map(|h| h.trim().to_string()).collect();
+    let layout = Layout::new(cols, &header, hive)?;
+    Ok(Box::new(reader.into_records().zip(1..).map(move |(rec, row)| {
+        let rec = rec?;
+        Ok(layout.row(row, |i| rec.get(i).map(str::to_string)))
+    })))
+}
+
+fn jsonl_rows(path: &Path, cols: &ColumnMap, hive: &HashMap<String, String>) -> Result<Rows> {
+    use serde_json::{Map, Value};
+
+    let file = std::fs::File::open(path)?;
+    let (cols, hive) = (cols.clone(), hive.clone());
+    // Resolved against the first object's keys; later lines are looked up by name
+    let mut layout: Option<(Layout, Vec<String>)> = None;
+
+    let lines = std::io::BufReader::new(file).lines().zip(1..);
+    Ok(Box::new(lines.filter_map(move |(line, row)| {
+        let line = match line {
+            Ok(l) if l.trim().is_empty() => return None,
+            Ok(l) => l,
+            Err(e) => return Some(Err(e.into())),
+        };
+        let obj: Map<String, Value> = match serde_json::from_str(&line) {
+            Ok(o) => o,
+            Err(e) => return Some(Err(anyhow!("line {row}: {e}"))),
+        };
+        if layout.is_none() {
+            let keys: Vec<String> = obj.keys().cloned().collect();
+            match Layout::new(&cols, &keys, &hive) {
+                Ok(l) => layout = Some((l, keys)),
+                Err(e) => return Some(Err(e)),
+            }
+        }
+        let (layout, keys) = layout.as_ref()?;
+        Some(Ok(layout.row(row, |i| match obj.get(&keys[i])? {
+            Value::Null => None,
+            Value::String(s) => Some(s.clone()),
+            other => Some(other.to_string()),
+        })))
+    })))
+}
+
+fn parquet_rows(path: &Path, cols: &ColumnMap, hive: &HashMap<String, String>) -> Result<Rows> {
+    let file = std::fs::File::open(path)?;
+    let builder = ParquetRecordBatchReaderBuilder::try_new(file)?;
+    let header: Vec<String> = builder.schema().fields().iter().map(|f| f.name().clone()).collect();
+    let layout = Layout::new(cols, &header, hive)?;
+    let reader = builder.with_batch_size(8192).build()?;
+
+    let mut next_row = 1u64;
+    Ok(Box::new(reader.flat_map(move |batch| -> Vec<Result<RawRow>> {
+        let batch = match batch {
+            Ok(b) => b,
+            Err(e) => return vec![Err(e.into())],
+        };
+        let first = next_row;
+        next_row += batch.num_rows() as u64;
+        match decode_batch(&batch, &layout, first) {
+            Ok(rows) => rows.into_iter().map(Ok).collect(),
+            Err(e) => vec![Err(e)],
+        }
+    })))
+}
+
+/// Text for every column the layout reads (numbers, decimals and dictionaries
+/// are cast), except that a typed timestamp / date column is read as an instant.
+fn decode_batch(batch: &RecordBatch, layout: &Layout, first_row: u64) -> Result<Vec<RawRow>> {
+    let mut text = Vec::with_capacity(batch.num_columns());
+    for (i, col) in batch.columns().iter().enumerate() {
+        let is_time = matches!(col.data_type(), DataType::Timestamp(..) | DataType::Date32 | DataType::Date64);
+        text.push(if is_time || !layout.uses(i) { None } else { Some(arrow_cast::cast(col, &DataType::Utf8)?) });
+    }
+    let instants = match layout.timestamp {
+        Source::Column(i) if text[i].is_none() => {
+            // Same zone, so only the unit changes (values are UTC instants either way)
+            let tz = match batch.column(i).data_type() {
+                DataType::Timestamp(_, tz) => tz.clone(),
+                _ => None,
+            };
+            let nanos = arrow_cast::cast(batch.column(i), &DataType::Timestamp(TimeUnit::Nanosecond, tz))?;
+            Some(nanos.as_primitive::<TimestampNanosecondType>().clone())
+        }
+        _ => None,
+    };
+
+    let mut rows = Vec::with_capacity(batch.num_rows());
+    for r in 0..batch.num_rows() {
+        let mut row = layout.row(first_row + r as u64, |i| {
+            let col = text[i].as_ref()?.as_string::<i32>();
+            col.is_valid(r).then(|| col.value(r).to_string())
+        });
+        if let Some(ns) = &instants {
+            row.ts = ns
+                .is_valid(r)
+                .then(|| OffsetDateTime::from_unix_timestamp_nanos(ns.value(r) as i128).ok())
+                .flatten()
+                .map(TsValue::At);
+        }
+        rows.push(row);
+    }
+    Ok(rows)
+}
+
+/* ----------------- Validation ----------------- */
+
+struct ImportRow {
+    exchange: String,
+    token: String,
+    interval_minutes: Option<i32>,
+    rate: NormalizedFundingRate,
+}
+
+impl RawRow {
+    fn validate(&self, exchange: Option<&str>, now: i64) -> Result<ImportRow, &'static str> {
+        let exchange = exchange
+            .or(self.exchange.as_deref())
+            .map(str::trim)
+            .filter(|e| !e.is_empty())
+            .ok_or("missing exchange")?;
+        let market = self.market.as_deref().map(str::trim).ok_or("missing market")?;
+
+        let rate = self.rate.as_deref().map(str::trim).ok_or("missing rate")?;
+        let rate = Decimal::from_str(rate)
+            .or_else(|_| Decimal::from_scientific(rate))
+            .map_err(|_| "invalid rate")?;
+        if rate.abs() > MAX_ABS_RATE {
+            return Err("rate out of range (over 100% per interval)");
+        }
+
+        let ts = match self.ts.as_ref().ok_or("missing timestamp")? {
+            TsValue::At(t) => *t,
+            TsValue::Text(s) => parse_timestamp(s).ok_or("invalid timestamp")?,
+        };
+        let secs = ts.unix_timestamp();
+        if !(MIN_TS..=now + 86_400).contains(&secs) {
+            return Err("timestamp out of range");
+        }
+        let timestamp = DateTime::<Utc>::from_timestamp(secs, ts.nanosecond()).ok_or("invalid timestamp")?;
+
+        let interval_minutes = match self.interval.as_deref() {
+            None => None,
+            Some(s) => Some(parse_interval(s).ok_or("invalid interval")?),
+        };
+        let token = match self.token.as_deref().map(str::trim) {
+            Some(t) if !t.is_empty() => t.to_ascii_uppercase(),
+            _ => token_of(market).ok_or("missing token")?,
+        };
+
+        Ok(ImportRow {
+            exchange: exchange.to_string(),
+            token,
+            interval_minutes,
+            rate: NormalizedFundingRate { market_symbol: market.to_string(), rate, timestamp },
+        })
+    }
+}
+
+/// RFC 3339, `YYYY-MM-DD HH:MM:SS[.f]` / `YYYY-MM-DD` (UTC), or a unix epoch in
+/// s / ms / µs / ns (told apart by magnitude).
+fn parse_timestamp(s: &str) -> Option<OffsetDateTime> {
+    let s = s.trim();
+    if let Ok(t) = OffsetDateTime::parse(s, &Rfc3339) {
+        return Some(t);
+    }
+    let naive = s.replacen('T', " ", 1);
+    for fmt in [
+        format_description!("[year]-[month]-[day] [hour]:[minute]:[second].[subsecond]"),
+        format_description!("[year]-[month]-[day] [hour]:[minute]:[second]"),
+    ] {
+        if let Ok(t) = PrimitiveDateTime::parse(&naive, fmt) {
+            return Some(t.assume_utc());
+        }
+    }
+    if let Ok(d) = time::Date::parse(s, format_description!("[year]-[month]-[day]")) {
+        return Some(d.midnight().assume_utc());
+    }
+
+    let epoch: f64 = s.parse().ok().filter(|v: &f64| v.is_finite())?;
+    let nanos_per_unit = match epoch.abs() {
+        v if v < 1e11 => 1e9,
+        v if v < 1e14 => 1e6,
+        v if v < 1e17 => 1e3,
+        _ => 1.0,
+    };
+    let nanos = match s.parse::<i128>() {
+        Ok(n) => n * nanos_per_unit as i128,
+        Err(_) => (epoch * nanos_per_unit) as i128,
+    };
+    OffsetDateTime::from_unix_timestamp_nanos(nanos).ok()
+}
+
+/// `8h`, `30m` or bare minutes.
+fn parse_interval(s: &str) -> Option<i32> {
+    let s = s.trim().to_ascii_lowercase();
+    let minutes = match s.strip_suffix('h') {
+        Some(h) => h.trim().parse::<i32>().ok()?.checked_mul(60)?,
+        None => s.strip_suffix('m').unwrap_or(&s).trim().parse::<i32>().ok()?,
+    };
+    (1..=7 * 24 * 60).contains(&minutes).then_some(minutes)
+}
+
+/// Base asset of a market symbol: `BTC-USD-PERP` / `ETH_USDT` / `SOL/USD` / `BTCUSDT`.
+fn token_of(market: &str) -> Option<String> {
+    let upper = market.trim().to_ascii_uppercase();
+    let base = match upper.split(['-', '_', '/']).next() {
+        Some(b) if b.len() < upper.len() => b,
+        _ => ["USDT", "USDC", "USD", "PERP"]
+            .iter()
+            .find_map(|q| upper.strip_suffix(q).filter(|b| !b.is_empty()))
+            .unwrap_or(&upper),
+    };
+    (!base.is_empty()).then(|| base.to_string())
+}
+
+/* ----------------- Writing ----------------- */
+
+/// Exchange and market ids, looked up once and cached. Exchanges and markets
+/// the database doesn't know yet are created inactive, so importing history
+/// for a delisted market doesn't make the collectors start polling it.
+#[derive(Default)]
+struct MarketResolver {
+    exchanges: HashMap<String, i32>,
+    markets: HashMap<(i32, String), i32>,
+    created: usize,
+}
+
+impl MarketResolver {
+    async fn exchange_id(&mut self, pool: &PgPool, name: &str) -> Result<i32> {
+        let key = name.to_ascii_lowercase();
+        if let Some(id) = self.exchanges.get(&key) {
+            return Ok(*id);
+        }
+        let found = sqlx::query_scalar!("SELECT id FROM exchanges WHERE lower(name) = lower($1)", name)
+            .fetch_optional(pool)
+            .await?;
+        let id = match found {
+            Some(id) => id,
+            None => {
+                info!("import: adding exchange {} (inactive)", name);
+                sqlx::query_scalar!(
+                    r#"
+                    INSERT INTO exchanges (name, is_active) VALUES ($1, false)
+                    ON CONFLICT (name) DO UPDATE SET updated_at = NOW()
+                    RETURNING id
+                    "#,
+                    name
+                )
+                .fetch_one(pool)
+                .await?
+            }
+        };
+        self.exchanges.insert(key, id);
+        Ok(id)
+    }
+
+    /// Make sure every market in `rows` has an id; leaves existing markets'
+    /// `is_active` alone and only fills in a missing funding interval.
+    async fn resolve(&mut self, pool: &PgPool, exchange_id: i32, exchange: &str, rows: &[&ImportRow]) -> Result<()> {
+        let mut wanted: BTreeMap<&str, &ImportRow> = BTreeMap::new();
+        for r in rows {
+            let symbol = r.rate.market_symbol.as_str();
+            if !self.markets.contains_key(&(exchange_id, symbol.to_string())) {
+                wanted.entry(symbol).or_insert(r);
+            }
+        }
+        if wanted.is_empty() {
+            return Ok(());
+        }
+
+        let symbols: Vec<String> = wanted.keys().map(|s| s.to_string()).collect();
+        let existing = sqlx::query!(
+            r#"
+            SELECT market_symbol, is_active, funding_interval_minutes
+            FROM markets
+            WHERE exchange_id = $1 AND market_symbol = ANY($2)
+            "#,
+            exchange_id,
+            &symbols
+        )
+        .fetch_all(pool)
+        .await?;
+        let existing: HashMap<String, (bool, Option<i32>)> = existing
+            .into_iter()
+            .map(|m| (m.market_symbol, (m.is_active, m.funding_interval_minutes)))
+            .collect();
+
+        let mut upserts = Vec::new();
+        for (symbol, r) in &wanted {
+            let market = |is_active: bool| NormalizedMarket {
+                exchange: exchange.to_string(),
+                symbol: r.token.clone(),
+                market_symbol: symbol.to_string(),
+                base_currency: r.token.clone(),
+                quote_currency: symbol.split(['-', '_', '/']).nth(1).unwrap_or("USD").to_string(),
+                is_active,
+                funding_interval_minutes: r.interval_minutes,
+            };
+            match existing.get(*symbol) {
+                None => {
+                    upserts.push(market(false));
+                    self.created += 1;
+                }
+                Some((is_active, None)) if r.interval_minutes.is_some() => upserts.push(market(*is_active)),
+                Some((_, Some(stored))) if r.interval_minutes.is_some_and(|m| m != *stored) => warn!(
+                    "import: {} {} has a {}m funding interval, file says {}m; keeping {}m",
+                    exchange, symbol, stored, r.interval_minutes.unwrap_or_default(), stored
+                ),
+                Some(_) => {}
+            }
+        }
+        insert::upsert_markets(pool, exchange_id, &upserts).await?;
+
+        let ids = sqlx::query!(
+            "SELECT id, market_symbol FROM markets WHERE exchange_id = $1 AND market_symbol = ANY($2)",
+            exchange_id,
+            &symbols
+        )
+        .fetch_all(pool)
+        .await?;
+        for m in ids {
+            self.markets.insert((exchange_id, m.market_symbol), m.id);
+        }
+        Ok(())
+    }
+}
+
+async fn write_batch(pool: &PgPool, markets: &mut MarketResolver, batch: &[ImportRow]) -> Result<()> {
+    let mut by_exchange: BTreeMap<&str, Vec<&ImportRow>> = BTreeMap::new();
+    for r in batch {
+        by_exchange.entry(r.exchange.as_str()).or_default().push(r);
+    }
+
+    for (exchange, rows) in by_exchange {
+        let exchange_id = markets.exchange_id(pool, exchange).await?;
+        markets.resolve(pool, exchange_id, exchange, &rows).await?;
+
+        let with_ids: Vec<(i32, &NormalizedFundingRate)> = rows
+            .iter()
+            .filter_map(|r| {
+                let id = markets.markets.get(&(exchange_id, r.rate.market_symbol.clone()))?;
+                Some((*id, &r.rate))
+            })
+            .collect();
+        insert::insert_funding_rates(pool, exchange_id, &with_ids)
+            .await
+            .with_context(|| format!("inserting {} funding row(s) for {}", with_ids.len(), exchange))?;
+        info!("import: {} row(s) for {}", with_ids.len(), exchange);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::datetime;
+
+    #[test]
+    fn timestamps_in_text_formats() {
+        let t = datetime!(2025-03-01 08:00:00 UTC);
+        assert_eq!(parse_timestamp("2025-03-01T08:00:00Z"), Some(t));
+        assert_eq!(parse_timestamp("2025-03-01T10:00:00+02:00"), Some(t));
+        assert_eq!(parse_timestamp(" 2025-03-01 08:00:00 "), Some(t));
+        assert_eq!(parse_timestamp("2025-03-01T08:00:00"), Some(t));
+        assert_eq!(parse_timestamp("2025-03-01 08:00:00.250"), Some(datetime!(2025-03-01 08:00:00.25 UTC)));
+        assert_eq!(parse_timestamp("2025-03-01"), Some(datetime!(2025-03-01 00:00:00 UTC)));
+    }
+
+    #[test]
+    fn epoch_unit_from_magnitude() {
+        let t = datetime!(2025-03-01 08:00:00 UTC);
+        assert_eq!(parse_timestamp("1740816000"), Some(t));
+        assert_eq!(parse_timestamp("1740816000000"), Some(t));
+        assert_eq!(parse_timestamp("1740816000000000"), Some(t));
+        assert_eq!(parse_timestamp("1740816000000000000"), Some(t));
+        assert_eq!(parse_timestamp("1740816000.5"), Some(datetime!(2025-03-01 08:00:00.5 UTC)));
+    }
+
+    #[test]
+    fn unparseable_timestamps() {
+        assert_eq!(parse_timestamp(""), None);
+        assert_eq!(parse_timestamp("yesterday"), None);
+        assert_eq!(parse_timestamp("NaN"), None);
+    }
+
+    #[test]
+    fn intervals() {
+        assert_eq!(parse_interval("8h"), Some(480));
+        assert_eq!(parse_interval(" 1H "), Some(60));
+        assert_eq!(parse_interval("30m"), Some(30));
+        assert_eq!(parse_interval("480"), Some(480));
+        assert_eq!(parse_interval("0"), None);
+        assert_eq!(parse_interval("169h"), None);
+        assert_eq!(parse_interval("8d"), None);
+    }
+
+    #[test]
+    fn tokens_from_market_symbols() {
+        assert_eq!(token_of("BTC-USD-PERP").as_deref(), Some("BTC"));
+        assert_eq!(token_of("eth_usdt").as_deref(), Some("ETH"));
+        assert_eq!(token_of("SOL/USD").as_deref(), Some("SOL"));
+        assert_eq!(token_of("BTCUSDT").as_deref(), Some("BTC"));
+        assert_eq!(token_of("ETHUSDC").as_deref(), Some("ETH"));
+        assert_eq!(token_of("DOGE").as_deref(), Some("DOGE"));
+        assert_eq!(token_of("USDT").as_deref(), Some("USDT"));
+        assert_eq!(token_of("-USD"), None);
+    }
+}
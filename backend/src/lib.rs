@@ -1,4 +1,9 @@
 pub mod exchanges;
 pub mod db;
 pub mod data;
-pub mod utils;
\ No newline at end of file
+pub mod utils;
+pub mod analytics;
+pub mod alerts;
+pub mod store;
+pub mod import;
+pub mod backfill;
@@ -6,45 +6,114 @@ mod db;
 mod utils;
 mod exchanges;
 mod data;
-
-use axum::{extract::State, response::Json, routing::get, Router};
+mod analytics;
+mod alerts;
+mod api;
+mod store;
+
+use axum::{
+    extract::{Query, State},
+    http::header,
+    response::{IntoResponse, Json},
+    routing::{get, post},
+    Router,
+};
 use serde::{Deserialize, Serialize};
-use serde_json::Value as JsonValue;
 use sqlx::PgPool;
-use std::{collections::HashMap, net::SocketAddr};
+use std::{collections::HashMap, net::SocketAddr, sync::Arc};
 use tokio::net::TcpListener;
 use tower_http::cors::{Any, CorsLayer};
 use tracing::{error, info};
-use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+use time::OffsetDateTime;
 
 use mimalloc::MiMalloc;
 
 #[global_allocator]
 static GLOBAL: MiMalloc = MiMalloc;
 
-use crate::utils::scheduler;
+use crate::analytics::prices;
+use crate::analytics::rates::{self, RateBasis, DEFAULT_INTERVAL_MINUTES};
+use crate::analytics::rolling::{rolling_stats, RollingWindow};
+use crate::api::{fmt_ts, AppState};
+use crate::store::{Backend, FundingStore, MatrixEntry, PgStore};
+use crate::utils::{metrics, scheduler};
 
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 struct ExchangeData {
     market_symbol: String,
-    funding_rate: f64,
+    funding_rate: f64,             // in the requested `basis` (default 8h)
+    funding_interval_minutes: i32, // native interval the raw rate is paid on
+    funding_rate_hourly: f64,
+    funding_rate_8h: f64,
+    funding_rate_daily: f64,
+    funding_apr: f64,              // simple, non-compounded
     open_interest: f64,
     volume_24h: f64,
     funding_ts: Option<String>, // latest 8h bucket start (or legacy funding_ts)
     stats_ts:   Option<String>,
+    // venue's predicted rate for the upcoming interval, same `basis` / APR as above
+    predicted_funding_rate: Option<f64>,
+    predicted_funding_apr: Option<f64>,
+    next_funding_time: Option<String>,
+    seconds_to_next_funding: Option<i64>,
+    predicted_ts: Option<String>,
+    mark_price: Option<f64>,
+    index_price: Option<f64>,
+    last_price: Option<f64>,
+    premium_bps: Option<f64>, // (mark - index) / index
+    price_ts: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rolling: Option<Vec<RollingWindow>>, // only with ?rolling=true
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 struct TokenRow {
     token: String,
     exchanges: HashMap<String, ExchangeData>, 
+    price_divergence_bps: Option<f64>, // widest mark-price gap across venues
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 struct ApiResponse {
     last_updated: String,
+    basis: RateBasis,
     tokens: Vec<TokenRow>,
+    total: usize,               // tokens matching the filters, before limit/offset
+    next_offset: Option<usize>, // None on the last page
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+enum SortKey {
+    Token,
+    Rate,
+    Spread,
+    Oi,
+    Volume,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+enum SortDir {
+    Asc,
+    Desc,
+}
+
+/// `/api/funding-matrix` query string. Every field is optional; no params = full matrix.
+#[derive(Deserialize, Debug, Default)]
+struct MatrixQuery {
+    exchanges: Option<String>, // comma-separated, case-insensitive
+    search: Option<String>,    // substring of token, case-insensitive
+    prefix: Option<String>,    // token prefix, case-insensitive
+    min_oi: Option<f64>,       // per exchange entry, USD
+    min_volume: Option<f64>,   // per exchange entry, USD
+    sort: Option<SortKey>,     // default: token
+    dir: Option<SortDir>,      // default: asc for token, desc otherwise
+    limit: Option<usize>,
+    offset: Option<usize>,
+    basis: Option<RateBasis>,  // hourly | 8h | daily | apr (default 8h)
+    rolling: Option<bool>,     // attach 24h/3d/7d/30d rolling stats per entry
 }
 
 #[derive(Serialize, Debug)]
@@ -55,106 +124,213 @@ struct HealthResponse {
 }
 
 
-fn fmt_ts(ts: OffsetDateTime) -> String {
-    ts.format(&Rfc3339).unwrap_or_else(|_| ts.to_string())
+// ---------- Matrix filtering / sorting ----------
+
+fn filter_tokens(tokens: Vec<TokenRow>, q: &MatrixQuery) -> Vec<TokenRow> {
+    // `?exchanges=` or `?exchanges=,` doesn't filter
+    let wanted: Option<Vec<String>> = q
+        .exchanges
+        .as_ref()
+        .map(|s| {
+            s.split(',')
+                .map(|x| x.trim().to_ascii_lowercase())
+                .filter(|x| !x.is_empty())
+                .collect::<Vec<_>>()
+        })
+        .filter(|w| !w.is_empty());
+    let search = q.search.as_ref().map(|s| s.trim().to_ascii_uppercase());
+    let prefix = q.prefix.as_ref().map(|s| s.trim().to_ascii_uppercase());
+
+    tokens
+        .into_iter()
+        .filter(|t| {
+            let sym = t.token.to_ascii_uppercase();
+            search.as_ref().is_none_or(|s| sym.contains(s.as_str()))
+                && prefix.as_ref().is_none_or(|p| sym.starts_with(p.as_str()))
+        })
+        .filter_map(|mut t| {
+            t.exchanges.retain(|name, ex| {
+                wanted
+                    .as_ref()
+                    .is_none_or(|w| w.iter().any(|x| *x == name.to_ascii_lowercase()))
+                    && q.min_oi.is_none_or(|m| ex.open_interest >= m)
+                    && q.min_volume.is_none_or(|m| ex.volume_24h >= m)
+            });
+            (!t.exchanges.is_empty()).then_some(t)
+        })
+        .collect()
 }
 
-fn f64_field(v: &JsonValue, k: &str) -> f64 {
-    v.get(k).and_then(|x| x.as_f64()).unwrap_or(0.0)
+/// Sort value for a token; `None` (e.g. spread with a single venue) always sorts last.
+fn sort_value(t: &TokenRow, key: SortKey) -> Option<f64> {
+    let rates = t.exchanges.values().map(|e| e.funding_rate);
+    match key {
+        SortKey::Token => None,
+        SortKey::Rate => rates.reduce(f64::max),
+        SortKey::Spread => {
+            if t.exchanges.len() < 2 {
+                return None;
+            }
+            let (lo, hi) = rates.fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), r| {
+                (lo.min(r), hi.max(r))
+            });
+            Some(hi - lo)
+        }
+        SortKey::Oi => Some(t.exchanges.values().map(|e| e.open_interest).sum()),
+        SortKey::Volume => Some(t.exchanges.values().map(|e| e.volume_24h).sum()),
+    }
 }
 
-fn str_field(v: &JsonValue, k: &str) -> Option<String> {
-    v.get(k).and_then(|x| x.as_str()).map(|s| s.to_string())
+fn sort_tokens(tokens: &mut [TokenRow], key: SortKey, dir: SortDir) {
+    if key == SortKey::Token {
+        tokens.sort_by(|a, b| a.token.cmp(&b.token));
+        if dir == SortDir::Desc {
+            tokens.reverse();
+        }
+        return;
+    }
+
+    tokens.sort_by(|a, b| {
+        match (sort_value(a, key), sort_value(b, key)) {
+            (Some(x), Some(y)) => {
+                let ord = x.total_cmp(&y);
+                if dir == SortDir::Desc { ord.reverse() } else { ord }
+            }
+            (Some(_), None) => std::cmp::Ordering::Less,
+            (None, Some(_)) => std::cmp::Ordering::Greater,
+            (None, None) => std::cmp::Ordering::Equal,
+        }
+        .then_with(|| a.token.cmp(&b.token))
+    });
+}
+
+/// Matrix timestamps keep the `+00:00` offset they had when the rows came
+/// straight out of Postgres JSON.
+fn matrix_ts(ts: OffsetDateTime) -> String {
+    let s = fmt_ts(ts);
+    match s.strip_suffix('Z') {
+        Some(base) => format!("{base}+00:00"),
+        None => s,
+    }
 }
 
 // ---------- Routes ----------
 
-async fn get_funding_matrix(State(pool): State<PgPool>) -> Json<ApiResponse> {
-    let rows = match sqlx::query!(
-        r#"
-        SELECT symbol, per_exchange, last_update
-        FROM funding_matrix_view
-        "#
-    )
-    .fetch_all(&pool)
-    .await
-    {
+async fn get_funding_matrix(
+    State(state): State<AppState>,
+    Query(q): Query<MatrixQuery>,
+) -> Json<ApiResponse> {
+    let basis = q.basis.unwrap_or_default();
+    let rows = match state.store.latest_matrix().await {
         Ok(rs) => rs,
         Err(e) => {
-            error!("query funding_matrix_view failed: {e:?}");
+            error!("latest matrix query failed: {e:?}");
             return Json(ApiResponse {
                 last_updated: fmt_ts(OffsetDateTime::now_utc()),
+                basis,
                 tokens: vec![],
+                total: 0,
+                next_offset: None,
             });
         }
     };
 
-    let mut tokens: Vec<TokenRow> = Vec::with_capacity(rows.len());
-    let mut max_ts: Option<OffsetDateTime> = None;
-
-    for r in rows {
-        let symbol = r.symbol.unwrap_or_default();
-        let mut exchanges: HashMap<String, ExchangeData> = HashMap::new();
-
-        if let Some(JsonValue::Object(obj)) = r.per_exchange {
-            for (ex_name, v) in obj {
-
-                let market_symbol = str_field(&v, "market_symbol").unwrap_or_default();
-                let funding_rate = v.get("funding_rate_8h")
-                    .and_then(|x| x.as_f64())
-                    .unwrap_or_else(|| f64_field(&v, "funding_rate")); // fallback
-
-                let open_interest = f64_field(&v, "open_interest");
-                let volume_24h    = f64_field(&v, "volume_24h");
-
-                let funding_ts = str_field(&v, "funding_bucket")
-                    .or_else(|| str_field(&v, "funding_ts")); // fallback
-                let stats_ts   = str_field(&v, "stats_ts");
-
-                exchanges.insert(
-                    ex_name,
-                    ExchangeData {
-                        market_symbol,
-                        funding_rate,
-                        open_interest,
-                        volume_24h,
-                        funding_ts,
-                        stats_ts,
-                    },
-                );
+    let max_ts = rows.iter().map(MatrixEntry::last_update).max();
+    let now = OffsetDateTime::now_utc();
+
+    // Entries come ordered by token, so one pass groups them
+    let mut tokens: Vec<TokenRow> = Vec::new();
+    for e in rows {
+        let interval = e.funding_interval_minutes.unwrap_or(DEFAULT_INTERVAL_MINUTES);
+        // bucket average of the native per-interval rate
+        let native_rate = e.funding_rate_8h.unwrap_or(0.0);
+        let bases = rates::all_bases(native_rate, interval);
+        let data = ExchangeData {
+            market_symbol: e.market_symbol,
+            funding_rate: rates::convert(native_rate, interval, basis),
+            funding_interval_minutes: interval,
+            funding_rate_hourly: bases.hourly,
+            funding_rate_8h: bases.rate_8h,
+            funding_rate_daily: bases.daily,
+            funding_apr: bases.apr,
+            open_interest: e.open_interest.unwrap_or(0.0),
+            volume_24h: e.volume_24h.unwrap_or(0.0),
+            funding_ts: e.funding_bucket.map(matrix_ts),
+            stats_ts: e.stats_ts.map(matrix_ts),
+            predicted_funding_rate: e.predicted_rate.map(|p| rates::convert(p, interval, basis)),
+            predicted_funding_apr: e.predicted_rate.map(|p| rates::convert(p, interval, RateBasis::Apr)),
+            next_funding_time: e.next_funding_time.map(matrix_ts),
+            seconds_to_next_funding: e.next_funding_time.map(|t| (t - now).whole_seconds().max(0)),
+            predicted_ts: e.predicted_ts.map(matrix_ts),
+            mark_price: e.mark_price,
+            index_price: e.index_price,
+            last_price: e.last_price,
+            premium_bps: prices::premium_bps(e.mark_price, e.index_price),
+            price_ts: e.price_ts.map(matrix_ts),
+            rolling: None,
+        };
+        match tokens.last_mut() {
+            Some(t) if t.token == e.token => {
+                t.exchanges.insert(e.exchange, data);
             }
+            _ => tokens.push(TokenRow {
+                token: e.token,
+                exchanges: HashMap::from([(e.exchange, data)]),
+                price_divergence_bps: None,
+            }),
         }
+    }
+    let last_updated = fmt_ts(max_ts.unwrap_or_else(OffsetDateTime::now_utc));
 
-        let lu = r.last_update.unwrap_or_else(OffsetDateTime::now_utc);
-        if max_ts.map(|m| lu > m).unwrap_or(true) {
-            max_ts = Some(lu);
+    let mut tokens = filter_tokens(tokens, &q);
+    // Across the exchanges left after filtering
+    for t in tokens.iter_mut() {
+        t.price_divergence_bps = prices::divergence_bps(t.exchanges.values().filter_map(|e| e.mark_price));
+    }
+    let key = q.sort.unwrap_or(SortKey::Token);
+    let dir = q.dir.unwrap_or(if key == SortKey::Token { SortDir::Asc } else { SortDir::Desc });
+    sort_tokens(&mut tokens, key, dir);
+
+    let total = tokens.len();
+    let offset = q.offset.unwrap_or(0).min(total);
+    let end = q.limit.map_or(total, |l| offset.saturating_add(l).min(total));
+    let mut tokens: Vec<TokenRow> = tokens.drain(offset..end).collect();
+    let next_offset = (end < total).then_some(end);
+
+    // Rolling windows come from the Postgres rollups; other stores leave them out
+    if q.rolling == Some(true) && !tokens.is_empty() && let Some(pool) = &state.pool {
+        let page: Vec<String> = tokens.iter().map(|t| t.token.clone()).collect();
+        match rolling_stats(pool, Some(&page), basis).await {
+            Ok(stats) => {
+                let mut by_market: HashMap<(String, String), Vec<RollingWindow>> = stats
+                    .into_iter()
+                    .map(|m| ((m.exchange, m.market_symbol), m.windows))
+                    .collect();
+                for t in tokens.iter_mut() {
+                    for (ex_name, ex) in t.exchanges.iter_mut() {
+                        let key = (ex_name.clone(), ex.market_symbol.clone());
+                        ex.rolling = Some(by_market.remove(&key).unwrap_or_default());
+                    }
+                }
+            }
+            Err(e) => error!("rolling stats failed: {e:?}"),
         }
-
-        tokens.push(TokenRow { token: symbol, exchanges });
     }
 
-    let last_updated = fmt_ts(max_ts.unwrap_or_else(OffsetDateTime::now_utc));
-    info!("funding-matrix: {} tokens, last_updated={}", tokens.len(), last_updated);
+    info!("funding-matrix: {}/{} tokens, last_updated={}", tokens.len(), total, last_updated);
 
-    Json(ApiResponse { last_updated, tokens })
+    Json(ApiResponse { last_updated, basis, tokens, total, next_offset })
 }
 
-async fn health(State(pool): State<PgPool>) -> Json<HealthResponse> {
-    let res = sqlx::query!(
-        r#"
-        SELECT COUNT(*)::BIGINT as cnt, MAX(last_update) as last_update
-        FROM funding_matrix_view
-        "#
-    )
-    .fetch_one(&pool)
-    .await;
-
-    match res {
-        Ok(r) => {
-            let last = r.last_update.map(fmt_ts);
+async fn health(State(state): State<AppState>) -> Json<HealthResponse> {
+    match state.store.latest_matrix().await {
+        Ok(rows) => {
+            let last = rows.iter().map(MatrixEntry::last_update).max().map(fmt_ts);
+            let mut tokens: Vec<&str> = rows.iter().map(|r| r.token.as_str()).collect();
+            tokens.dedup();
             Json(HealthResponse {
                 ok: true,
-                tokens: r.cnt.unwrap_or(0) as usize,
+                tokens: tokens.len(),
                 last_updated: last,
             })
         }
@@ -169,6 +345,18 @@ async fn health(State(pool): State<PgPool>) -> Json<HealthResponse> {
     }
 }
 
+async fn metrics_endpoint(State(state): State<AppState>) -> impl IntoResponse {
+    if let Some(pool) = &state.pool
+        && let Err(e) = metrics::refresh_data_lag(pool).await
+    {
+        error!("refresh data lag failed: {e:?}");
+    }
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        metrics::render(),
+    )
+}
+
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
@@ -179,12 +367,35 @@ async fn main() -> anyhow::Result<()> {
         .compact()
         .init();
 
-    let pool = db::migrations::create_pool().await;
+    // `--no-migrate`: only verify the schema; apply migrations with `sync migrate`
+    // `--on-conflict POLICY`: how scheduled syncs treat rows that already exist
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    let mode = db::migrations::MigrationMode::from_args(&mut args);
+    db::insert::init_conflict_policy(db::insert::ConflictPolicy::from_args(&mut args)?)?;
+    if let Some(other) = args.first() {
+        anyhow::bail!("unknown argument: {other} (supported: --no-migrate, --on-conflict POLICY)");
+    }
+
+    // Postgres runs everything; `sqlite://...` and `memory` serve the portable routes only
+    let db_url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+    let (store, pool): (Arc<dyn FundingStore>, Option<PgPool>) = match Backend::from_url(&db_url) {
+        Backend::Postgres => {
+            let pool = db::migrations::create_pool_with_mode(mode).await;
+            (Arc::new(PgStore::new(pool.clone())), Some(pool))
+        }
+        _ => (store::open_embedded(&db_url).await?, None),
+    };
+    info!("storage: {}", store.kind());
 
     {
         let pool_clone = pool.clone();
+        let store_clone = store.clone();
         tokio::spawn(async move {
-            if let Err(e) = scheduler::start_scheduler(pool_clone).await {
+            let res = match pool_clone {
+                Some(pool) => scheduler::start_scheduler(pool).await,
+                None => scheduler::start_embedded_scheduler(store_clone).await,
+            };
+            if let Err(e) = res {
                 eprintln!("scheduler failed: {:?}", e);
             }
         });
@@ -195,12 +406,37 @@ async fn main() -> anyhow::Result<()> {
         .allow_headers(Any)
         .allow_methods(Any);
 
-    let app = Router::new()
+    let portable = Router::new()
         .route("/api/funding-matrix", get(get_funding_matrix))
         .route("/api/health", get(health))
+        .route("/api/carry", get(api::carry::get_carry))
+        .route("/metrics", get(metrics_endpoint))
+        .with_state(AppState { store, pool: pool.clone() });
+
+    let Some(pool) = pool else {
+        return serve(portable.layer(cors)).await;
+    };
+
+    let app = Router::new()
+        .route("/api/tokens/{symbol}", get(api::tokens::get_token_detail))
+        .route("/api/backtest", post(api::backtest::post_backtest))
+        .route("/api/events", get(api::events::get_events))
+        .route("/api/export", get(api::export::get_export))
+        .route("/api/alerts/rules", get(api::alerts::list_rules).post(api::alerts::create_rule))
+        .route(
+            "/api/alerts/rules/{id}",
+            get(api::alerts::get_rule)
+                .put(api::alerts::update_rule)
+                .delete(api::alerts::delete_rule),
+        )
         .with_state(pool)
+        .merge(portable)
         .layer(cors);
 
+    serve(app).await
+}
+
+async fn serve(app: Router) -> anyhow::Result<()> {
     let addr: SocketAddr = "0.0.0.0:8080".parse().unwrap();
     info!("Server running at http://{}", addr);
 
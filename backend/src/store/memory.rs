@@ -0,0 +1,343 @@
+// backend/src/store/memory.rs
+// Everything in process memory (`DATABASE_URL=memory`): for tests, demos and
+// throwaway runs. Same semantics as the SQLite store; nothing survives a restart.
+use anyhow::Result;
+use async_trait::async_trait;
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use time::OffsetDateTime;
+
+use super::{bucket_start, ExchangeRef, FundingStore, MarketRef, MatrixEntry, DEFAULT_EXCHANGES, MATRIX_BUCKET_SECS};
+use crate::analytics::rates::DEFAULT_INTERVAL_MINUTES;
+use crate::db::insert::{conflict_policy, ConflictPolicy};
+use crate::exchanges::shared::types::{NormalizedFundingRate, NormalizedMarket, NormalizedMarketStats};
+use crate::utils::metrics;
+
+struct Exchange {
+    id: i32,
+    name: String,
+    funding_interval_minutes: Option<i32>,
+    is_active: bool,
+}
+
+struct Market {
+    id: i32,
+    exchange_id: i32,
+    token: String,
+    market_symbol: String,
+    is_active: bool,
+    funding_interval_minutes: Option<i32>,
+}
+
+#[derive(Clone, PartialEq)]
+struct Stats {
+    open_interest: Option<Decimal>,
+    volume_24h: Option<Decimal>,
+}
+
+struct Prices {
+    mark: Option<Decimal>,
+    index: Option<Decimal>,
+    last: Option<Decimal>,
+}
+
+/// Rows keyed by market id, then unix seconds.
+type Series<T> = HashMap<i32, BTreeMap<i64, T>>;
+
+#[derive(Default)]
+struct Inner {
+    exchanges: Vec<Exchange>,
+    markets: Vec<Market>,
+    funding: Series<Decimal>,
+    stats: Series<Stats>,
+    prices: Series<Prices>,
+}
+
+impl Inner {
+    fn exchange(&self, id: i32) -> Option<&Exchange> {
+        self.exchanges.iter().find(|e| e.id == id)
+    }
+
+    /// Lowercase exchange name, for metric labels.
+    fn exchange_label(&self, id: i32) -> String {
+        self.exchange(id).map(|e| e.name.to_ascii_lowercase()).unwrap_or_default()
+    }
+
+    fn market_ref(&self, m: &Market) -> MarketRef {
+        let exchange = self.exchange(m.exchange_id);
+        MarketRef {
+            id: m.id,
+            exchange: exchange.map(|e| e.name.clone()).unwrap_or_default(),
+            market_symbol: m.market_symbol.clone(),
+            token: m.token.clone(),
+            funding_interval_minutes: m
+                .funding_interval_minutes
+                .or_else(|| exchange.and_then(|e| e.funding_interval_minutes)),
+        }
+    }
+}
+
+/// Write `value` at `key` under the active conflict policy; true if anything was written.
+fn put<T: PartialEq>(series: &mut BTreeMap<i64, T>, key: i64, value: T) -> bool {
+    match (series.get_mut(&key), conflict_policy()) {
+        (None, _) => {
+            series.insert(key, value);
+            true
+        }
+        (Some(_), ConflictPolicy::Ignore) => false,
+        (Some(old), ConflictPolicy::OverwriteIfDifferent) if *old == value => false,
+        (Some(old), _) => {
+            *old = value;
+            true
+        }
+    }
+}
+
+fn ts(secs: i64) -> Result<OffsetDateTime> {
+    Ok(OffsetDateTime::from_unix_timestamp(secs)?)
+}
+
+fn f64_of(d: &Option<Decimal>) -> Option<f64> {
+    d.as_ref().and_then(ToPrimitive::to_f64)
+}
+
+fn mean<'a>(values: impl Iterator<Item = &'a Decimal>) -> Option<f64> {
+    let (sum, n) = values.fold((0.0, 0u32), |(s, n), v| (s + v.to_f64().unwrap_or(0.0), n + 1));
+    (n > 0).then(|| sum / f64::from(n))
+}
+
+pub struct MemoryStore {
+    inner: RwLock<Inner>,
+}
+
+impl Default for MemoryStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MemoryStore {
+    /// An empty store holding just the supported exchanges.
+    pub fn new() -> Self {
+        let exchanges = DEFAULT_EXCHANGES
+            .iter()
+            .zip(1..)
+            .map(|(name, id)| Exchange {
+                id,
+                name: name.to_string(),
+                funding_interval_minutes: Some(DEFAULT_INTERVAL_MINUTES),
+                is_active: true,
+            })
+            .collect();
+        Self { inner: RwLock::new(Inner { exchanges, ..Default::default() }) }
+    }
+
+    // A panicked writer leaves the maps consistent (no multi-step invariants), so keep going
+    fn read(&self) -> RwLockReadGuard<'_, Inner> {
+        self.inner.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, Inner> {
+        self.inner.write().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[async_trait]
+impl FundingStore for MemoryStore {
+    fn kind(&self) -> &'static str {
+        "memory"
+    }
+
+    async fn active_exchanges(&self) -> Result<Vec<ExchangeRef>> {
+        let inner = self.read();
+        let mut out: Vec<ExchangeRef> = inner
+            .exchanges
+            .iter()
+            .filter(|e| e.is_active)
+            .map(|e| ExchangeRef {
+                id: e.id,
+                name: e.name.clone(),
+                funding_interval_minutes: e.funding_interval_minutes,
+            })
+            .collect();
+        out.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(out)
+    }
+
+    async fn set_funding_interval(&self, exchange_id: i32, minutes: i32) -> Result<bool> {
+        let mut inner = self.write();
+        let Some(e) = inner.exchanges.iter_mut().find(|e| e.id == exchange_id) else {
+            return Ok(false);
+        };
+        let changed = e.funding_interval_minutes != Some(minutes);
+        e.funding_interval_minutes = Some(minutes);
+        Ok(changed)
+    }
+
+    async fn upsert_markets(&self, exchange_id: i32, markets: &[NormalizedMarket]) -> Result<()> {
+        let mut inner = self.write();
+        for nm in markets {
+            let existing = inner
+                .markets
+                .iter_mut()
+                .find(|m| m.exchange_id == exchange_id && m.market_symbol == nm.market_symbol);
+            match existing {
+                Some(m) => {
+                    m.is_active = nm.is_active;
+                    m.funding_interval_minutes = nm.funding_interval_minutes.or(m.funding_interval_minutes);
+                }
+                None => {
+                    let id = inner.markets.len() as i32 + 1;
+                    inner.markets.push(Market {
+                        id,
+                        exchange_id,
+                        token: nm.symbol.clone(),
+                        market_symbol: nm.market_symbol.clone(),
+                        is_active: nm.is_active,
+                        funding_interval_minutes: nm.funding_interval_minutes,
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn active_markets(&self, exchange_id: i32) -> Result<Vec<MarketRef>> {
+        let inner = self.read();
+        let mut out: Vec<MarketRef> = inner
+            .markets
+            .iter()
+            .filter(|m| m.exchange_id == exchange_id && m.is_active)
+            .map(|m| inner.market_ref(m))
+            .collect();
+        out.sort_by(|a, b| a.market_symbol.cmp(&b.market_symbol));
+        Ok(out)
+    }
+
+    async fn find_market(&self, token: &str, exchange: &str) -> Result<Option<MarketRef>> {
+        let inner = self.read();
+        let token = token.to_ascii_uppercase();
+        let found = inner
+            .markets
+            .iter()
+            .filter(|m| {
+                m.token == token
+                    && inner
+                        .exchange(m.exchange_id)
+                        .is_some_and(|e| e.name.eq_ignore_ascii_case(exchange))
+            })
+            .min_by_key(|m| (!m.is_active, m.id));
+        Ok(found.map(|m| inner.market_ref(m)))
+    }
+
+    async fn insert_funding_rates(&self, exchange_id: i32, rows: &[(i32, &NormalizedFundingRate)]) -> Result<()> {
+        let mut inner = self.write();
+        let mut written = 0u64;
+        for (market_id, r) in rows {
+            let series = inner.funding.entry(*market_id).or_default();
+            written += put(series, r.timestamp.timestamp(), r.rate.normalize()) as u64;
+        }
+        let exchange = inner.exchange_label(exchange_id);
+        drop(inner);
+        metrics::inc_rows_inserted("funding_rates", &exchange, written);
+        Ok(())
+    }
+
+    async fn insert_market_stats(&self, exchange_id: i32, rows: &[(i32, &NormalizedMarketStats)]) -> Result<()> {
+        let mut inner = self.write();
+        let (mut stats_n, mut prices_n) = (0u64, 0u64);
+        for (market_id, s) in rows {
+            let key = s.timestamp.timestamp();
+            let stats = Stats { open_interest: s.open_interest, volume_24h: s.volume_24h };
+            stats_n += put(inner.stats.entry(*market_id).or_default(), key, stats) as u64;
+
+            if s.mark_price.is_some() || s.index_price.is_some() || s.last_price.is_some() {
+                let prices = inner.prices.entry(*market_id).or_default();
+                if let std::collections::btree_map::Entry::Vacant(v) = prices.entry(key) {
+                    v.insert(Prices { mark: s.mark_price, index: s.index_price, last: s.last_price });
+                    prices_n += 1;
+                }
+            }
+        }
+        let exchange = inner.exchange_label(exchange_id);
+        drop(inner);
+        metrics::inc_rows_inserted("market_stats", &exchange, stats_n);
+        metrics::inc_rows_inserted("price_snapshots", &exchange, prices_n);
+        Ok(())
+    }
+
+    async fn last_funding_ts(&self, market_id: i32) -> Result<Option<OffsetDateTime>> {
+        let inner = self.read();
+        let last = inner
+            .funding
+            .get(&market_id)
+            .and_then(|s| s.last_key_value())
+            .map(|(k, _)| *k);
+        last.map(ts).transpose()
+    }
+
+    async fn latest_matrix(&self) -> Result<Vec<MatrixEntry>> {
+        let inner = self.read();
+        let mut out = Vec::new();
+        for m in inner.markets.iter().filter(|m| m.is_active) {
+            let r = inner.market_ref(m);
+            let mut entry = MatrixEntry {
+                token: m.token.clone(),
+                exchange: r.exchange,
+                market_symbol: r.market_symbol,
+                funding_interval_minutes: r.funding_interval_minutes,
+                ..Default::default()
+            };
+
+            if let Some(series) = inner.funding.get(&m.id)
+                && let Some((last, _)) = series.last_key_value()
+            {
+                let bucket = bucket_start(*last, MATRIX_BUCKET_SECS);
+                entry.funding_rate_8h = mean(series.range(bucket..bucket + MATRIX_BUCKET_SECS).map(|(_, v)| v));
+                entry.funding_bucket = Some(ts(bucket)?);
+            }
+            if let Some((k, s)) = inner.stats.get(&m.id).and_then(|s| s.last_key_value()) {
+                entry.open_interest = f64_of(&s.open_interest);
+                entry.volume_24h = f64_of(&s.volume_24h);
+                entry.stats_ts = Some(ts(*k)?);
+            }
+            if let Some((k, p)) = inner.prices.get(&m.id).and_then(|s| s.last_key_value()) {
+                entry.mark_price = f64_of(&p.mark);
+                entry.index_price = f64_of(&p.index);
+                entry.last_price = f64_of(&p.last);
+                entry.price_ts = Some(ts(*k)?);
+            }
+            out.push(entry);
+        }
+        out.sort_by(|a, b| a.token.cmp(&b.token).then_with(|| a.exchange.cmp(&b.exchange)));
+        Ok(out)
+    }
+
+    async fn funding_history(
+        &self,
+        market_id: i32,
+        bucket_minutes: i32,
+        from: OffsetDateTime,
+        to: OffsetDateTime,
+    ) -> Result<Vec<(OffsetDateTime, f64)>> {
+        let inner = self.read();
+        let Some(series) = inner.funding.get(&market_id) else {
+            return Ok(Vec::new());
+        };
+        let width = i64::from(bucket_minutes.max(1)) * 60;
+        let to = to.unix_timestamp();
+
+        let mut buckets: BTreeMap<i64, Vec<&Decimal>> = BTreeMap::new();
+        for (k, v) in series.range(from.unix_timestamp()..to) {
+            buckets.entry(bucket_start(*k, width)).or_default().push(v);
+        }
+        buckets
+            .into_iter()
+            .filter(|(b, _)| b + width <= to)
+            .filter_map(|(b, vs)| mean(vs.into_iter()).map(|r| (b, r)))
+            .map(|(b, r)| Ok((ts(b)?, r)))
+            .collect()
+    }
+}
@@ -0,0 +1,303 @@
+// backend/src/store/mod.rs
+// What the collectors and the portable API routes need from storage.
+// Postgres is the full backend (rollups, partitions, retention, alerts,
+// analytics); SQLite and the in-memory store cover ingestion, the matrix and
+// funding history, so a single user can run the service without a server.
+pub mod memory;
+pub mod postgres;
+pub mod sqlite;
+
+use std::sync::Arc;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use time::OffsetDateTime;
+
+use crate::exchanges::shared::types::{NormalizedFundingRate, NormalizedMarket, NormalizedMarketStats};
+
+pub use memory::MemoryStore;
+pub use postgres::PgStore;
+pub use sqlite::SqliteStore;
+
+/// Bucket width of the matrix's funding column.
+pub const MATRIX_BUCKET_SECS: i64 = 8 * 3600;
+
+#[derive(Debug, Clone)]
+pub struct ExchangeRef {
+    pub id: i32,
+    pub name: String,
+    pub funding_interval_minutes: Option<i32>,
+}
+
+#[derive(Debug, Clone)]
+pub struct MarketRef {
+    pub id: i32,
+    pub exchange: String,
+    pub market_symbol: String,
+    /// Base token symbol (`tokens.symbol`).
+    pub token: String,
+    /// The market's own interval, else its exchange's.
+    pub funding_interval_minutes: Option<i32>,
+}
+
+/// One (token, exchange) cell of the funding matrix for an active market.
+#[derive(Debug, Clone, Default)]
+pub struct MatrixEntry {
+    pub token: String,
+    pub exchange: String,
+    pub market_symbol: String,
+    pub funding_interval_minutes: Option<i32>,
+    /// Average native rate over the 8h bucket holding the newest funding row.
+    pub funding_rate_8h: Option<f64>,
+    pub funding_bucket: Option<OffsetDateTime>,
+    pub open_interest: Option<f64>,
+    pub volume_24h: Option<f64>,
+    pub stats_ts: Option<OffsetDateTime>,
+    pub predicted_rate: Option<f64>,
+    pub next_funding_time: Option<OffsetDateTime>,
+    pub predicted_ts: Option<OffsetDateTime>,
+    pub mark_price: Option<f64>,
+    pub index_price: Option<f64>,
+    pub last_price: Option<f64>,
+    pub price_ts: Option<OffsetDateTime>,
+}
+
+impl MatrixEntry {
+    /// Newest funding bucket or stats row, whichever is later.
+    pub fn last_update(&self) -> OffsetDateTime {
+        let epoch = OffsetDateTime::UNIX_EPOCH;
+        self.funding_bucket.unwrap_or(epoch).max(self.stats_ts.unwrap_or(epoch))
+    }
+}
+
+#[async_trait]
+pub trait FundingStore: Send + Sync {
+    /// Short name for logs (`postgres`, `sqlite`, `memory`).
+    fn kind(&self) -> &'static str;
+
+    async fn active_exchanges(&self) -> Result<Vec<ExchangeRef>>;
+    /// Force the exchange's funding interval; true if it changed.
+    async fn set_funding_interval(&self, exchange_id: i32, minutes: i32) -> Result<bool>;
+
+    async fn upsert_markets(&self, exchange_id: i32, markets: &[NormalizedMarket]) -> Result<()>;
+    async fn active_markets(&self, exchange_id: i32) -> Result<Vec<MarketRef>>;
+    /// Market for `token` on `exchange` (both case-insensitive), active ones first.
+    async fn find_market(&self, token: &str, exchange: &str) -> Result<Option<MarketRef>>;
+
+    async fn insert_funding_rates(&self, exchange_id: i32, rows: &[(i32, &NormalizedFundingRate)]) -> Result<()>;
+    async fn insert_market_stats(&self, exchange_id: i32, rows: &[(i32, &NormalizedMarketStats)]) -> Result<()>;
+    async fn last_funding_ts(&self, market_id: i32) -> Result<Option<OffsetDateTime>>;
+
+    async fn latest_matrix(&self) -> Result<Vec<MatrixEntry>>;
+    /// Average rate per `bucket_minutes` bucket (epoch-aligned) over [`from`, `to`),
+    /// leaving out a bucket still open at `to`.
+    async fn funding_history(
+        &self,
+        market_id: i32,
+        bucket_minutes: i32,
+        from: OffsetDateTime,
+        to: OffsetDateTime,
+    ) -> Result<Vec<(OffsetDateTime, f64)>>;
+}
+
+/// Which backend `DATABASE_URL` points at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    Postgres,
+    /// `sqlite://path.db` (created if missing) or `sqlite::memory:`
+    Sqlite,
+    /// `memory` / `memory:` — nothing survives a restart
+    Memory,
+}
+
+impl Backend {
+    pub fn from_url(url: &str) -> Self {
+        if url.starts_with("sqlite:") {
+            Backend::Sqlite
+        } else if url == "memory" || url.starts_with("memory:") {
+            Backend::Memory
+        } else {
+            Backend::Postgres
+        }
+    }
+}
+
+/// Open the SQLite or in-memory store; Postgres goes through
+/// `db::migrations` so its pool can back the Postgres-only routes too.
+pub async fn open_embedded(url: &str) -> Result<Arc<dyn FundingStore>> {
+    match Backend::from_url(url) {
+        Backend::Sqlite => Ok(Arc::new(SqliteStore::open(url).await?)),
+        Backend::Memory => Ok(Arc::new(MemoryStore::new())),
+        Backend::Postgres => anyhow::bail!("{url} is a Postgres URL; use db::migrations"),
+    }
+}
+
+/// Exchanges the collectors know how to talk to, seeded into embedded stores.
+pub(crate) const DEFAULT_EXCHANGES: &[&str] = &["Extended", "Paradex"];
+
+/// Start of the epoch-aligned bucket of `width_secs` holding `ts`.
+pub(crate) fn bucket_start(ts: i64, width_secs: i64) -> i64 {
+    ts - ts.rem_euclid(width_secs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::insert::{self, set_conflict_policy, ConflictPolicy};
+    use crate::db::migrations::scratch_db;
+    use chrono::{DateTime, Utc};
+    use rust_decimal::Decimal;
+    use std::str::FromStr;
+    use time::macros::datetime;
+    use tokio::sync::RwLock;
+
+    /// The conflict policy is process-wide: `conflict_policies` holds this
+    /// exclusively while it switches policies, every other test shares it.
+    static POLICY: RwLock<()> = RwLock::const_new(());
+
+    /// Every backend, each empty. PostgreSQL is included when `DATABASE_URL`
+    /// points at a server; `name` picks its scratch database.
+    async fn stores(name: &str) -> Vec<Box<dyn FundingStore>> {
+        let mut stores: Vec<Box<dyn FundingStore>> = vec![
+            Box::new(MemoryStore::new()),
+            Box::new(SqliteStore::open("sqlite::memory:").await.unwrap()),
+        ];
+        if let Some(store) = pg_store(name).await {
+            stores.push(Box::new(store));
+        }
+        stores
+    }
+
+    /// A scratch PostgreSQL database with the Paradex exchange row.
+    async fn pg_store(name: &str) -> Option<PgStore> {
+        let pool = scratch_db(name).await?;
+        insert::upsert_exchange(&pool, "Paradex").await.unwrap();
+        Some(PgStore::new(pool))
+    }
+
+    /// Paradex id and the id of its one market, `BTC-USD-PERP`.
+    async fn seed(store: &dyn FundingStore) -> (i32, i32) {
+        let exchange = store
+            .active_exchanges()
+            .await
+            .unwrap()
+            .into_iter()
+            .find(|e| e.name == "Paradex")
+            .unwrap();
+        let market = NormalizedMarket {
+            exchange: "Paradex".into(),
+            symbol: "BTC".into(),
+            market_symbol: "BTC-USD-PERP".into(),
+            base_currency: "BTC".into(),
+            quote_currency: "USD".into(),
+            is_active: true,
+            funding_interval_minutes: None,
+        };
+        store.upsert_markets(exchange.id, &[market]).await.unwrap();
+        let market_id = store.active_markets(exchange.id).await.unwrap()[0].id;
+        (exchange.id, market_id)
+    }
+
+    fn rate(rate: &str, at: OffsetDateTime) -> NormalizedFundingRate {
+        NormalizedFundingRate {
+            market_symbol: "BTC-USD-PERP".into(),
+            rate: Decimal::from_str(rate).unwrap(),
+            timestamp: DateTime::<Utc>::from_timestamp(at.unix_timestamp(), 0).unwrap(),
+        }
+    }
+
+    async fn insert(store: &dyn FundingStore, exchange_id: i32, market_id: i32, rows: &[NormalizedFundingRate]) {
+        let rows: Vec<(i32, &NormalizedFundingRate)> = rows.iter().map(|r| (market_id, r)).collect();
+        store.insert_funding_rates(exchange_id, &rows).await.unwrap();
+    }
+
+    fn assert_close(a: f64, b: f64) {
+        assert!((a - b).abs() < 1e-12, "{a} != {b}");
+    }
+
+    // Every policy in one test: the policy is process-wide
+    #[tokio::test]
+    async fn conflict_policies() {
+        let _policy = POLICY.write().await;
+        let at = datetime!(2025-03-01 08:00:00 UTC);
+        let to = datetime!(2025-03-01 09:00:00 UTC);
+        for store in stores("conflict_policies").await {
+            let store = store.as_ref();
+            let (exchange_id, market_id) = seed(store).await;
+            let stored = || async {
+                let h = store.funding_history(market_id, 60, at, to).await.unwrap();
+                assert_eq!(h.len(), 1, "{}", store.kind());
+                h[0].1
+            };
+
+            set_conflict_policy(ConflictPolicy::Ignore);
+            insert(store, exchange_id, market_id, &[rate("0.0001", at)]).await;
+            insert(store, exchange_id, market_id, &[rate("0.0002", at)]).await;
+            assert_close(stored().await, 0.0001);
+
+            set_conflict_policy(ConflictPolicy::Overwrite);
+            insert(store, exchange_id, market_id, &[rate("0.0002", at)]).await;
+            assert_close(stored().await, 0.0002);
+
+            set_conflict_policy(ConflictPolicy::OverwriteIfDifferent);
+            insert(store, exchange_id, market_id, &[rate("0.00020", at)]).await;
+            assert_close(stored().await, 0.0002);
+            insert(store, exchange_id, market_id, &[rate("0.0003", at)]).await;
+            assert_close(stored().await, 0.0003);
+        }
+        set_conflict_policy(ConflictPolicy::Ignore);
+    }
+
+    #[tokio::test]
+    async fn latest_matrix_averages_the_newest_8h_bucket() {
+        let _policy = POLICY.read().await;
+        for store in stores("latest_matrix_averages_the_newest_8h_bucket").await {
+            let store = store.as_ref();
+            let (exchange_id, market_id) = seed(store).await;
+            insert(
+                store,
+                exchange_id,
+                market_id,
+                &[
+                    rate("0.0009", datetime!(2025-03-01 07:00:00 UTC)),
+                    rate("0.0001", datetime!(2025-03-01 08:00:00 UTC)),
+                    rate("0.0002", datetime!(2025-03-01 09:00:00 UTC)),
+                    rate("0.0003", datetime!(2025-03-01 15:00:00 UTC)),
+                ],
+            )
+            .await;
+
+            let matrix = store.latest_matrix().await.unwrap();
+            assert_eq!(matrix.len(), 1, "{}", store.kind());
+            let e = &matrix[0];
+            assert_eq!((e.token.as_str(), e.exchange.as_str()), ("BTC", "Paradex"));
+            assert_eq!(e.funding_bucket, Some(datetime!(2025-03-01 08:00:00 UTC)), "{}", store.kind());
+            assert_close(e.funding_rate_8h.unwrap(), 0.0002);
+        }
+    }
+
+    #[tokio::test]
+    async fn funding_history_leaves_out_the_open_bucket() {
+        let _policy = POLICY.read().await;
+        for store in stores("funding_history_leaves_out_the_open_bucket").await {
+            let store = store.as_ref();
+            let (exchange_id, market_id) = seed(store).await;
+            let from = datetime!(2025-03-01 00:00:00 UTC);
+            let rows: Vec<NormalizedFundingRate> = (0..11)
+                .map(|h| rate(if h < 8 { "0.0001" } else { "0.0005" }, from + time::Duration::hours(h)))
+                .collect();
+            insert(store, exchange_id, market_id, &rows).await;
+
+            let h = store.funding_history(market_id, 480, from, datetime!(2025-03-01 10:30:00 UTC)).await.unwrap();
+            assert_eq!(h.len(), 1, "{}", store.kind());
+            assert_eq!(h[0].0, from);
+            assert_close(h[0].1, 0.0001);
+
+            // Once the 08:00 bucket has closed it is reported too
+            let h = store.funding_history(market_id, 480, from, datetime!(2025-03-01 16:00:00 UTC)).await.unwrap();
+            assert_eq!(h.len(), 2, "{}", store.kind());
+            assert_eq!(h[1].0, datetime!(2025-03-01 08:00:00 UTC));
+            assert_close(h[1].1, 0.0005);
+        }
+    }
+}
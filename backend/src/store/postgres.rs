@@ -0,0 +1,186 @@
+// backend/src/store/postgres.rs
+// The full backend: inserts go through `db::insert` (COPY, conflict policy,
+// rollups), reads use the rollup-backed views.
+use anyhow::Result;
+use async_trait::async_trait;
+use sqlx::PgPool;
+use std::time::Instant;
+use time::OffsetDateTime;
+
+use super::{ExchangeRef, FundingStore, MarketRef, MatrixEntry};
+use crate::db::insert;
+use crate::exchanges::shared::types::{NormalizedFundingRate, NormalizedMarket, NormalizedMarketStats};
+use crate::utils::metrics;
+
+#[derive(Clone)]
+pub struct PgStore {
+    pool: PgPool,
+}
+
+impl PgStore {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl FundingStore for PgStore {
+    fn kind(&self) -> &'static str {
+        "postgres"
+    }
+
+    async fn active_exchanges(&self) -> Result<Vec<ExchangeRef>> {
+        let rows = sqlx::query_as!(
+            ExchangeRef,
+            "SELECT id, name, funding_interval_minutes FROM exchanges WHERE is_active = true ORDER BY name"
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows)
+    }
+
+    async fn set_funding_interval(&self, exchange_id: i32, minutes: i32) -> Result<bool> {
+        let res = sqlx::query!(
+            r#"
+            UPDATE exchanges
+            SET funding_interval_minutes = $2, updated_at = NOW()
+            WHERE id = $1 AND (funding_interval_minutes IS NULL OR funding_interval_minutes <> $2)
+            "#,
+            exchange_id,
+            minutes
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(res.rows_affected() > 0)
+    }
+
+    async fn upsert_markets(&self, exchange_id: i32, markets: &[NormalizedMarket]) -> Result<()> {
+        insert::upsert_markets(&self.pool, exchange_id, markets).await
+    }
+
+    async fn active_markets(&self, exchange_id: i32) -> Result<Vec<MarketRef>> {
+        let rows = sqlx::query_as!(
+            MarketRef,
+            r#"
+            SELECT m.id, e.name AS exchange, m.market_symbol, t.symbol AS token,
+                   COALESCE(m.funding_interval_minutes, e.funding_interval_minutes) AS funding_interval_minutes
+            FROM markets m
+            JOIN tokens    t ON t.id = m.token_id
+            JOIN exchanges e ON e.id = m.exchange_id
+            WHERE m.exchange_id = $1 AND m.is_active = true
+            ORDER BY m.market_symbol
+            "#,
+            exchange_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows)
+    }
+
+    async fn find_market(&self, token: &str, exchange: &str) -> Result<Option<MarketRef>> {
+        let row = sqlx::query_as!(
+            MarketRef,
+            r#"
+            SELECT m.id, e.name AS exchange, m.market_symbol, t.symbol AS token,
+                   COALESCE(m.funding_interval_minutes, e.funding_interval_minutes) AS funding_interval_minutes
+            FROM markets m
+            JOIN tokens    t ON t.id = m.token_id
+            JOIN exchanges e ON e.id = m.exchange_id
+            WHERE t.symbol = upper($1) AND lower(e.name) = lower($2)
+            ORDER BY m.is_active DESC, m.id
+            LIMIT 1
+            "#,
+            token,
+            exchange
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(row)
+    }
+
+    async fn insert_funding_rates(&self, exchange_id: i32, rows: &[(i32, &NormalizedFundingRate)]) -> Result<()> {
+        insert::insert_funding_rates(&self.pool, exchange_id, rows).await
+    }
+
+    async fn insert_market_stats(&self, exchange_id: i32, rows: &[(i32, &NormalizedMarketStats)]) -> Result<()> {
+        insert::insert_market_stats(&self.pool, exchange_id, rows).await
+    }
+
+    async fn last_funding_ts(&self, market_id: i32) -> Result<Option<OffsetDateTime>> {
+        let ts = sqlx::query_scalar!(
+            "SELECT MAX(timestamp) FROM funding_rates WHERE market_id = $1",
+            market_id
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(ts)
+    }
+
+    async fn latest_matrix(&self) -> Result<Vec<MatrixEntry>> {
+        let started = Instant::now();
+        let rows = sqlx::query_as!(
+            MatrixEntry,
+            r#"
+            SELECT
+              t.symbol        AS "token!",
+              e.name          AS "exchange!",
+              m.market_symbol AS "market_symbol!",
+              COALESCE(m.funding_interval_minutes, e.funding_interval_minutes) AS funding_interval_minutes,
+              lfr.rate_8h::float8        AS funding_rate_8h,
+              lfr.timestamp              AS "funding_bucket?",
+              lms.open_interest::float8  AS open_interest,
+              lms.volume_24h::float8     AS volume_24h,
+              lms.timestamp              AS "stats_ts?",
+              lpf.rate::float8           AS predicted_rate,
+              lpf.next_funding_time      AS "next_funding_time?",
+              lpf.timestamp              AS "predicted_ts?",
+              lps.mark_price::float8     AS mark_price,
+              lps.index_price::float8    AS index_price,
+              lps.last_price::float8     AS last_price,
+              lps.timestamp              AS "price_ts?"
+            FROM markets m
+            JOIN tokens    t ON t.id = m.token_id
+            JOIN exchanges e ON e.id = m.exchange_id
+            LEFT JOIN latest_funding_8h_view      lfr ON lfr.market_id = m.id
+            LEFT JOIN latest_market_stats_view    lms ON lms.market_id = m.id
+            LEFT JOIN latest_predicted_funding_view lpf ON lpf.market_id = m.id
+            LEFT JOIN latest_price_snapshots_view lps ON lps.market_id = m.id
+            WHERE m.is_active = true
+            ORDER BY t.symbol, e.name
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        metrics::observe_db_query("funding_matrix_view", started.elapsed());
+        Ok(rows)
+    }
+
+    async fn funding_history(
+        &self,
+        market_id: i32,
+        bucket_minutes: i32,
+        from: OffsetDateTime,
+        to: OffsetDateTime,
+    ) -> Result<Vec<(OffsetDateTime, f64)>> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT
+              date_bin(make_interval(mins => $2), fr.timestamp, TIMESTAMPTZ 'epoch') AS "bucket!",
+              AVG(fr.rate)::float8 AS "rate!"
+            FROM funding_rates fr
+            WHERE fr.market_id = $1 AND fr.timestamp >= $3 AND fr.timestamp < $4
+            GROUP BY 1
+            HAVING date_bin(make_interval(mins => $2), fr.timestamp, TIMESTAMPTZ 'epoch')
+                   + make_interval(mins => $2) <= $4 -- only intervals that have been paid
+            ORDER BY 1
+            "#,
+            market_id,
+            bucket_minutes,
+            from,
+            to
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows.into_iter().map(|r| (r.bucket, r.rate)).collect())
+    }
+}
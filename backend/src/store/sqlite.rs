@@ -0,0 +1,396 @@
+// backend/src/store/sqlite.rs
+// Embedded single-file store (`DATABASE_URL=sqlite://funding.db`). Decimals
+// are kept as text so nothing is rounded on the way in; aggregates cast to
+// REAL, which is all the API serves anyway.
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use rust_decimal::Decimal;
+use sqlx::migrate::Migrator;
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePool, SqlitePoolOptions};
+use std::collections::BTreeSet;
+use std::str::FromStr;
+use time::OffsetDateTime;
+use tracing::info;
+
+use super::{ExchangeRef, FundingStore, MarketRef, MatrixEntry, DEFAULT_EXCHANGES, MATRIX_BUCKET_SECS};
+use crate::analytics::rates::DEFAULT_INTERVAL_MINUTES;
+use crate::db::insert::{conflict_policy, ConflictPolicy};
+use crate::exchanges::shared::types::{NormalizedFundingRate, NormalizedMarket, NormalizedMarketStats};
+use crate::utils::metrics;
+
+static MIGRATOR: Migrator = sqlx::migrate!("./migrations_sqlite");
+
+pub struct SqliteStore {
+    pool: SqlitePool,
+}
+
+fn text(d: &Decimal) -> String {
+    d.normalize().to_string()
+}
+
+fn ts(secs: i64) -> Result<OffsetDateTime> {
+    Ok(OffsetDateTime::from_unix_timestamp(secs)?)
+}
+
+fn opt_ts(secs: Option<i64>) -> Result<Option<OffsetDateTime>> {
+    secs.map(ts).transpose()
+}
+
+/// `ON CONFLICT` clause for the active conflict policy. There is no revision
+/// log here; overwritten values are simply replaced.
+fn on_conflict(set: &str, changed: &str) -> String {
+    match conflict_policy() {
+        ConflictPolicy::Ignore => "ON CONFLICT (market_id, timestamp) DO NOTHING".to_string(),
+        ConflictPolicy::Overwrite => format!("ON CONFLICT (market_id, timestamp) DO UPDATE SET {set}"),
+        ConflictPolicy::OverwriteIfDifferent => {
+            format!("ON CONFLICT (market_id, timestamp) DO UPDATE SET {set} WHERE {changed}")
+        }
+    }
+}
+
+/// Lowercase exchange name for metric labels, looked up once per id.
+async fn exchange_label(pool: &SqlitePool, exchange_id: i32) -> Result<String> {
+    if let Some(label) = metrics::exchange_label(exchange_id) {
+        return Ok(label);
+    }
+    let name: String = sqlx::query_scalar("SELECT name FROM exchanges WHERE id = ?")
+        .bind(exchange_id)
+        .fetch_one(pool)
+        .await?;
+    Ok(metrics::set_exchange_label(exchange_id, &name))
+}
+
+#[derive(sqlx::FromRow)]
+struct MatrixRow {
+    token: String,
+    exchange: String,
+    market_symbol: String,
+    funding_interval_minutes: Option<i32>,
+    funding_rate_8h: Option<f64>,
+    funding_bucket: Option<i64>,
+    open_interest: Option<f64>,
+    volume_24h: Option<f64>,
+    stats_ts: Option<i64>,
+    mark_price: Option<f64>,
+    index_price: Option<f64>,
+    last_price: Option<f64>,
+    price_ts: Option<i64>,
+}
+
+impl SqliteStore {
+    /// Open (creating if missing) and migrate the database at `url`.
+    pub async fn open(url: &str) -> Result<Self> {
+        let opts = SqliteConnectOptions::from_str(url)?
+            .create_if_missing(true)
+            .journal_mode(SqliteJournalMode::Wal)
+            .foreign_keys(true);
+        // `sqlite::memory:` lives and dies with its connection, so keep exactly one open
+        let in_memory = url.contains(":memory:");
+        let pool = SqlitePoolOptions::new()
+            .max_connections(if in_memory { 1 } else { 4 })
+            .idle_timeout(None)
+            .max_lifetime(None)
+            .connect_with(opts)
+            .await
+            .with_context(|| format!("open {url}"))?;
+        MIGRATOR.run(&pool).await.context("sqlite migrations failed")?;
+
+        for name in DEFAULT_EXCHANGES {
+            sqlx::query("INSERT OR IGNORE INTO exchanges (name, funding_interval_minutes) VALUES (?, ?)")
+                .bind(name)
+                .bind(DEFAULT_INTERVAL_MINUTES)
+                .execute(&pool)
+                .await?;
+        }
+        info!("sqlite store ready ({url})");
+        Ok(Self { pool })
+    }
+}
+
+#[async_trait]
+impl FundingStore for SqliteStore {
+    fn kind(&self) -> &'static str {
+        "sqlite"
+    }
+
+    async fn active_exchanges(&self) -> Result<Vec<ExchangeRef>> {
+        let rows: Vec<(i32, String, Option<i32>)> = sqlx::query_as(
+            "SELECT id, name, funding_interval_minutes FROM exchanges WHERE is_active = 1 ORDER BY name",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows
+            .into_iter()
+            .map(|(id, name, funding_interval_minutes)| ExchangeRef { id, name, funding_interval_minutes })
+            .collect())
+    }
+
+    async fn set_funding_interval(&self, exchange_id: i32, minutes: i32) -> Result<bool> {
+        let res = sqlx::query(
+            "UPDATE exchanges SET funding_interval_minutes = ?2
+             WHERE id = ?1 AND (funding_interval_minutes IS NULL OR funding_interval_minutes <> ?2)",
+        )
+        .bind(exchange_id)
+        .bind(minutes)
+        .execute(&self.pool)
+        .await?;
+        Ok(res.rows_affected() > 0)
+    }
+
+    async fn upsert_markets(&self, exchange_id: i32, markets: &[NormalizedMarket]) -> Result<()> {
+        if markets.is_empty() {
+            return Ok(());
+        }
+        let mut tx = self.pool.begin().await?;
+        let tokens: BTreeSet<&str> = markets.iter().map(|m| m.symbol.as_str()).collect();
+        for sym in tokens {
+            sqlx::query("INSERT OR IGNORE INTO tokens (symbol) VALUES (?)")
+                .bind(sym)
+                .execute(&mut *tx)
+                .await?;
+        }
+        for m in markets {
+            sqlx::query(
+                "INSERT INTO markets (exchange_id, token_id, market_symbol, is_active, funding_interval_minutes)
+                 VALUES (?, (SELECT id FROM tokens WHERE symbol = ?), ?, ?, ?)
+                 ON CONFLICT (exchange_id, market_symbol) DO UPDATE SET
+                   is_active = excluded.is_active,
+                   funding_interval_minutes = COALESCE(excluded.funding_interval_minutes, markets.funding_interval_minutes)",
+            )
+            .bind(exchange_id)
+            .bind(&m.symbol)
+            .bind(&m.market_symbol)
+            .bind(m.is_active)
+            .bind(m.funding_interval_minutes)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn active_markets(&self, exchange_id: i32) -> Result<Vec<MarketRef>> {
+        let rows: Vec<(i32, String, String, String, Option<i32>)> = sqlx::query_as(
+            "SELECT m.id, e.name, m.market_symbol, t.symbol,
+                    COALESCE(m.funding_interval_minutes, e.funding_interval_minutes)
+             FROM markets m
+             JOIN tokens    t ON t.id = m.token_id
+             JOIN exchanges e ON e.id = m.exchange_id
+             WHERE m.exchange_id = ? AND m.is_active = 1
+             ORDER BY m.market_symbol",
+        )
+        .bind(exchange_id)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows
+            .into_iter()
+            .map(|(id, exchange, market_symbol, token, funding_interval_minutes)| MarketRef {
+                id,
+                exchange,
+                market_symbol,
+                token,
+                funding_interval_minutes,
+            })
+            .collect())
+    }
+
+    async fn find_market(&self, token: &str, exchange: &str) -> Result<Option<MarketRef>> {
+        let row: Option<(i32, String, String, String, Option<i32>)> = sqlx::query_as(
+            "SELECT m.id, e.name, m.market_symbol, t.symbol,
+                    COALESCE(m.funding_interval_minutes, e.funding_interval_minutes)
+             FROM markets m
+             JOIN tokens    t ON t.id = m.token_id
+             JOIN exchanges e ON e.id = m.exchange_id
+             WHERE t.symbol = upper(?) AND lower(e.name) = lower(?)
+             ORDER BY m.is_active DESC, m.id
+             LIMIT 1",
+        )
+        .bind(token)
+        .bind(exchange)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(row.map(|(id, exchange, market_symbol, token, funding_interval_minutes)| MarketRef {
+            id,
+            exchange,
+            market_symbol,
+            token,
+            funding_interval_minutes,
+        }))
+    }
+
+    async fn insert_funding_rates(&self, exchange_id: i32, rows: &[(i32, &NormalizedFundingRate)]) -> Result<()> {
+        if rows.is_empty() {
+            return Ok(());
+        }
+        let sql = format!(
+            "INSERT INTO funding_rates (market_id, exchange_id, rate, timestamp) VALUES (?, ?, ?, ?) {}",
+            on_conflict("rate = excluded.rate", "rate IS NOT excluded.rate")
+        );
+        let exchange = exchange_label(&self.pool, exchange_id).await?;
+        let mut tx = self.pool.begin().await?;
+        let mut written = 0u64;
+        for (market_id, r) in rows {
+            written += sqlx::query(&sql)
+                .bind(market_id)
+                .bind(exchange_id)
+                .bind(text(&r.rate))
+                .bind(r.timestamp.timestamp())
+                .execute(&mut *tx)
+                .await?
+                .rows_affected();
+        }
+        tx.commit().await?;
+        metrics::inc_rows_inserted("funding_rates", &exchange, written);
+        Ok(())
+    }
+
+    async fn insert_market_stats(&self, exchange_id: i32, rows: &[(i32, &NormalizedMarketStats)]) -> Result<()> {
+        if rows.is_empty() {
+            return Ok(());
+        }
+        let sql = format!(
+            "INSERT INTO market_stats (market_id, open_interest, volume_24h, timestamp) VALUES (?, ?, ?, ?) {}",
+            on_conflict(
+                "open_interest = excluded.open_interest, volume_24h = excluded.volume_24h",
+                "open_interest IS NOT excluded.open_interest OR volume_24h IS NOT excluded.volume_24h",
+            )
+        );
+        let exchange = exchange_label(&self.pool, exchange_id).await?;
+        let mut tx = self.pool.begin().await?;
+        let (mut stats_n, mut prices_n) = (0u64, 0u64);
+        for (market_id, s) in rows {
+            let ts = s.timestamp.timestamp();
+            stats_n += sqlx::query(&sql)
+                .bind(market_id)
+                .bind(s.open_interest.as_ref().map(text))
+                .bind(s.volume_24h.as_ref().map(text))
+                .bind(ts)
+                .execute(&mut *tx)
+                .await?
+                .rows_affected();
+
+            if s.mark_price.is_some() || s.index_price.is_some() || s.last_price.is_some() {
+                prices_n += sqlx::query(
+                    "INSERT OR IGNORE INTO price_snapshots (market_id, mark_price, index_price, last_price, timestamp)
+                     VALUES (?, ?, ?, ?, ?)",
+                )
+                .bind(market_id)
+                .bind(s.mark_price.as_ref().map(text))
+                .bind(s.index_price.as_ref().map(text))
+                .bind(s.last_price.as_ref().map(text))
+                .bind(ts)
+                .execute(&mut *tx)
+                .await?
+                .rows_affected();
+            }
+        }
+        tx.commit().await?;
+        metrics::inc_rows_inserted("market_stats", &exchange, stats_n);
+        metrics::inc_rows_inserted("price_snapshots", &exchange, prices_n);
+        Ok(())
+    }
+
+    async fn last_funding_ts(&self, market_id: i32) -> Result<Option<OffsetDateTime>> {
+        let secs: Option<i64> = sqlx::query_scalar("SELECT MAX(timestamp) FROM funding_rates WHERE market_id = ?")
+            .bind(market_id)
+            .fetch_one(&self.pool)
+            .await?;
+        opt_ts(secs)
+    }
+
+    async fn latest_matrix(&self) -> Result<Vec<MatrixEntry>> {
+        // SQLite fills bare columns next to MAX() from the row holding the maximum
+        let rows: Vec<MatrixRow> = sqlx::query_as(
+            "WITH lf AS (
+               SELECT market_id, MAX(timestamp) - MAX(timestamp) % ?1 AS bucket
+               FROM funding_rates GROUP BY market_id
+             ),
+             f8 AS (
+               SELECT lf.market_id, lf.bucket, AVG(CAST(f.rate AS REAL)) AS rate_8h
+               FROM lf
+               JOIN funding_rates f
+                 ON f.market_id = lf.market_id AND f.timestamp >= lf.bucket AND f.timestamp < lf.bucket + ?1
+               GROUP BY lf.market_id, lf.bucket
+             ),
+             ls AS (
+               SELECT market_id, open_interest, volume_24h, MAX(timestamp) AS timestamp
+               FROM market_stats GROUP BY market_id
+             ),
+             lp AS (
+               SELECT market_id, mark_price, index_price, last_price, MAX(timestamp) AS timestamp
+               FROM price_snapshots GROUP BY market_id
+             )
+             SELECT
+               t.symbol AS token,
+               e.name   AS exchange,
+               m.market_symbol,
+               COALESCE(m.funding_interval_minutes, e.funding_interval_minutes) AS funding_interval_minutes,
+               f8.rate_8h AS funding_rate_8h,
+               f8.bucket  AS funding_bucket,
+               CAST(ls.open_interest AS REAL) AS open_interest,
+               CAST(ls.volume_24h AS REAL)    AS volume_24h,
+               ls.timestamp AS stats_ts,
+               CAST(lp.mark_price AS REAL)  AS mark_price,
+               CAST(lp.index_price AS REAL) AS index_price,
+               CAST(lp.last_price AS REAL)  AS last_price,
+               lp.timestamp AS price_ts
+             FROM markets m
+             JOIN tokens    t ON t.id = m.token_id
+             JOIN exchanges e ON e.id = m.exchange_id
+             LEFT JOIN f8 ON f8.market_id = m.id
+             LEFT JOIN ls ON ls.market_id = m.id
+             LEFT JOIN lp ON lp.market_id = m.id
+             WHERE m.is_active = 1
+             ORDER BY t.symbol, e.name",
+        )
+        .bind(MATRIX_BUCKET_SECS)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter()
+            .map(|r| {
+                Ok(MatrixEntry {
+                    token: r.token,
+                    exchange: r.exchange,
+                    market_symbol: r.market_symbol,
+                    funding_interval_minutes: r.funding_interval_minutes,
+                    funding_rate_8h: r.funding_rate_8h,
+                    funding_bucket: opt_ts(r.funding_bucket)?,
+                    open_interest: r.open_interest,
+                    volume_24h: r.volume_24h,
+                    stats_ts: opt_ts(r.stats_ts)?,
+                    mark_price: r.mark_price,
+                    index_price: r.index_price,
+                    last_price: r.last_price,
+                    price_ts: opt_ts(r.price_ts)?,
+                    ..Default::default()
+                })
+            })
+            .collect()
+    }
+
+    async fn funding_history(
+        &self,
+        market_id: i32,
+        bucket_minutes: i32,
+        from: OffsetDateTime,
+        to: OffsetDateTime,
+    ) -> Result<Vec<(OffsetDateTime, f64)>> {
+        let width = i64::from(bucket_minutes.max(1)) * 60;
+        let rows: Vec<(i64, f64)> = sqlx::query_as(
+            "SELECT timestamp - timestamp % ?1 AS bucket, AVG(CAST(rate AS REAL))
+             FROM funding_rates
+             WHERE market_id = ?2 AND timestamp >= ?3 AND timestamp < ?4
+             GROUP BY bucket
+             HAVING bucket + ?1 <= ?4
+             ORDER BY bucket",
+        )
+        .bind(width)
+        .bind(market_id)
+        .bind(from.unix_timestamp())
+        .bind(to.unix_timestamp())
+        .fetch_all(&self.pool)
+        .await?;
+        rows.into_iter().map(|(b, r)| Ok((ts(b)?, r))).collect()
+    }
+}
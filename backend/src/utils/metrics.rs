@@ -0,0 +1,217 @@
+// src/utils/metrics.rs
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{LazyLock, PoisonError, RwLock};
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use prometheus::core::Collector;
+use prometheus::{
+    Encoder, GaugeVec, HistogramOpts, HistogramVec, IntCounterVec, Opts, Registry, TextEncoder,
+};
+use sqlx::PgPool;
+use time::OffsetDateTime;
+
+pub static REGISTRY: LazyLock<Registry> = LazyLock::new(Registry::new);
+
+fn register<C: Collector + Clone + 'static>(c: C) -> C {
+    REGISTRY
+        .register(Box::new(c.clone()))
+        .expect("metric registered twice");
+    c
+}
+
+static HTTP_REQUESTS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register(
+        IntCounterVec::new(
+            Opts::new("exchange_http_requests_total", "HTTP requests sent to exchange APIs"),
+            &["exchange", "endpoint", "status"],
+        )
+        .unwrap(),
+    )
+});
+
+static HTTP_LATENCY: LazyLock<HistogramVec> = LazyLock::new(|| {
+    register(
+        HistogramVec::new(
+            HistogramOpts::new(
+                "exchange_http_request_duration_seconds",
+                "Latency of exchange API requests (until response headers)",
+            ),
+            &["exchange", "endpoint"],
+        )
+        .unwrap(),
+    )
+});
+
+static ROWS_INSERTED: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register(
+        IntCounterVec::new(
+            Opts::new("db_rows_inserted_total", "Rows written by db::insert"),
+            &["table", "exchange"],
+        )
+        .unwrap(),
+    )
+});
+
+static JOB_DURATION: LazyLock<HistogramVec> = LazyLock::new(|| {
+    register(
+        HistogramVec::new(
+            HistogramOpts::new("scheduler_job_duration_seconds", "Scheduler job run time")
+                .buckets(vec![1.0, 5.0, 15.0, 30.0, 60.0, 120.0, 300.0, 600.0, 1800.0]),
+            &["job"],
+        )
+        .unwrap(),
+    )
+});
+
+static JOB_FAILURES: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register(
+        IntCounterVec::new(
+            Opts::new("scheduler_job_failures_total", "Scheduler job runs that returned an error"),
+            &["job"],
+        )
+        .unwrap(),
+    )
+});
+
+static DB_QUERY_LATENCY: LazyLock<HistogramVec> = LazyLock::new(|| {
+    register(
+        HistogramVec::new(
+            HistogramOpts::new("db_query_duration_seconds", "Latency of API read queries"),
+            &["query"],
+        )
+        .unwrap(),
+    )
+});
+
+static DATA_LAG: LazyLock<GaugeVec> = LazyLock::new(|| {
+    register(
+        GaugeVec::new(
+            Opts::new(
+                "exchange_data_lag_seconds",
+                "Seconds since the newest stored row per exchange (refreshed on scrape)",
+            ),
+            &["exchange", "kind"],
+        )
+        .unwrap(),
+    )
+});
+
+/* ---------------- Recording helpers ---------------- */
+
+/// Record one exchange request. Transport errors are labelled `error`.
+pub fn observe_http(
+    exchange: &str,
+    endpoint: &str,
+    res: &Result<reqwest::Response, reqwest::Error>,
+    elapsed: Duration,
+) {
+    let status = match res {
+        Ok(r) => r.status().as_u16().to_string(),
+        Err(e) => e
+            .status()
+            .map(|s| s.as_u16().to_string())
+            .unwrap_or_else(|| "error".to_string()),
+    };
+    HTTP_REQUESTS
+        .with_label_values(&[exchange, endpoint, status.as_str()])
+        .inc();
+    HTTP_LATENCY
+        .with_label_values(&[exchange, endpoint])
+        .observe(elapsed.as_secs_f64());
+}
+
+/// Lowercase exchange names by id, for labelling rows written by exchange id.
+static EXCHANGE_LABELS: LazyLock<RwLock<HashMap<i32, String>>> = LazyLock::new(Default::default);
+
+/// The `exchange` label for `exchange_id`, if it has been seen.
+pub fn exchange_label(exchange_id: i32) -> Option<String> {
+    EXCHANGE_LABELS
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .get(&exchange_id)
+        .cloned()
+}
+
+/// Remember `name` as the `exchange` label for `exchange_id` and return it.
+/// Labels are lowercase, as in `exchange_http_requests_total`.
+pub fn set_exchange_label(exchange_id: i32, name: &str) -> String {
+    let label = name.trim().to_ascii_lowercase();
+    EXCHANGE_LABELS
+        .write()
+        .unwrap_or_else(PoisonError::into_inner)
+        .insert(exchange_id, label.clone());
+    label
+}
+
+/// `exchange` is the lowercase exchange name (see `set_exchange_label`).
+pub fn inc_rows_inserted(table: &str, exchange: &str, rows: u64) {
+    ROWS_INSERTED
+        .with_label_values(&[table, exchange])
+        .inc_by(rows);
+}
+
+pub fn observe_db_query(query: &str, elapsed: Duration) {
+    DB_QUERY_LATENCY
+        .with_label_values(&[query])
+        .observe(elapsed.as_secs_f64());
+}
+
+/// Run a scheduler job, recording its duration and counting it as failed on `Err`.
+pub async fn time_job<F, T, E>(job: &str, fut: F) -> Result<T, E>
+where
+    F: Future<Output = Result<T, E>>,
+{
+    let started = Instant::now();
+    let res = fut.await;
+    JOB_DURATION
+        .with_label_values(&[job])
+        .observe(started.elapsed().as_secs_f64());
+    if res.is_err() {
+        JOB_FAILURES.with_label_values(&[job]).inc();
+    }
+    res
+}
+
+/// Recompute `exchange_data_lag_seconds` from the newest funding / stats row per exchange.
+pub async fn refresh_data_lag(pool: &PgPool) -> Result<()> {
+    let started = Instant::now();
+    let rows = sqlx::query!(
+        r#"
+        SELECT
+          lower(e.name) AS "name!",
+          (SELECT MAX(fr.timestamp) FROM funding_rates fr WHERE fr.exchange_id = e.id) AS funding_ts,
+          (SELECT MAX(ms.timestamp)
+             FROM market_stats ms
+             JOIN markets m ON m.id = ms.market_id
+            WHERE m.exchange_id = e.id) AS stats_ts
+        FROM exchanges e
+        WHERE e.is_active = true
+        "#
+    )
+    .fetch_all(pool)
+    .await?;
+    observe_db_query("data_lag", started.elapsed());
+
+    let now = OffsetDateTime::now_utc();
+    for r in rows {
+        for (kind, ts) in [("funding", r.funding_ts), ("stats", r.stats_ts)] {
+            if let Some(ts) = ts {
+                DATA_LAG
+                    .with_label_values(&[r.name.as_str(), kind])
+                    .set((now - ts).as_seconds_f64());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Prometheus text exposition of everything registered so far.
+pub fn render() -> String {
+    let mut buf = Vec::new();
+    if let Err(e) = TextEncoder::new().encode(&REGISTRY.gather(), &mut buf) {
+        tracing::error!("metrics encode failed: {e:?}");
+    }
+    String::from_utf8(buf).unwrap_or_default()
+}
@@ -1 +1,2 @@
-pub mod scheduler;
\ No newline at end of file
+pub mod scheduler;
+pub mod metrics;
\ No newline at end of file
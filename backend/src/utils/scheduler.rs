@@ -1,8 +1,15 @@
 use anyhow::Result;
 use sqlx::PgPool;
+use std::sync::Arc;
 use tokio_cron_scheduler::{JobScheduler, Job};
 use tracing::{info, error};
-use crate::data::{coin::refresh_all_markets, stats::collect_daily_market_stats, funding::collect_funding_for_exchange};
+use crate::data::{coin::refresh_all_markets, stats::collect_daily_market_stats, funding::collect_funding_for_exchange, predicted::collect_predicted_funding};
+use crate::utils::metrics::time_job;
+use crate::alerts::evaluate_alerts;
+use crate::analytics::events::{detect_funding_events, EventConfig};
+use crate::db::partitions::maintain_partitions;
+use crate::db::retention::{prune, RetentionPolicy};
+use crate::store::{FundingStore, PgStore};
 
 #[derive(Debug)]
 struct ExchangeCfg {
@@ -13,15 +20,117 @@ struct ExchangeCfg {
 
 pub async fn start_scheduler(pool: PgPool) -> Result<()> {
     let sched = JobScheduler::new().await?;
+    let store: Arc<dyn FundingStore> = Arc::new(PgStore::new(pool.clone()));
 
-    // --- Daily Market Discovery (midnight UTC)
+    // Make sure the current and upcoming monthly partitions exist before any insert
+    if let Err(e) = time_job("partitions", maintain_partitions(&pool)).await {
+        error!("Partition maintenance failed: {}", e);
+    }
+
+    // --- Partition maintenance (daily 00:10 UTC): upcoming months
     {
         let pool_clone = pool.clone();
-        let job = Job::new_async("0 0 0 * * *", move |_id, _| {
+        let job = Job::new_async("0 10 0 * * *", move |_id, _| {
+            let pool = pool_clone.clone();
+            Box::pin(async move {
+                if let Err(e) = time_job("partitions", maintain_partitions(&pool)).await {
+                    error!("Partition maintenance failed: {}", e);
+                }
+            })
+        })?;
+        sched.add(job).await?;
+    }
+
+    // --- Retention / downsampling (daily 00:20 UTC), when a policy is configured
+    {
+        let pool_clone = pool.clone();
+        let job = Job::new_async("0 20 0 * * *", move |_id, _| {
+            let pool = pool_clone.clone();
+            Box::pin(async move {
+                let policy = RetentionPolicy::from_env();
+                if policy.is_empty() {
+                    return;
+                }
+                if let Err(e) = time_job("retention", prune(&pool, &policy, false)).await {
+                    error!("Retention failed: {}", e);
+                }
+            })
+        })?;
+        sched.add(job).await?;
+    }
+
+    // --- Predicted next funding (every 5 minutes)
+    {
+        let pool_clone = pool.clone();
+        let job = Job::new_async("0 */5 * * * *", move |_id, _| {
+            let pool = pool_clone.clone();
+            Box::pin(async move {
+                if let Err(e) = time_job("predicted_funding", collect_predicted_funding(&pool)).await {
+                    error!("Predicted funding collection failed: {}", e);
+                }
+            })
+        })?;
+        sched.add(job).await?;
+    }
+
+    // --- Funding event detection (hourly, after the top-of-hour funding runs)
+    {
+        let pool_clone = pool.clone();
+        let job = Job::new_async("0 5 * * * *", move |_id, _| {
             let pool = pool_clone.clone();
+            Box::pin(async move {
+                let cfg = EventConfig::from_env();
+                match time_job("funding_events", detect_funding_events(&pool, &cfg, 24)).await {
+                    Ok(n) => info!("Funding event detection: {} new event(s)", n),
+                    Err(e) => error!("Funding event detection failed: {}", e),
+                }
+            })
+        })?;
+        sched.add(job).await?;
+    }
+
+    add_collection_jobs(&sched, store, Some(pool)).await?;
+
+    sched.start().await?;
+    Ok(())
+}
+
+/// Scheduler for the SQLite / in-memory stores: collection only (partitions,
+/// retention, predicted funding, events and alerts need Postgres). Catches up
+/// once at startup since there is no `sync` run to seed the store.
+pub async fn start_embedded_scheduler(store: Arc<dyn FundingStore>) -> Result<()> {
+    let sched = JobScheduler::new().await?;
+
+    info!("Initial sync into the {} store...", store.kind());
+    if let Err(e) = time_job("market_discovery", refresh_all_markets(&*store)).await {
+        error!("Market discovery failed: {}", e);
+    }
+    for exch in store.active_exchanges().await? {
+        let job = format!("funding_{}", exch.name.to_ascii_lowercase());
+        if let Err(e) = time_job(&job, collect_funding_for_exchange(&*store, exch.id, &exch.name)).await {
+            error!("Funding collection {} failed: {}", exch.name, e);
+        }
+    }
+    if let Err(e) = time_job("market_stats", collect_daily_market_stats(&*store)).await {
+        error!("Collecting stats failed: {}", e);
+    }
+
+    add_collection_jobs(&sched, store, None).await?;
+    sched.start().await?;
+    Ok(())
+}
+
+/// Market discovery, stats and per-exchange funding jobs. With a pool, alert
+/// rules are evaluated after each stats / funding run.
+async fn add_collection_jobs(sched: &JobScheduler, store: Arc<dyn FundingStore>, pool: Option<PgPool>) -> Result<()> {
+    // --- Daily Market Discovery (midnight UTC)
+    {
+        let store = store.clone();
+        let job = Job::new_async("0 0 0 * * *", move |_id, _| {
+            let store = store.clone();
             Box::pin(async move {
                 info!("Running daily coin discovery...");
-                if let Err(e) = refresh_all_markets(&pool).await {
+                if let Err(e) = time_job("market_discovery", refresh_all_markets(&*store)).await {
                     error!("Market discovery failed: {}", e);
                 }
             })
@@ -31,35 +140,35 @@ pub async fn start_scheduler(pool: PgPool) -> Result<()> {
 
     // --- Daily Market Stats Snapshot (00:30 UTC)
     {
+        let store = store.clone();
         let pool_clone = pool.clone();
         let job = Job::new_async("0 30 0 * * *", move |_id, _| {
+            let store = store.clone();
             let pool = pool_clone.clone();
             Box::pin(async move {
                 info!("Running daily market stats snapshot...");
-                if let Err(e) = collect_daily_market_stats(&pool).await {
+                if let Err(e) = time_job("market_stats", collect_daily_market_stats(&*store)).await {
                     error!("Collecting stats failed: {}", e);
                 }
+                if let Some(pool) = pool {
+                    run_alerts(&pool).await;
+                }
             })
         })?;
         sched.add(job).await?;
     }
 
     // --- Funding Jobs per Exchange ---
-    let exchanges = sqlx::query!(
-        r#"
-        SELECT id, name, funding_interval_minutes
-        FROM exchanges
-        WHERE is_active = true AND funding_interval_minutes IS NOT NULL
-        "#
-    )
-    .fetch_all(&pool)
-    .await?;
+    let exchanges = store.active_exchanges().await?;
 
     for exch in exchanges {
+        let Some(funding_interval_minutes) = exch.funding_interval_minutes else {
+            continue;
+        };
         let cfg = ExchangeCfg {
             id: exch.id,
             name: exch.name.clone(),
-            funding_interval_minutes: exch.funding_interval_minutes.unwrap(),
+            funding_interval_minutes,
         };
 
         let cron_expr = minutes_to_cron(cfg.funding_interval_minutes)?;
@@ -68,28 +177,39 @@ pub async fn start_scheduler(pool: PgPool) -> Result<()> {
             cfg.name, cfg.funding_interval_minutes, cron_expr
         );
 
+        let store_clone = store.clone();
         let pool_clone = pool.clone();
         let exch_name = cfg.name.clone();
         let exch_id = cfg.id;
 
         let job = Job::new_async(&cron_expr, move |_id, _| {
+            let store = store_clone.clone();
             let pool = pool_clone.clone();
             let name = exch_name.clone();
             Box::pin(async move {
                 info!("Running funding collection for {}", name);
-                if let Err(e) = collect_funding_for_exchange(&pool, exch_id, &name).await {
+                let job = format!("funding_{}", name.to_ascii_lowercase());
+                if let Err(e) = time_job(&job, collect_funding_for_exchange(&*store, exch_id, &name)).await {
                     error!("Funding collection {} failed: {}", name, e);
                 }
+                if let Some(pool) = pool {
+                    run_alerts(&pool).await;
+                }
             })
         })?;
         sched.add(job).await?;
     }
 
-    sched.start().await?;
     Ok(())
 }
 
 
+/// Alert rules are evaluated after every ingestion job.
+async fn run_alerts(pool: &PgPool) {
+    if let Err(e) = time_job("alerts", evaluate_alerts(pool)).await {
+        error!("Alert evaluation failed: {}", e);
+    }
+}
 
 fn minutes_to_cron(minutes: i32) -> Result<String> {
     match minutes {
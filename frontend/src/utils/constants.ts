@@ -14,10 +14,6 @@ if (!REFRESH_INTERVAL || isNaN(REFRESH_INTERVAL)) {
   throw new Error('VITE_REFRESH_INTERVAL environment variable is required and must be a number')
 }
 
-// Constants for calculations
-export const FUNDING_PERIODS_PER_DAY = 3 // Most exchanges have 8-hour funding periods
-export const DAYS_PER_YEAR = 365
-
 // Thresholds
 export const MIN_SPREAD_THRESHOLD_BPS = 2
 export const SPREAD_THRESHOLDS = {
@@ -1,5 +1,4 @@
-import { FUNDING_PERIODS_PER_DAY, DAYS_PER_YEAR } from './constants'
-import type { DisplayMode, SpreadUnit } from './types'
+import type { DisplayMode, ExchangeData, SpreadUnit } from './types'
 
 export function formatNumber(value: number): string {
   if (value >= 1e9) return (value / 1e9).toFixed(2) + 'B'
@@ -12,14 +11,13 @@ export function formatTimestamp(ts: string): string {
   return new Date(ts).toLocaleString()
 }
 
-export function formatRate(rate: number, displayMode: DisplayMode): string {
+export function formatRate(data: ExchangeData, displayMode: DisplayMode): string {
   if (displayMode === 'annualized') {
-    // Convert 8-hour rate to annualized rate
-    const annualizedRate = rate * FUNDING_PERIODS_PER_DAY * DAYS_PER_YEAR * 100
-    return annualizedRate.toFixed(2) + '%'
+    // APR is computed by the API from the market's native funding interval
+    return (data.funding_apr * 100).toFixed(2) + '%'
   }
   // Regular 8-hour rate
-  return (rate * 100).toFixed(3) + '%'
+  return (data.funding_rate_8h * 100).toFixed(3) + '%'
 }
 
 export function formatSpread(spreadValue: number, spreadUnit: SpreadUnit): string {
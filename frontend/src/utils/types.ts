@@ -1,11 +1,24 @@
 export interface ExchangeData {
   funding_rate: number
+  funding_interval_minutes: number
+  funding_rate_hourly: number
+  funding_rate_8h: number
+  funding_rate_daily: number
+  funding_apr: number
   open_interest: number
+  predicted_funding_rate: number | null
+  predicted_funding_apr: number | null
+  next_funding_time: string | null
+  seconds_to_next_funding: number | null
+  mark_price: number | null
+  index_price: number | null
+  premium_bps: number | null
 }
 
 export interface TokenRow {
   token: string
   exchanges: Record<string, ExchangeData>
+  price_divergence_bps: number | null
 }
 
 export interface ApiResponse {